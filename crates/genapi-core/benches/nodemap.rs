@@ -0,0 +1,175 @@
+//! Construction and feature access latency on a large synthetic nodemap.
+//!
+//! Run with `cargo bench -p genapi-core --bench nodemap [-- <node count>]`.
+
+use std::fmt::Write as _;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use genapi_core::{MemoryRegisterIo, NodeMap};
+
+const DEFAULT_NODES: usize = 10_000;
+const ACCESS_ROUNDS: usize = 20;
+
+fn synthetic_xml(count: usize) -> String {
+    let mut xml = String::from(
+        r#"<RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+            <Enumeration Name="Selector">
+                <Address>0x1000</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <EnumEntry Name="A" Value="0" />
+                <EnumEntry Name="B" Value="1" />
+            </Enumeration>
+"#,
+    );
+    let mut category = String::from("<Category Name=\"Root\">");
+    for idx in 0..count {
+        let address = 0x10_0000 + idx as u64 * 8;
+        match idx % 4 {
+            0 | 1 => {
+                let _ = write!(
+                    xml,
+                    "<Integer Name=\"Int{idx}\"><Address>{address:#x}</Address><Length>4</Length>\
+                     <AccessMode>RW</AccessMode><Min>0</Min><Max>100000</Max></Integer>"
+                );
+            }
+            2 => {
+                let _ = write!(
+                    xml,
+                    "<Float Name=\"Float{idx}\"><Address>{address:#x}</Address><Length>4</Length>\
+                     <AccessMode>RW</AccessMode><Min>0</Min><Max>1000</Max><Scale>1/100</Scale></Float>"
+                );
+            }
+            _ => {
+                let _ = write!(
+                    xml,
+                    "<Integer Name=\"Sel{idx}\"><Length>4</Length><AccessMode>RW</AccessMode>\
+                     <Min>0</Min><Max>100000</Max><pSelected>Selector</pSelected>\
+                     <Selected>A</Selected><Address>{address:#x}</Address>\
+                     <Selected>B</Selected><Address>{:#x}</Address></Integer>",
+                    address + 4
+                );
+            }
+        }
+        let _ = write!(category, "<pFeature>{}</pFeature>", feature_name(idx));
+    }
+    category.push_str("</Category>");
+    xml.push_str(&category);
+    xml.push_str("</RegisterDescription>");
+    xml
+}
+
+fn feature_name(idx: usize) -> String {
+    match idx % 4 {
+        0 | 1 => format!("Int{idx}"),
+        2 => format!("Float{idx}"),
+        _ => format!("Sel{idx}"),
+    }
+}
+
+fn report(label: &str, elapsed: Duration, ops: usize) {
+    let per_op = elapsed.as_nanos() as f64 / ops.max(1) as f64;
+    println!("{label:<28} {elapsed:>12.3?} total {per_op:>10.1} ns/op ({ops} ops)");
+}
+
+fn main() {
+    let count = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(DEFAULT_NODES);
+    let xml = synthetic_xml(count);
+    println!(
+        "synthetic nodemap: {count} features, {} bytes of XML",
+        xml.len()
+    );
+
+    let started = Instant::now();
+    let model = genapi_xml::parse(&xml).expect("parse synthetic XML");
+    report("parse", started.elapsed(), 1);
+
+    let io = MemoryRegisterIo::from_model(&model).unwrap();
+    let started = Instant::now();
+    let mut nodemap = NodeMap::try_from_xml(model).expect("build nodemap");
+    report("try_from_xml", started.elapsed(), 1);
+
+    let ints: Vec<String> = (0..count)
+        .filter(|idx| idx % 4 < 2)
+        .map(feature_name)
+        .collect();
+    let ids: Vec<_> = ints
+        .iter()
+        .map(|name| nodemap.node_id(name).expect("known node"))
+        .collect();
+
+    let started = Instant::now();
+    for round in 0..ACCESS_ROUNDS {
+        for name in &ints {
+            nodemap
+                .set_integer(name, round as i64, &io)
+                .expect("set by name");
+        }
+    }
+    report(
+        "set_integer (name)",
+        started.elapsed(),
+        ACCESS_ROUNDS * ints.len(),
+    );
+
+    let started = Instant::now();
+    for round in 0..ACCESS_ROUNDS {
+        for id in &ids {
+            nodemap
+                .set_integer_by_id(*id, round as i64, &io)
+                .expect("set by id");
+        }
+    }
+    report(
+        "set_integer_by_id",
+        started.elapsed(),
+        ACCESS_ROUNDS * ids.len(),
+    );
+
+    let started = Instant::now();
+    for _ in 0..ACCESS_ROUNDS {
+        for name in &ints {
+            black_box(nodemap.get_integer(name, &io).expect("get by name"));
+        }
+    }
+    report(
+        "get_integer (name, cached)",
+        started.elapsed(),
+        ACCESS_ROUNDS * ints.len(),
+    );
+
+    let started = Instant::now();
+    for _ in 0..ACCESS_ROUNDS {
+        for id in &ids {
+            black_box(nodemap.get_integer_by_id(*id, &io).expect("get by id"));
+        }
+    }
+    report(
+        "get_integer_by_id (cached)",
+        started.elapsed(),
+        ACCESS_ROUNDS * ids.len(),
+    );
+
+    let selected: Vec<String> = (0..count)
+        .filter(|idx| idx % 4 == 3)
+        .map(feature_name)
+        .collect();
+    let started = Instant::now();
+    for entry in ["A", "B"] {
+        nodemap
+            .set_enum("Selector", entry, &io)
+            .expect("set selector");
+        for name in &selected {
+            black_box(nodemap.get_integer(name, &io).expect("get selected"));
+        }
+    }
+    report(
+        "selector switch + reads",
+        started.elapsed(),
+        2 * selected.len(),
+    );
+}
@@ -0,0 +1,68 @@
+//! Interpreted versus compiled SwissKnife evaluation.
+//!
+//! Run with `cargo bench -p genapi-core --bench swissknife --features bench-internals`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use genapi_core::__bench::{compile, evaluate, parse_expression, EvalError};
+
+const ITERATIONS: usize = 1_000_000;
+
+const EXPRESSIONS: &[(&str, &str)] = &[
+    (
+        "payload size",
+        "Width * Height * (BitsPerPixel / 8) + 0 * 64",
+    ),
+    (
+        "timestamp ns",
+        "(TickHigh * 4294967296 + TickLow) * (1000000000 / 125000000)",
+    ),
+    ("exposure us", "-(Offset - Raw) * (1 / 1000) + 2 * 3"),
+];
+
+const VARIABLES: &[(&str, f64)] = &[
+    ("Width", 2448.0),
+    ("Height", 2048.0),
+    ("BitsPerPixel", 16.0),
+    ("TickHigh", 3.0),
+    ("TickLow", 123_456.0),
+    ("Offset", 10.0),
+    ("Raw", 50_000.0),
+];
+
+fn main() {
+    let inputs: Vec<f64> = VARIABLES.iter().map(|(_, value)| *value).collect();
+    let slot = |name: &str| VARIABLES.iter().position(|(var, _)| *var == name);
+    for (label, expr) in EXPRESSIONS {
+        let ast = parse_expression(expr).expect("parse expression");
+        let program = compile(&ast, &slot).expect("compile expression");
+        let mut resolve = |name: &str| {
+            VARIABLES
+                .iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| *value)
+                .ok_or_else(|| EvalError::UnknownVariable(name.to_string()))
+        };
+
+        let started = Instant::now();
+        for _ in 0..ITERATIONS {
+            black_box(evaluate(black_box(&ast), &mut resolve).expect("interpret"));
+        }
+        let interpreted = started.elapsed();
+
+        let started = Instant::now();
+        for _ in 0..ITERATIONS {
+            black_box(program.run(black_box(&inputs)).expect("run"));
+        }
+        let compiled = started.elapsed();
+
+        let per_op = |elapsed: std::time::Duration| elapsed.as_nanos() as f64 / ITERATIONS as f64;
+        println!(
+            "{label:<14} interpreted {:>7.1} ns/op  compiled {:>7.1} ns/op  ({} ops)",
+            per_op(interpreted),
+            per_op(compiled),
+            program.ops().len()
+        );
+    }
+}
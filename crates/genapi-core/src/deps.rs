@@ -0,0 +1,275 @@
+//! Static dependency information extracted from node declarations.
+
+use std::collections::{HashMap, HashSet};
+
+use genapi_xml::{Addressing, EnumEntryDecl, EnumValueSrc, NodeDecl};
+
+use crate::Node;
+
+/// Relationship between a node and another node it references.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum DependencyKind {
+    /// The referenced node is a selector gating or addressing the node.
+    Selector,
+    /// The referenced node provides the register address (`<pAddress>`).
+    Address,
+    /// The referenced node provides an enum entry value (`<pValue>`).
+    Value,
+    /// The referenced node is bound to a SwissKnife variable.
+    Variable,
+    /// The referenced node is a feature listed by a category.
+    Category,
+}
+
+impl DependencyKind {
+    /// Short lowercase label used in exported graphs.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DependencyKind::Selector => "selector",
+            DependencyKind::Address => "address",
+            DependencyKind::Value => "value",
+            DependencyKind::Variable => "variable",
+            DependencyKind::Category => "category",
+        }
+    }
+}
+
+/// Outgoing reference of a node declaration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Reference<'a> {
+    pub kind: DependencyKind,
+    pub target: &'a str,
+}
+
+/// Name of the node declared by `decl`.
+pub(crate) fn decl_name(decl: &NodeDecl) -> &str {
+    match decl {
+        NodeDecl::Integer { name, .. }
+        | NodeDecl::Float { name, .. }
+        | NodeDecl::Enum { name, .. }
+        | NodeDecl::Boolean { name, .. }
+        | NodeDecl::Command { name, .. }
+        | NodeDecl::Category { name, .. } => name,
+        NodeDecl::SwissKnife(decl) => &decl.name,
+    }
+}
+
+/// Collect every node referenced by `decl`, deduplicated per kind.
+pub(crate) fn decl_references(decl: &NodeDecl) -> Vec<Reference<'_>> {
+    let mut refs = Vec::new();
+    match decl {
+        NodeDecl::Integer {
+            addressing,
+            selected_if,
+            ..
+        }
+        | NodeDecl::Float {
+            addressing,
+            selected_if,
+            ..
+        }
+        | NodeDecl::Boolean {
+            addressing,
+            selected_if,
+            ..
+        } => feature_references(&mut refs, addressing, selected_if, &[]),
+        NodeDecl::Enum {
+            addressing,
+            selected_if,
+            entries,
+            ..
+        } => feature_references(&mut refs, addressing, selected_if, entries),
+        NodeDecl::SwissKnife(sk) => {
+            for (_, provider) in &sk.variables {
+                push(&mut refs, DependencyKind::Variable, provider);
+            }
+        }
+        NodeDecl::Category { children, .. } => category_references(&mut refs, children),
+        NodeDecl::Command { .. } => {}
+    }
+    refs
+}
+
+/// Collect every node referenced by a runtime `node`, deduplicated per kind.
+pub(crate) fn node_references(node: &Node) -> Vec<Reference<'_>> {
+    let mut refs = Vec::new();
+    match node {
+        Node::Integer(node) => {
+            feature_references(&mut refs, &node.addressing, &node.selected_if, &[])
+        }
+        Node::Float(node) => {
+            feature_references(&mut refs, &node.addressing, &node.selected_if, &[])
+        }
+        Node::Boolean(node) => {
+            feature_references(&mut refs, &node.addressing, &node.selected_if, &[])
+        }
+        Node::Enum(node) => feature_references(
+            &mut refs,
+            &node.addressing,
+            &node.selected_if,
+            &node.entries,
+        ),
+        Node::SwissKnife(node) => {
+            for (_, provider) in &node.vars {
+                push(&mut refs, DependencyKind::Variable, provider);
+            }
+        }
+        Node::Category(node) => category_references(&mut refs, &node.children),
+        Node::Command(_) => {}
+    }
+    refs
+}
+
+fn feature_references<'a>(
+    refs: &mut Vec<Reference<'a>>,
+    addressing: &'a Addressing,
+    selected_if: &'a [(String, Vec<String>)],
+    entries: &'a [EnumEntryDecl],
+) {
+    match addressing {
+        Addressing::BySelector { selector, .. } => push(refs, DependencyKind::Selector, selector),
+        Addressing::Indirect { p_address_node, .. } => {
+            push(refs, DependencyKind::Address, p_address_node)
+        }
+        Addressing::Fixed { .. } => {}
+    }
+    for (selector, _) in selected_if {
+        push(refs, DependencyKind::Selector, selector);
+    }
+    for entry in entries {
+        if let EnumValueSrc::FromNode(provider) = &entry.value {
+            push(refs, DependencyKind::Value, provider);
+        }
+    }
+}
+
+/// Find cycles among the evaluation dependencies of `nodes`.
+///
+/// Category membership is ignored since it does not take part in value
+/// evaluation. Each cycle is returned as the list of node names along it.
+pub(crate) fn dependency_cycles(nodes: &[NodeDecl]) -> Vec<Vec<&str>> {
+    let index: HashMap<&str, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(idx, decl)| (decl_name(decl), idx))
+        .collect();
+    let adjacency: Vec<Vec<usize>> = nodes
+        .iter()
+        .map(|decl| {
+            decl_references(decl)
+                .into_iter()
+                .filter(|reference| reference.kind != DependencyKind::Category)
+                .filter_map(|reference| index.get(reference.target).copied())
+                .collect()
+        })
+        .collect();
+    find_cycles(&adjacency)
+        .into_iter()
+        .map(|cycle| {
+            cycle
+                .into_iter()
+                .map(|idx| decl_name(&nodes[idx]))
+                .collect()
+        })
+        .collect()
+}
+
+/// Find dependency cycles in a graph given as adjacency lists of node indices.
+///
+/// Each cycle is reported once as the list of nodes along the cycle, starting
+/// at the node first reached by the depth-first search.
+pub(crate) fn find_cycles(adjacency: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const WHITE: u8 = 0;
+    const GRAY: u8 = 1;
+    const BLACK: u8 = 2;
+
+    let mut state = vec![WHITE; adjacency.len()];
+    let mut cycles: Vec<Vec<usize>> = Vec::new();
+    for start in 0..adjacency.len() {
+        if state[start] != WHITE {
+            continue;
+        }
+        state[start] = GRAY;
+        let mut stack = vec![(start, 0usize)];
+        while let Some((node, edge)) = stack.last_mut() {
+            let node = *node;
+            if let Some(&next) = adjacency[node].get(*edge) {
+                *edge += 1;
+                match state[next] {
+                    WHITE => {
+                        state[next] = GRAY;
+                        stack.push((next, 0));
+                    }
+                    GRAY => {
+                        let pos = stack
+                            .iter()
+                            .position(|(candidate, _)| *candidate == next)
+                            .unwrap_or(0);
+                        let cycle: Vec<usize> = stack[pos..].iter().map(|(n, _)| *n).collect();
+                        if !cycles.iter().any(|known| same_cycle(known, &cycle)) {
+                            cycles.push(cycle);
+                        }
+                    }
+                    _ => {}
+                }
+            } else {
+                state[node] = BLACK;
+                stack.pop();
+            }
+        }
+    }
+    cycles
+}
+
+/// Categories of large vendor XMLs list thousands of features, so their
+/// children are deduplicated through a set instead of [`push`].
+fn category_references<'a>(refs: &mut Vec<Reference<'a>>, children: &'a [String]) {
+    let mut seen = HashSet::with_capacity(children.len());
+    for child in children {
+        if seen.insert(child.as_str()) {
+            refs.push(Reference {
+                kind: DependencyKind::Category,
+                target: child,
+            });
+        }
+    }
+}
+
+fn push<'a>(refs: &mut Vec<Reference<'a>>, kind: DependencyKind, target: &'a str) {
+    let reference = Reference { kind, target };
+    if !refs.contains(&reference) {
+        refs.push(reference);
+    }
+}
+
+fn same_cycle(a: &[usize], b: &[usize]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let Some(offset) = b.iter().position(|node| Some(node) == a.first()) else {
+        return false;
+    };
+    a.iter()
+        .enumerate()
+        .all(|(idx, node)| b[(idx + offset) % b.len()] == *node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_self_loops_and_longer_cycles() {
+        let adjacency = vec![vec![1], vec![2], vec![0], vec![3], vec![]];
+        let cycles = find_cycles(&adjacency);
+        assert_eq!(cycles, vec![vec![0, 1, 2], vec![3]]);
+    }
+
+    #[test]
+    fn acyclic_graph_has_no_cycles() {
+        let adjacency = vec![vec![1, 2], vec![2], vec![]];
+        assert!(find_cycles(&adjacency).is_empty());
+    }
+}
@@ -0,0 +1,477 @@
+//! Typed feature handles borrowing a [`NodeMap`] and its transport.
+//!
+//! Handles are obtained through [`NodeMap::integer_feature`] and friends,
+//! which check the node type once so later accesses cannot fail with
+//! [`GenApiError::Type`].
+
+use crate::{BooleanNode, EnumNode, FloatNode, GenApiError, IntegerNode, NodeMap, RegisterIo};
+
+/// Handle to an integer feature.
+pub struct IntegerFeature<'a> {
+    map: &'a mut NodeMap,
+    io: &'a dyn RegisterIo,
+    name: String,
+}
+
+/// Handle to a floating point feature.
+pub struct FloatFeature<'a> {
+    map: &'a mut NodeMap,
+    io: &'a dyn RegisterIo,
+    name: String,
+}
+
+/// Handle to an enumeration feature.
+pub struct EnumFeature<'a> {
+    map: &'a mut NodeMap,
+    io: &'a dyn RegisterIo,
+    name: String,
+}
+
+/// Handle to a boolean feature.
+pub struct BoolFeature<'a> {
+    map: &'a mut NodeMap,
+    io: &'a dyn RegisterIo,
+    name: String,
+}
+
+/// Handle to a command feature.
+pub struct CommandFeature<'a> {
+    map: &'a mut NodeMap,
+    io: &'a dyn RegisterIo,
+    name: String,
+}
+
+impl NodeMap {
+    /// Borrow an integer feature as a typed handle.
+    pub fn integer_feature<'a>(
+        &'a mut self,
+        name: &str,
+        io: &'a dyn RegisterIo,
+    ) -> Result<IntegerFeature<'a>, GenApiError> {
+        self.get_integer_node(name)?;
+        Ok(IntegerFeature {
+            map: self,
+            io,
+            name: name.to_string(),
+        })
+    }
+
+    /// Borrow a floating point feature as a typed handle.
+    pub fn float_feature<'a>(
+        &'a mut self,
+        name: &str,
+        io: &'a dyn RegisterIo,
+    ) -> Result<FloatFeature<'a>, GenApiError> {
+        self.get_float_node(name)?;
+        Ok(FloatFeature {
+            map: self,
+            io,
+            name: name.to_string(),
+        })
+    }
+
+    /// Borrow an enumeration feature as a typed handle.
+    pub fn enum_feature<'a>(
+        &'a mut self,
+        name: &str,
+        io: &'a dyn RegisterIo,
+    ) -> Result<EnumFeature<'a>, GenApiError> {
+        self.get_enum_node(name)?;
+        Ok(EnumFeature {
+            map: self,
+            io,
+            name: name.to_string(),
+        })
+    }
+
+    /// Borrow a boolean feature as a typed handle.
+    pub fn bool_feature<'a>(
+        &'a mut self,
+        name: &str,
+        io: &'a dyn RegisterIo,
+    ) -> Result<BoolFeature<'a>, GenApiError> {
+        self.get_bool_node(name)?;
+        Ok(BoolFeature {
+            map: self,
+            io,
+            name: name.to_string(),
+        })
+    }
+
+    /// Borrow a command feature as a typed handle.
+    pub fn command_feature<'a>(
+        &'a mut self,
+        name: &str,
+        io: &'a dyn RegisterIo,
+    ) -> Result<CommandFeature<'a>, GenApiError> {
+        self.get_command_node(name)?;
+        Ok(CommandFeature {
+            map: self,
+            io,
+            name: name.to_string(),
+        })
+    }
+}
+
+impl IntegerFeature<'_> {
+    /// Feature name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Read the current value.
+    pub fn get(&self) -> Result<i64, GenApiError> {
+        self.map.get_integer(&self.name, self.io)
+    }
+
+    /// Write `value`, failing with [`GenApiError::Range`] when it is invalid.
+    pub fn set(&mut self, value: i64) -> Result<(), GenApiError> {
+        self.map.set_integer(&self.name, value, self.io)
+    }
+
+    /// Minimum permitted value.
+    pub fn min(&self) -> i64 {
+        self.node().min
+    }
+
+    /// Maximum permitted value.
+    pub fn max(&self) -> i64 {
+        self.node().max
+    }
+
+    /// Increment valid values must respect, counted from [`min`](Self::min).
+    pub fn inc(&self) -> Option<i64> {
+        self.node().inc.filter(|inc| *inc > 0)
+    }
+
+    /// Engineering unit declared in the XML.
+    pub fn unit(&self) -> Option<&str> {
+        self.node().unit.as_deref()
+    }
+
+    /// Write the valid value closest to `value` and return it.
+    ///
+    /// The value is clamped to the limits and rounded to the nearest
+    /// increment; ties round up unless that would exceed the maximum.
+    pub fn set_nearest(&mut self, value: i64) -> Result<i64, GenApiError> {
+        let (min, max, inc) = (self.min(), self.max(), self.inc());
+        let mut nearest = value.clamp(min, max);
+        if let Some(inc) = inc {
+            // Offsets from `min` span up to 2^64, so step in i128.
+            let (min, max, inc) = (min as i128, max as i128, inc as i128);
+            let offset = nearest as i128 - min;
+            let down = min + offset / inc * inc;
+            let snapped = if offset % inc * 2 >= inc && down + inc <= max {
+                down + inc
+            } else {
+                down
+            };
+            nearest = snapped as i64;
+        }
+        self.set(nearest)?;
+        Ok(nearest)
+    }
+
+    fn node(&self) -> &IntegerNode {
+        self.map
+            .get_integer_node(&self.name)
+            .expect("node type checked on creation")
+    }
+}
+
+impl FloatFeature<'_> {
+    /// Feature name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Read the current value.
+    pub fn get(&self) -> Result<f64, GenApiError> {
+        self.map.get_float(&self.name, self.io)
+    }
+
+    /// Write `value`, failing with [`GenApiError::Range`] when out of range.
+    pub fn set(&mut self, value: f64) -> Result<(), GenApiError> {
+        self.map.set_float(&self.name, value, self.io)
+    }
+
+    /// Minimum permitted value.
+    pub fn min(&self) -> f64 {
+        self.node().min
+    }
+
+    /// Maximum permitted value.
+    pub fn max(&self) -> f64 {
+        self.node().max
+    }
+
+    /// Smallest representable step, derived from the register scale.
+    pub fn inc(&self) -> Option<f64> {
+        match self.node().scale {
+            Some((num, den)) if num != 0 && den != 0 => Some((num as f64 / den as f64).abs()),
+            _ => None,
+        }
+    }
+
+    /// Engineering unit declared in the XML.
+    pub fn unit(&self) -> Option<&str> {
+        self.node().unit.as_deref()
+    }
+
+    /// Write the representable value closest to `value` and return it.
+    ///
+    /// Fails with [`GenApiError::Range`] when no representable step lies
+    /// within the limits.
+    pub fn set_nearest(&mut self, value: f64) -> Result<f64, GenApiError> {
+        let (min, max) = (self.min(), self.max());
+        let mut nearest = value.clamp(min, max);
+        if let Some(inc) = self.inc() {
+            let origin = self.node().offset.unwrap_or(0.0);
+            let first = ((min - origin) / inc).ceil();
+            let last = ((max - origin) / inc).floor();
+            if first > last {
+                return Err(GenApiError::Range(self.name.clone()));
+            }
+            let step = ((nearest - origin) / inc).round().clamp(first, last);
+            // Guard against the product drifting past a limit by an ulp.
+            nearest = (origin + step * inc).clamp(min, max);
+        }
+        self.set(nearest)?;
+        Ok(nearest)
+    }
+
+    fn node(&self) -> &FloatNode {
+        self.map
+            .get_float_node(&self.name)
+            .expect("node type checked on creation")
+    }
+}
+
+impl EnumFeature<'_> {
+    /// Feature name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Read the current entry name.
+    pub fn get(&self) -> Result<String, GenApiError> {
+        self.map.get_enum(&self.name, self.io)
+    }
+
+    /// Select `entry`.
+    pub fn set(&mut self, entry: &str) -> Result<(), GenApiError> {
+        self.map.set_enum(&self.name, entry, self.io)
+    }
+
+    /// Names of all declared entries, sorted.
+    pub fn entries(&self) -> Vec<String> {
+        self.map.enum_entries(&self.name).unwrap_or_default()
+    }
+
+    /// Default entry declared in the XML, if any.
+    pub fn default_entry(&self) -> Option<&str> {
+        self.node().default.as_deref()
+    }
+
+    fn node(&self) -> &EnumNode {
+        self.map
+            .get_enum_node(&self.name)
+            .expect("node type checked on creation")
+    }
+}
+
+impl BoolFeature<'_> {
+    /// Feature name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Read the current value.
+    pub fn get(&self) -> Result<bool, GenApiError> {
+        self.map.get_bool(&self.name, self.io)
+    }
+
+    /// Write `value`.
+    pub fn set(&mut self, value: bool) -> Result<(), GenApiError> {
+        self.map.set_bool(&self.name, value, self.io)
+    }
+
+    /// Whether the feature may be written.
+    pub fn is_writable(&self) -> bool {
+        !matches!(self.node().access, crate::AccessMode::RO)
+    }
+
+    fn node(&self) -> &BooleanNode {
+        self.map
+            .get_bool_node(&self.name)
+            .expect("node type checked on creation")
+    }
+}
+
+impl CommandFeature<'_> {
+    /// Feature name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Execute the command.
+    pub fn execute(&mut self) -> Result<(), GenApiError> {
+        self.map.exec_command(&self.name, self.io)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryRegisterIo;
+
+    const FIXTURE: &str = r#"
+        <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+            <Integer Name="Width">
+                <Address>0x100</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>16</Min>
+                <Max>4000</Max>
+                <Inc>16</Inc>
+                <Unit>px</Unit>
+            </Integer>
+            <Integer Name="Offset">
+                <Address>0x600</Address>
+                <Length>8</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>-9223372036854775808</Min>
+                <Max>9223372036854775807</Max>
+                <Inc>4611686018427387904</Inc>
+            </Integer>
+            <Float Name="Gain">
+                <Address>0x700</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>0.1</Min>
+                <Max>0.9</Max>
+                <Scale>1/4</Scale>
+            </Float>
+            <Float Name="BlackLevel">
+                <Address>0x704</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>0.1</Min>
+                <Max>0.2</Max>
+                <Scale>1/4</Scale>
+            </Float>
+            <Float Name="ExposureTime">
+                <Address>0x200</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>10.0</Min>
+                <Max>1000.0</Max>
+                <Unit>us</Unit>
+                <Scale>1/2</Scale>
+            </Float>
+            <Enumeration Name="PixelFormat">
+                <Address>0x300</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <EnumEntry Name="Mono8" Value="1" />
+                <EnumEntry Name="Mono16" Value="2" />
+            </Enumeration>
+            <Boolean Name="ReverseX">
+                <Address>0x400</Address>
+                <Length>1</Length>
+                <AccessMode>RW</AccessMode>
+            </Boolean>
+            <Command Name="TriggerSoftware">
+                <Address>0x500</Address>
+                <Length>4</Length>
+            </Command>
+        </RegisterDescription>
+    "#;
+
+    fn setup() -> (NodeMap, MemoryRegisterIo) {
+        let model = genapi_xml::parse(FIXTURE).expect("parse fixture");
+        let io = MemoryRegisterIo::from_model(&model).unwrap();
+        (NodeMap::try_from_xml(model).expect("build nodemap"), io)
+    }
+
+    #[test]
+    fn integer_handle_exposes_limits_and_rounds() {
+        let (mut nodemap, io) = setup();
+        let mut width = nodemap.integer_feature("Width", &io).unwrap();
+        assert_eq!(
+            (width.min(), width.max(), width.inc()),
+            (16, 4000, Some(16))
+        );
+        assert_eq!(width.unit(), Some("px"));
+        assert!(matches!(width.set(100), Err(GenApiError::Range(_))));
+        assert_eq!(width.set_nearest(100).unwrap(), 96);
+        assert_eq!(width.set_nearest(104).unwrap(), 112);
+        assert_eq!(width.set_nearest(5000).unwrap(), 4000);
+        assert_eq!(width.set_nearest(3999).unwrap(), 4000);
+        assert_eq!(width.set_nearest(-3).unwrap(), 16);
+        assert_eq!(width.get().unwrap(), 16);
+    }
+
+    #[test]
+    fn integer_rounding_survives_i64_extremes() {
+        let (mut nodemap, io) = setup();
+        let mut offset = nodemap.integer_feature("Offset", &io).unwrap();
+        assert_eq!(offset.set_nearest(i64::MAX).unwrap(), 1 << 62);
+        assert_eq!(offset.set_nearest(i64::MIN).unwrap(), i64::MIN);
+        assert_eq!(offset.set_nearest(-(3 << 61)).unwrap(), -(1 << 62));
+        assert_eq!(offset.set_nearest(1).unwrap(), 0);
+        assert!(matches!(offset.set(1), Err(GenApiError::Range(_))));
+    }
+
+    #[test]
+    fn float_rounding_stays_within_unaligned_limits() {
+        let (mut nodemap, io) = setup();
+        let mut gain = nodemap.float_feature("Gain", &io).unwrap();
+        assert_eq!(gain.set_nearest(0.95).unwrap(), 0.75);
+        assert_eq!(gain.set_nearest(0.0).unwrap(), 0.25);
+        assert_eq!(gain.set_nearest(0.6).unwrap(), 0.5);
+        let mut black = nodemap.float_feature("BlackLevel", &io).unwrap();
+        assert!(matches!(
+            black.set_nearest(0.15),
+            Err(GenApiError::Range(_))
+        ));
+    }
+
+    #[test]
+    fn float_handle_snaps_to_scale() {
+        let (mut nodemap, io) = setup();
+        let mut exposure = nodemap.float_feature("ExposureTime", &io).unwrap();
+        assert_eq!(exposure.inc(), Some(0.5));
+        assert_eq!(exposure.unit(), Some("us"));
+        assert_eq!(exposure.set_nearest(100.3).unwrap(), 100.5);
+        assert_eq!(exposure.set_nearest(2000.0).unwrap(), 1000.0);
+        assert_eq!(exposure.get().unwrap(), 1000.0);
+    }
+
+    #[test]
+    fn other_handles_and_type_checks() {
+        let (mut nodemap, io) = setup();
+        assert!(matches!(
+            nodemap.integer_feature("ExposureTime", &io),
+            Err(GenApiError::Type(_))
+        ));
+        {
+            let mut format = nodemap.enum_feature("PixelFormat", &io).unwrap();
+            assert_eq!(format.entries(), vec!["Mono16", "Mono8"]);
+            format.set("Mono16").unwrap();
+            assert_eq!(format.get().unwrap(), "Mono16");
+        }
+        {
+            let mut reverse = nodemap.bool_feature("ReverseX", &io).unwrap();
+            reverse.set(true).unwrap();
+            assert!(reverse.get().unwrap());
+            assert!(reverse.is_writable());
+        }
+        nodemap
+            .command_feature("TriggerSoftware", &io)
+            .unwrap()
+            .execute()
+            .unwrap();
+        assert_eq!(io.peek(0x500, 4).unwrap(), vec![0, 0, 0, 1]);
+    }
+}
@@ -0,0 +1,351 @@
+//! Export of the nodemap dependency graph for debugging.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt::Write as _;
+
+use crate::deps::{node_references, DependencyKind};
+use crate::{Node, NodeId, NodeMap};
+
+/// Node entry of a [`DependencyGraph`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GraphNode {
+    /// Feature name.
+    pub name: String,
+    /// Node type (`Integer`, `Enumeration`, `SwissKnife`, ...).
+    pub kind: &'static str,
+}
+
+/// Directed edge stating that `from` references `to`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DependencyEdge {
+    /// Referencing node.
+    pub from: String,
+    /// Referenced node.
+    pub to: String,
+    /// Kind of reference.
+    pub kind: DependencyKind,
+}
+
+/// Snapshot of the relationships between nodes of a [`NodeMap`].
+///
+/// Nodes are sorted by name and edges by `(from, to)` so exports are stable.
+/// With the `serde` feature the graph serialises to a document with `nodes`
+/// and `edges` arrays.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DependencyGraph {
+    /// Nodes present in the graph.
+    pub nodes: Vec<GraphNode>,
+    /// References between nodes. Dangling references are omitted.
+    pub edges: Vec<DependencyEdge>,
+}
+
+impl NodeMap {
+    /// Build the dependency graph covering selector, address, value,
+    /// SwissKnife variable and category membership edges.
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        let mut ids: Vec<(NodeId, &str)> = self.names.iter().collect();
+        ids.sort_by_key(|(_, name)| *name);
+        let mut graph = DependencyGraph::default();
+        for (id, name) in ids {
+            let node = &self.nodes[id.index()];
+            graph.nodes.push(GraphNode {
+                name: name.to_string(),
+                kind: node_kind(node),
+            });
+            for reference in node_references(node) {
+                if self.names.get(reference.target).is_some() {
+                    graph.edges.push(DependencyEdge {
+                        from: name.to_string(),
+                        to: reference.target.to_string(),
+                        kind: reference.kind,
+                    });
+                }
+            }
+        }
+        graph
+            .edges
+            .sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+        graph
+    }
+}
+
+impl DependencyGraph {
+    /// Restrict the graph to `feature`, everything it transitively references
+    /// and everything transitively referencing it.
+    ///
+    /// Returns `None` when `feature` is not part of the graph.
+    pub fn neighbourhood(&self, feature: &str) -> Option<DependencyGraph> {
+        if !self.nodes.iter().any(|node| node.name == feature) {
+            return None;
+        }
+        let mut forward: HashMap<&str, Vec<&str>> = HashMap::new();
+        let mut backward: HashMap<&str, Vec<&str>> = HashMap::new();
+        for edge in &self.edges {
+            forward.entry(&edge.from).or_default().push(&edge.to);
+            backward.entry(&edge.to).or_default().push(&edge.from);
+        }
+        let mut keep = reachable(&forward, feature);
+        keep.extend(reachable(&backward, feature));
+
+        Some(DependencyGraph {
+            nodes: self
+                .nodes
+                .iter()
+                .filter(|node| keep.contains(node.name.as_str()))
+                .cloned()
+                .collect(),
+            edges: self
+                .edges
+                .iter()
+                .filter(|edge| keep.contains(edge.from.as_str()) && keep.contains(edge.to.as_str()))
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// Order node names so every node follows the nodes it references.
+    ///
+    /// Category membership is ignored. Ties are broken alphabetically so the
+    /// order is stable across runs.
+    pub fn topological_order(&self) -> Vec<String> {
+        let mut pending: BTreeMap<&str, usize> = self
+            .nodes
+            .iter()
+            .map(|node| (node.name.as_str(), 0))
+            .collect();
+        let mut users: HashMap<&str, Vec<&str>> = HashMap::new();
+        for edge in &self.edges {
+            if edge.kind == DependencyKind::Category {
+                continue;
+            }
+            *pending.entry(&edge.from).or_default() += 1;
+            users.entry(&edge.to).or_default().push(&edge.from);
+        }
+        let mut order = Vec::with_capacity(pending.len());
+        let mut ready: Vec<&str> = pending
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(name, _)| *name)
+            .rev()
+            .collect();
+        while let Some(name) = ready.pop() {
+            order.push(name.to_string());
+            for user in users.get(name).into_iter().flatten() {
+                let count = pending.get_mut(user).expect("user is a graph node");
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(user);
+                }
+            }
+        }
+        order
+    }
+
+    /// Render the graph in Graphviz DOT syntax.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph nodemap {\n    rankdir=LR;\n");
+        for node in &self.nodes {
+            let shape = match node.kind {
+                "Category" => "folder",
+                "SwissKnife" => "hexagon",
+                _ => "box",
+            };
+            let _ = writeln!(
+                out,
+                "    \"{}\" [shape={shape}, tooltip=\"{}\"];",
+                escape(&node.name),
+                node.kind
+            );
+        }
+        for edge in &self.edges {
+            let style = match edge.kind {
+                DependencyKind::Category => "dashed",
+                DependencyKind::Selector => "bold",
+                _ => "solid",
+            };
+            let _ = writeln!(
+                out,
+                "    \"{}\" -> \"{}\" [label=\"{}\", style={style}];",
+                escape(&edge.from),
+                escape(&edge.to),
+                edge.kind.as_str()
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn node_kind(node: &Node) -> &'static str {
+    match node {
+        Node::Integer(_) => "Integer",
+        Node::Float(_) => "Float",
+        Node::Enum(_) => "Enumeration",
+        Node::Boolean(_) => "Boolean",
+        Node::Command(_) => "Command",
+        Node::Category(_) => "Category",
+        Node::SwissKnife(_) => "SwissKnife",
+    }
+}
+
+fn reachable<'a>(adjacency: &HashMap<&'a str, Vec<&'a str>>, start: &'a str) -> BTreeSet<&'a str> {
+    let mut seen = BTreeSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(current) = queue.pop_front() {
+        for next in adjacency.get(current).into_iter().flatten() {
+            if seen.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    seen
+}
+
+/// Escape a DOT string literal; line breaks become DOT's `\n` escape.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            ch if ch.is_control() => out.push(' '),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = r#"
+        <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+            <Category Name="Root">
+                <pFeature>Gain</pFeature>
+                <pFeature>Width</pFeature>
+            </Category>
+            <Enumeration Name="GainSelector">
+                <Address>0x300</Address>
+                <Length>4</Length>
+                <EnumEntry Name="All" Value="0" />
+            </Enumeration>
+            <Integer Name="Gain">
+                <Address>0x310</Address>
+                <Length>4</Length>
+                <Min>0</Min>
+                <Max>48</Max>
+                <pSelected>GainSelector</pSelected>
+                <Selected>All</Selected>
+            </Integer>
+            <SwissKnife Name="GainDb">
+                <Expression>G * 2</Expression>
+                <pVariable Name="G">Gain</pVariable>
+            </SwissKnife>
+            <Integer Name="Width">
+                <Address>0x100</Address>
+                <Length>4</Length>
+                <Min>0</Min>
+                <Max>4096</Max>
+            </Integer>
+        </RegisterDescription>
+    "#;
+
+    fn build_graph() -> DependencyGraph {
+        let model = genapi_xml::parse(FIXTURE).expect("parse fixture");
+        NodeMap::try_from_xml(model)
+            .expect("build nodemap")
+            .dependency_graph()
+    }
+
+    #[test]
+    fn graph_contains_all_edge_kinds() {
+        let graph = build_graph();
+        assert_eq!(graph.nodes.len(), 5);
+        let edges: Vec<(&str, &str, DependencyKind)> = graph
+            .edges
+            .iter()
+            .map(|edge| (edge.from.as_str(), edge.to.as_str(), edge.kind))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("Gain", "GainSelector", DependencyKind::Selector),
+                ("GainDb", "Gain", DependencyKind::Variable),
+                ("Root", "Gain", DependencyKind::Category),
+                ("Root", "Width", DependencyKind::Category),
+            ]
+        );
+    }
+
+    #[test]
+    fn neighbourhood_follows_both_directions() {
+        let graph = build_graph();
+        let sub = graph.neighbourhood("Gain").expect("known feature");
+        let names: Vec<&str> = sub.nodes.iter().map(|node| node.name.as_str()).collect();
+        assert_eq!(names, vec!["Gain", "GainDb", "GainSelector", "Root"]);
+        assert!(sub.edges.iter().all(|edge| edge.to != "Width"));
+        assert!(graph.neighbourhood("Missing").is_none());
+    }
+
+    #[test]
+    fn topological_order_puts_dependencies_first() {
+        let order = build_graph().topological_order();
+        let pos = |name: &str| order.iter().position(|n| n == name).unwrap();
+        assert_eq!(order.len(), 5);
+        assert!(pos("GainSelector") < pos("Gain"));
+        assert!(pos("Gain") < pos("GainDb"));
+    }
+
+    #[test]
+    fn exports_dot() {
+        let sub = build_graph()
+            .neighbourhood("GainDb")
+            .expect("known feature");
+        let dot = sub.to_dot();
+        assert!(dot.starts_with("digraph nodemap {"));
+        assert!(dot.contains("\"GainDb\" -> \"Gain\" [label=\"variable\", style=solid];"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialises_to_json() {
+        let sub = build_graph()
+            .neighbourhood("GainDb")
+            .expect("known feature");
+        let json = serde_json::to_value(&sub).expect("serialise graph");
+        assert_eq!(json["nodes"][0]["name"], "Gain");
+        assert_eq!(json["nodes"][0]["kind"], "Integer");
+        assert!(json["edges"]
+            .as_array()
+            .expect("edges array")
+            .iter()
+            .any(|edge| edge["from"] == "Gain"
+                && edge["to"] == "GainSelector"
+                && edge["kind"] == "selector"));
+    }
+
+    #[test]
+    fn dot_export_escapes_control_characters() {
+        let name = "Odd \"name\"\\\n\r\t\u{1}";
+        let graph = DependencyGraph {
+            nodes: vec![GraphNode {
+                name: name.to_string(),
+                kind: "Integer",
+            }],
+            edges: vec![DependencyEdge {
+                from: name.to_string(),
+                to: "Gain".to_string(),
+                kind: DependencyKind::Value,
+            }],
+        };
+        assert!(graph
+            .to_dot()
+            .lines()
+            .all(|line| line.ends_with(['{', '}', ';'])));
+    }
+}
@@ -0,0 +1,80 @@
+//! Dense node identifiers and the interned node name table.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Index of a node within the [`NodeMap`](crate::NodeMap) that issued it.
+///
+/// Resolve a name once with [`NodeMap::node_id`](crate::NodeMap::node_id) and
+/// use the `*_by_id` accessors on hot paths to skip the name lookup. Ids are
+/// only meaningful for the nodemap they were obtained from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    /// Position of the node in the nodemap's dense storage.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Interned node names mapping each distinct name to one [`NodeId`].
+#[derive(Debug, Default)]
+pub(crate) struct NameTable {
+    names: Vec<Arc<str>>,
+    ids: HashMap<Arc<str>, NodeId>,
+}
+
+impl NameTable {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            names: Vec::with_capacity(capacity),
+            ids: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// Return the id of `name`, assigning the next free one if it is new.
+    pub(crate) fn intern(&mut self, name: &str) -> NodeId {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = NodeId(u32::try_from(self.names.len()).expect("more than u32::MAX nodes"));
+        let name: Arc<str> = Arc::from(name);
+        self.names.push(Arc::clone(&name));
+        self.ids.insert(name, id);
+        id
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<NodeId> {
+        self.ids.get(name).copied()
+    }
+
+    /// Interned name of `id`, for nodes to hold without copying it.
+    ///
+    /// Panics when `id` was not issued by this table.
+    pub(crate) fn shared(&self, id: NodeId) -> Arc<str> {
+        Arc::clone(&self.names[id.index()])
+    }
+
+    pub(crate) fn name(&self, id: NodeId) -> Option<&str> {
+        self.names.get(id.index()).map(|name| &**name)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (NodeId, &str)> + '_ {
+        self.names
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| (NodeId(idx as u32), &**name))
+    }
+}
@@ -3,21 +3,59 @@
 
 use std::cell::{Cell, RefCell};
 use std::collections::{hash_map::Entry as HashMapEntry, HashMap, HashSet};
+use std::sync::Arc;
 
-pub use genapi_xml::SkOutput;
-use genapi_xml::{
-    AccessMode, Addressing, BitField, EnumEntryDecl, EnumValueSrc, NodeDecl, XmlModel,
-};
+pub use genapi_xml::{AccessMode, DisplayHints, DisplayNotation, Representation, SkOutput};
+use genapi_xml::{Addressing, BitField, EnumEntryDecl, EnumValueSrc, NodeDecl, XmlModel};
 use thiserror::Error;
 use tracing::{debug, trace, warn};
 
 mod bitops;
 use crate::bitops::{extract, insert, BitOpsError};
+mod deps;
+pub use crate::deps::DependencyKind;
+mod features;
+pub use crate::features::{BoolFeature, CommandFeature, EnumFeature, FloatFeature, IntegerFeature};
+mod graph;
+mod id;
+pub use crate::graph::{DependencyEdge, DependencyGraph, GraphNode};
+use crate::id::NameTable;
+pub use crate::id::NodeId;
+mod lint;
+pub use crate::lint::{lint, LintFinding, LintKind, LintSeverity};
+mod memory;
+pub use crate::memory::{MemoryRegisterIo, WriteHook};
+mod planning;
+pub use crate::planning::{PlannedWrite, PlanningRegisterIo};
+mod policy;
+pub use crate::policy::{AuditHook, WritePolicy, WriteRecord};
+mod selected;
+pub use crate::selected::{FeatureValue, SelectorCombination};
+mod snapshot;
+pub use crate::snapshot::{
+    diff, RestoreMode, RestoreReport, Snapshot, SnapshotChange, SnapshotEntry,
+};
+mod transcript;
+pub use crate::transcript::{
+    RecordingRegisterIo, ReplayMode, ReplayRegisterIo, TranscriptEntry, TranscriptOp,
+    TranscriptResult,
+};
 mod swissknife;
 use crate::swissknife::{
-    collect_identifiers, evaluate as eval_ast, parse_expression, AstNode, EvalError as SkEvalError,
+    collect_identifiers, compile as compile_expression, parse_expression, AstNode,
+    EvalError as SkEvalError, Program,
 };
 
+/// Expression parser and evaluators, exposed only for the benchmarks.
+///
+/// Requires the `bench-internals` feature. Not part of the public API; may
+/// change without notice.
+#[cfg(feature = "bench-internals")]
+#[doc(hidden)]
+pub mod __bench {
+    pub use crate::swissknife::{compile, evaluate, parse_expression, EvalError};
+}
+
 /// Error type produced by GenApi operations.
 #[derive(Debug, Error)]
 pub enum GenApiError {
@@ -77,6 +115,19 @@ pub enum GenApiError {
         value: i64,
         bit_length: u16,
     },
+    /// Nodes depend on each other in a cycle and can never be evaluated.
+    #[error("dependency cycle detected: {}", .path.join(" -> "))]
+    DependencyCycle { path: Vec<String> },
+    /// The installed [`WritePolicy`] rejected the write.
+    #[error("write to {feature} rejected by policy: {reason}")]
+    PolicyViolation { feature: String, reason: String },
+    /// Write verification read back a value different from the one written.
+    #[error("write to {name} not applied: requested {requested}, device reports {actual}")]
+    WriteMismatch {
+        name: String,
+        requested: FeatureValue,
+        actual: FeatureValue,
+    },
 }
 
 /// Register access abstraction backed by transports such as GVCP/GenCP.
@@ -130,32 +181,37 @@ impl Node {
 }
 
 fn register_addressing_dependency(
-    dependents: &mut HashMap<String, Vec<String>>,
-    node_name: &str,
+    dependents: &mut [Vec<NodeId>],
+    names: &NameTable,
+    node: NodeId,
     addressing: &Addressing,
 ) {
     match addressing {
         Addressing::Fixed { .. } => {}
         Addressing::BySelector { selector, .. } => {
-            dependents
-                .entry(selector.clone())
-                .or_default()
-                .push(node_name.to_string());
+            add_dependent(dependents, names, selector, node);
         }
         Addressing::Indirect { p_address_node, .. } => {
-            dependents
-                .entry(p_address_node.clone())
-                .or_default()
-                .push(node_name.to_string());
+            add_dependent(dependents, names, p_address_node, node);
         }
     }
 }
 
+/// Record that `node` must be invalidated whenever `provider` changes.
+///
+/// References to nodes missing from the model are ignored; they can never be
+/// written and so never invalidate anything.
+fn add_dependent(dependents: &mut [Vec<NodeId>], names: &NameTable, provider: &str, node: NodeId) {
+    if let Some(provider) = names.get(provider) {
+        dependents[provider.index()].push(node);
+    }
+}
+
 /// Integer feature metadata extracted from the XML description.
 #[derive(Debug)]
 pub struct IntegerNode {
-    /// Unique feature name.
-    pub name: String,
+    /// Unique feature name, shared with the nodemap name table.
+    pub name: Arc<str>,
     /// Register addressing metadata (fixed, selector-based, or indirect).
     pub addressing: Addressing,
     /// Nominal register length in bytes.
@@ -170,6 +226,8 @@ pub struct IntegerNode {
     pub inc: Option<i64>,
     /// Optional engineering unit such as "us".
     pub unit: Option<String>,
+    /// Presentation hints such as hexadecimal or IPv4 representation.
+    pub display: DisplayHints,
     /// Optional bitfield metadata restricting active bits.
     pub bitfield: Option<BitField>,
     /// Selector nodes controlling the visibility of this node.
@@ -183,12 +241,14 @@ pub struct IntegerNode {
 /// Floating point feature metadata.
 #[derive(Debug)]
 pub struct FloatNode {
-    pub name: String,
+    pub name: Arc<str>,
     pub addressing: Addressing,
     pub access: AccessMode,
     pub min: f64,
     pub max: f64,
     pub unit: Option<String>,
+    /// Presentation hints controlling notation and precision.
+    pub display: DisplayHints,
     /// Optional rational scale `(numerator, denominator)` applied to the raw value.
     pub scale: Option<(i64, i64)>,
     /// Optional offset added after scaling.
@@ -201,7 +261,7 @@ pub struct FloatNode {
 /// Enumeration feature metadata and mapping tables.
 #[derive(Debug)]
 pub struct EnumNode {
-    pub name: String,
+    pub name: Arc<str>,
     pub addressing: Addressing,
     pub access: AccessMode,
     pub entries: Vec<EnumEntryDecl>,
@@ -222,7 +282,7 @@ struct EnumMapping {
 /// Boolean feature metadata.
 #[derive(Debug)]
 pub struct BooleanNode {
-    pub name: String,
+    pub name: Arc<str>,
     pub addressing: Addressing,
     pub len: u32,
     pub access: AccessMode,
@@ -239,14 +299,18 @@ pub struct BooleanNode {
 /// after the expression has been evaluated as `f64`.
 #[derive(Debug)]
 pub struct SkNode {
-    /// Unique feature name.
-    pub name: String,
+    /// Unique feature name, shared with the nodemap name table.
+    pub name: Arc<str>,
     /// Desired output type as declared in the XML.
     pub output: SkOutput,
     /// Parsed expression AST.
     pub ast: AstNode,
     /// Mapping of variable identifiers to provider node names.
     pub vars: Vec<(String, String)>,
+    /// Expression compiled with variables bound to `vars` slots.
+    pub program: Program,
+    /// Provider node of each `vars` entry, `None` when it is missing.
+    pub providers: Vec<Option<NodeId>>,
     /// Cached value alongside the generation it was computed in.
     pub cache: RefCell<Option<(f64, u64)>>,
 }
@@ -261,7 +325,7 @@ impl EnumNode {
 /// Command feature metadata.
 #[derive(Debug)]
 pub struct CommandNode {
-    pub name: String,
+    pub name: Arc<str>,
     pub address: u64,
     pub len: u32,
 }
@@ -269,7 +333,7 @@ pub struct CommandNode {
 /// Category node describing child feature names.
 #[derive(Debug)]
 pub struct CategoryNode {
-    pub name: String,
+    pub name: Arc<str>,
     pub children: Vec<String>,
 }
 
@@ -278,9 +342,12 @@ pub struct CategoryNode {
 #[derive(Debug)]
 pub struct NodeMap {
     version: String,
-    nodes: HashMap<String, Node>,
-    dependents: HashMap<String, Vec<String>>,
+    names: NameTable,
+    nodes: Vec<Node>,
+    dependents: Vec<Vec<NodeId>>,
     generation: Cell<u64>,
+    policy: Option<WritePolicy>,
+    verify_writes: bool,
 }
 
 impl NodeMap {
@@ -291,17 +358,58 @@ impl NodeMap {
 
     /// Fetch a node by name for inspection.
     pub fn node(&self, name: &str) -> Option<&Node> {
-        self.nodes.get(name)
+        self.lookup(name)
+    }
+
+    /// Iterate over the names of all nodes in unspecified order.
+    pub fn node_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.names.iter().map(|(_, name)| name)
+    }
+
+    /// Number of nodes in the map; valid ids are below this count.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Resolve a node name to its dense id.
+    pub fn node_id(&self, name: &str) -> Option<NodeId> {
+        self.names.get(name)
+    }
+
+    /// Name of the node identified by `id`.
+    pub fn node_name(&self, id: NodeId) -> Option<&str> {
+        self.names.name(id)
+    }
+
+    /// Fetch a node by id for inspection.
+    pub fn node_by_id(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id.index())
     }
 
     /// Construct a [`NodeMap`] from an [`XmlModel`], validating SwissKnife expressions.
+    ///
+    /// Models whose value, address or selector references form a cycle are
+    /// rejected with [`GenApiError::DependencyCycle`].
     pub fn try_from_xml(model: XmlModel) -> Result<Self, GenApiError> {
-        let mut nodes = HashMap::new();
-        let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
+        if let Some(cycle) = deps::dependency_cycles(&model.nodes).into_iter().next() {
+            let mut path: Vec<String> = cycle.iter().map(|name| name.to_string()).collect();
+            path.push(path[0].clone());
+            return Err(GenApiError::DependencyCycle { path });
+        }
+        let mut names = NameTable::with_capacity(model.nodes.len());
+        for decl in &model.nodes {
+            names.intern(deps::decl_name(decl));
+        }
+        let mut nodes: Vec<Option<Node>> = Vec::new();
+        nodes.resize_with(names.len(), || None);
+        let mut dependents: Vec<Vec<NodeId>> = vec![Vec::new(); names.len()];
         for decl in model.nodes {
+            let id = names
+                .get(deps::decl_name(&decl))
+                .expect("declaration names are interned");
             match decl {
                 NodeDecl::Integer {
-                    name,
+                    name: _,
                     addressing,
                     len,
                     access,
@@ -309,19 +417,17 @@ impl NodeMap {
                     max,
                     inc,
                     unit,
+                    display,
                     bitfield,
                     selectors,
                     selected_if,
                 } => {
-                    register_addressing_dependency(&mut dependents, &name, &addressing);
+                    register_addressing_dependency(&mut dependents, &names, id, &addressing);
                     for (selector, _) in &selected_if {
-                        dependents
-                            .entry(selector.clone())
-                            .or_default()
-                            .push(name.clone());
+                        add_dependent(&mut dependents, &names, selector, id);
                     }
                     let node = IntegerNode {
-                        name: name.clone(),
+                        name: names.shared(id),
                         addressing,
                         len,
                         access,
@@ -329,50 +435,50 @@ impl NodeMap {
                         max,
                         inc,
                         unit,
+                        display,
                         bitfield,
                         selectors,
                         selected_if,
                         cache: RefCell::new(None),
                         raw_cache: RefCell::new(None),
                     };
-                    nodes.insert(name, Node::Integer(node));
+                    nodes[id.index()] = Some(Node::Integer(node));
                 }
                 NodeDecl::Float {
-                    name,
+                    name: _,
                     addressing,
                     access,
                     min,
                     max,
                     unit,
+                    display,
                     scale,
                     offset,
                     selectors,
                     selected_if,
                 } => {
-                    register_addressing_dependency(&mut dependents, &name, &addressing);
+                    register_addressing_dependency(&mut dependents, &names, id, &addressing);
                     for (selector, _) in &selected_if {
-                        dependents
-                            .entry(selector.clone())
-                            .or_default()
-                            .push(name.clone());
+                        add_dependent(&mut dependents, &names, selector, id);
                     }
                     let node = FloatNode {
-                        name: name.clone(),
+                        name: names.shared(id),
                         addressing,
                         access,
                         min,
                         max,
                         unit,
+                        display,
                         scale,
                         offset,
                         selectors,
                         selected_if,
                         cache: RefCell::new(None),
                     };
-                    nodes.insert(name, Node::Float(node));
+                    nodes[id.index()] = Some(Node::Float(node));
                 }
                 NodeDecl::Enum {
-                    name,
+                    name: _,
                     addressing,
                     access,
                     entries,
@@ -380,21 +486,15 @@ impl NodeMap {
                     selectors,
                     selected_if,
                 } => {
-                    register_addressing_dependency(&mut dependents, &name, &addressing);
+                    register_addressing_dependency(&mut dependents, &names, id, &addressing);
                     for (selector, _) in &selected_if {
-                        dependents
-                            .entry(selector.clone())
-                            .or_default()
-                            .push(name.clone());
+                        add_dependent(&mut dependents, &names, selector, id);
                     }
                     let mut providers = Vec::new();
                     let mut provider_set = HashSet::new();
                     for entry in &entries {
                         if let EnumValueSrc::FromNode(node_name) = &entry.value {
-                            dependents
-                                .entry(node_name.clone())
-                                .or_default()
-                                .push(name.clone());
+                            add_dependent(&mut dependents, &names, node_name, id);
                             if provider_set.insert(node_name.clone()) {
                                 providers.push(node_name.clone());
                             }
@@ -402,7 +502,7 @@ impl NodeMap {
                     }
                     providers.sort();
                     let node = EnumNode {
-                        name: name.clone(),
+                        name: names.shared(id),
                         addressing,
                         access,
                         entries,
@@ -413,10 +513,10 @@ impl NodeMap {
                         value_cache: RefCell::new(None),
                         mapping_cache: RefCell::new(None),
                     };
-                    nodes.insert(name, Node::Enum(node));
+                    nodes[id.index()] = Some(Node::Enum(node));
                 }
                 NodeDecl::Boolean {
-                    name,
+                    name: _,
                     addressing,
                     len,
                     access,
@@ -424,15 +524,12 @@ impl NodeMap {
                     selectors,
                     selected_if,
                 } => {
-                    register_addressing_dependency(&mut dependents, &name, &addressing);
+                    register_addressing_dependency(&mut dependents, &names, id, &addressing);
                     for (selector, _) in &selected_if {
-                        dependents
-                            .entry(selector.clone())
-                            .or_default()
-                            .push(name.clone());
+                        add_dependent(&mut dependents, &names, selector, id);
                     }
                     let node = BooleanNode {
-                        name: name.clone(),
+                        name: names.shared(id),
                         addressing,
                         len,
                         access,
@@ -442,22 +539,22 @@ impl NodeMap {
                         cache: RefCell::new(None),
                         raw_cache: RefCell::new(None),
                     };
-                    nodes.insert(name, Node::Boolean(node));
+                    nodes[id.index()] = Some(Node::Boolean(node));
                 }
-                NodeDecl::Command { name, address, len } => {
+                NodeDecl::Command { address, len, .. } => {
                     let node = CommandNode {
-                        name: name.clone(),
+                        name: names.shared(id),
                         address,
                         len,
                     };
-                    nodes.insert(name, Node::Command(node));
+                    nodes[id.index()] = Some(Node::Command(node));
                 }
-                NodeDecl::Category { name, children } => {
+                NodeDecl::Category { children, .. } => {
                     let node = CategoryNode {
-                        name: name.clone(),
+                        name: names.shared(id),
                         children,
                     };
-                    nodes.insert(name, Node::Category(node));
+                    nodes[id.index()] = Some(Node::Category(node));
                 }
                 NodeDecl::SwissKnife(decl) => {
                     let name = decl.name;
@@ -479,51 +576,67 @@ impl NodeMap {
                         }
                     }
                     for (_, provider) in &variables {
-                        dependents
-                            .entry(provider.clone())
-                            .or_default()
-                            .push(name.clone());
+                        add_dependent(&mut dependents, &names, provider, id);
                     }
-                    let node = SkNode {
+                    let program = compile_expression(&ast, &|ident: &str| {
+                        variables.iter().position(|(var, _)| var == ident)
+                    })
+                    .map_err(|err| GenApiError::ExprParse {
                         name: name.clone(),
+                        msg: err.to_string(),
+                    })?;
+                    let providers = variables
+                        .iter()
+                        .map(|(_, provider)| names.get(provider))
+                        .collect();
+                    let node = SkNode {
+                        name: names.shared(id),
                         output,
                         ast,
                         vars: variables,
+                        program,
+                        providers,
                         cache: RefCell::new(None),
                     };
-                    nodes.insert(name, Node::SwissKnife(node));
+                    nodes[id.index()] = Some(Node::SwissKnife(node));
                 }
             }
         }
 
+        let nodes = nodes
+            .into_iter()
+            .map(|node| node.expect("every interned name has a declaration"))
+            .collect();
         Ok(NodeMap {
             version: model.version,
+            names,
             nodes,
             dependents,
             generation: Cell::new(0),
+            policy: None,
+            verify_writes: false,
         })
     }
 
     /// Read an integer feature value using the provided transport.
     pub fn get_integer(&self, name: &str, io: &dyn RegisterIo) -> Result<i64, GenApiError> {
-        if let Some(output) = self.nodes.get(name).and_then(|node| match node {
-            Node::SwissKnife(sk) => Some(sk.output),
-            _ => None,
-        }) {
-            return match output {
+        self.get_integer_by_id(self.require_id(name)?, io)
+    }
+
+    /// Read an integer feature identified by `id`.
+    pub fn get_integer_by_id(&self, id: NodeId, io: &dyn RegisterIo) -> Result<i64, GenApiError> {
+        let name = self.name_of(id)?;
+        if let Some(Node::SwissKnife(node)) = self.nodes.get(id.index()) {
+            return match node.output {
                 SkOutput::Integer => {
-                    let node = match self.nodes.get(name) {
-                        Some(Node::SwissKnife(node)) => node,
-                        _ => unreachable!("node vanished during lookup"),
-                    };
-                    let mut stack = HashSet::new();
-                    let value = self.evaluate_swissknife(node, io, &mut stack)?;
+                    let mut stack = Vec::new();
+                    let value = self.evaluate_swissknife(id, node, io, &mut stack)?;
                     round_to_i64(name, value)
                 }
                 SkOutput::Float => Err(GenApiError::Type(name.to_string())),
             };
         }
-        let node = self.get_integer_node(name)?;
+        let node = self.integer_node(id)?;
         ensure_readable(&node.access, name)?;
         self.ensure_selectors(name, &node.selected_if, io)?;
         let (address, len) = self.resolve_address(name, &node.addressing, io)?;
@@ -534,12 +647,7 @@ impl NodeMap {
             GenApiError::Io(_) => err,
             other => other,
         })?;
-        let value = if let Some(bitfield) = node.bitfield {
-            let extracted = extract(&raw, bitfield).map_err(|err| map_bitops_error(name, err))?;
-            interpret_bitfield_value(name, extracted, bitfield.bit_length, node.min < 0)?
-        } else {
-            bytes_to_i64(name, &raw)?
-        };
+        let value = decode_integer(name, node, &raw)?;
         debug!(node = %name, raw = value, "read integer feature");
         node.cache.replace(Some(value));
         node.raw_cache.replace(Some(raw));
@@ -553,15 +661,27 @@ impl NodeMap {
         value: i64,
         io: &dyn RegisterIo,
     ) -> Result<(), GenApiError> {
-        let node = self.get_integer_node(name)?;
+        self.set_integer_by_id(self.require_id(name)?, value, io)
+    }
+
+    /// Write an integer feature identified by `id`.
+    pub fn set_integer_by_id(
+        &mut self,
+        id: NodeId,
+        value: i64,
+        io: &dyn RegisterIo,
+    ) -> Result<(), GenApiError> {
+        let name = self.name_of(id)?;
+        let node = self.integer_node(id)?;
         ensure_writable(&node.access, name)?;
+        let record = self.enforce_policy(name, || Some(FeatureValue::Integer(value)))?;
         self.ensure_selectors(name, &node.selected_if, io)?;
         let (address, len) = self.resolve_address(name, &node.addressing, io)?;
         if value < node.min || value > node.max {
             return Err(GenApiError::Range(name.to_string()));
         }
         if let Some(inc) = node.inc {
-            if inc != 0 && (value - node.min) % inc != 0 {
+            if inc != 0 && (value as i128 - node.min as i128) % inc as i128 != 0 {
                 return Err(GenApiError::Range(name.to_string()));
             }
         }
@@ -601,30 +721,43 @@ impl NodeMap {
             node.cache.replace(Some(value));
             node.raw_cache.replace(Some(bytes));
         }
+        if self.should_verify(&node.access) {
+            let readback = self.read_back(address, len, io)?;
+            let actual = decode_integer(name, node, &readback)?;
+            if actual != value {
+                node.cache.replace(Some(actual));
+                node.raw_cache.replace(Some(readback));
+                return Err(self.write_mismatch(
+                    name,
+                    FeatureValue::Integer(value),
+                    FeatureValue::Integer(actual),
+                    record,
+                ));
+            }
+        }
         self.invalidate_dependents(name);
+        self.audit_write(record);
         Ok(())
     }
 
     /// Read a floating point feature.
     pub fn get_float(&self, name: &str, io: &dyn RegisterIo) -> Result<f64, GenApiError> {
-        if let Some(output) = self.nodes.get(name).and_then(|node| match node {
-            Node::SwissKnife(sk) => Some(sk.output),
-            _ => None,
-        }) {
-            return match output {
+        self.get_float_by_id(self.require_id(name)?, io)
+    }
+
+    /// Read a floating point feature identified by `id`.
+    pub fn get_float_by_id(&self, id: NodeId, io: &dyn RegisterIo) -> Result<f64, GenApiError> {
+        let name = self.name_of(id)?;
+        if let Some(Node::SwissKnife(node)) = self.nodes.get(id.index()) {
+            return match node.output {
                 SkOutput::Float => {
-                    let node = match self.nodes.get(name) {
-                        Some(Node::SwissKnife(node)) => node,
-                        _ => unreachable!("node vanished during lookup"),
-                    };
-                    let mut stack = HashSet::new();
-                    let value = self.evaluate_swissknife(node, io, &mut stack)?;
-                    Ok(value)
+                    let mut stack = Vec::new();
+                    self.evaluate_swissknife(id, node, io, &mut stack)
                 }
-                SkOutput::Integer => self.get_integer(name, io).map(|v| v as f64),
+                SkOutput::Integer => self.get_integer_by_id(id, io).map(|v| v as f64),
             };
         }
-        let node = self.get_float_node(name)?;
+        let node = self.float_node(id)?;
         ensure_readable(&node.access, name)?;
         self.ensure_selectors(name, &node.selected_if, io)?;
         let (address, len) = self.resolve_address(name, &node.addressing, io)?;
@@ -649,8 +782,20 @@ impl NodeMap {
         value: f64,
         io: &dyn RegisterIo,
     ) -> Result<(), GenApiError> {
-        let node = self.get_float_node(name)?;
+        self.set_float_by_id(self.require_id(name)?, value, io)
+    }
+
+    /// Write a floating point feature identified by `id`.
+    pub fn set_float_by_id(
+        &mut self,
+        id: NodeId,
+        value: f64,
+        io: &dyn RegisterIo,
+    ) -> Result<(), GenApiError> {
+        let name = self.name_of(id)?;
+        let node = self.float_node(id)?;
         ensure_writable(&node.access, name)?;
+        let record = self.enforce_policy(name, || Some(FeatureValue::Float(value)))?;
         self.ensure_selectors(name, &node.selected_if, io)?;
         let (address, len) = self.resolve_address(name, &node.addressing, io)?;
         if value < node.min || value > node.max {
@@ -664,13 +809,33 @@ impl NodeMap {
             other => other,
         })?;
         node.cache.replace(Some(value));
+        if self.should_verify(&node.access) {
+            let actual = bytes_to_i64(name, &self.read_back(address, len, io)?)?;
+            if actual != raw {
+                let actual = apply_scale(node, actual as f64);
+                node.cache.replace(Some(actual));
+                return Err(self.write_mismatch(
+                    name,
+                    FeatureValue::Float(value),
+                    FeatureValue::Float(actual),
+                    record,
+                ));
+            }
+        }
         self.invalidate_dependents(name);
+        self.audit_write(record);
         Ok(())
     }
 
     /// Read an enumeration feature returning the symbolic entry name.
     pub fn get_enum(&self, name: &str, io: &dyn RegisterIo) -> Result<String, GenApiError> {
-        let node = self.get_enum_node(name)?;
+        self.get_enum_by_id(self.require_id(name)?, io)
+    }
+
+    /// Read an enumeration feature identified by `id`.
+    pub fn get_enum_by_id(&self, id: NodeId, io: &dyn RegisterIo) -> Result<String, GenApiError> {
+        let name = self.name_of(id)?;
+        let node = self.enum_node(id)?;
         ensure_readable(&node.access, name)?;
         self.ensure_selectors(name, &node.selected_if, io)?;
         let (address, len) = self.resolve_address(name, &node.addressing, io)?;
@@ -695,8 +860,20 @@ impl NodeMap {
         entry: &str,
         io: &dyn RegisterIo,
     ) -> Result<(), GenApiError> {
-        let node = self.get_enum_node(name)?;
+        self.set_enum_by_id(self.require_id(name)?, entry, io)
+    }
+
+    /// Write an enumeration entry to the feature identified by `id`.
+    pub fn set_enum_by_id(
+        &mut self,
+        id: NodeId,
+        entry: &str,
+        io: &dyn RegisterIo,
+    ) -> Result<(), GenApiError> {
+        let name = self.name_of(id)?;
+        let node = self.enum_node(id)?;
         ensure_writable(&node.access, name)?;
+        let record = self.enforce_policy(name, || Some(FeatureValue::Enum(entry.to_string())))?;
         self.ensure_selectors(name, &node.selected_if, io)?;
         let (address, len) = self.resolve_address(name, &node.addressing, io)?;
         let entry_decl = node
@@ -715,7 +892,23 @@ impl NodeMap {
             other => other,
         })?;
         node.value_cache.replace(None);
+        if self.should_verify(&node.access) {
+            let actual = bytes_to_i64(name, &self.read_back(address, len, io)?)?;
+            if actual != raw {
+                let reported = self
+                    .lookup_enum_entry(node, actual, io)
+                    .map(FeatureValue::Enum)
+                    .unwrap_or(FeatureValue::Integer(actual));
+                return Err(self.write_mismatch(
+                    name,
+                    FeatureValue::Enum(entry.to_string()),
+                    reported,
+                    record,
+                ));
+            }
+        }
         self.invalidate_dependents(name);
+        self.audit_write(record);
         Ok(())
     }
 
@@ -740,7 +933,13 @@ impl NodeMap {
 
     /// Read a boolean feature.
     pub fn get_bool(&self, name: &str, io: &dyn RegisterIo) -> Result<bool, GenApiError> {
-        let node = self.get_bool_node(name)?;
+        self.get_bool_by_id(self.require_id(name)?, io)
+    }
+
+    /// Read a boolean feature identified by `id`.
+    pub fn get_bool_by_id(&self, id: NodeId, io: &dyn RegisterIo) -> Result<bool, GenApiError> {
+        let name = self.name_of(id)?;
+        let node = self.bool_node(id)?;
         ensure_readable(&node.access, name)?;
         self.ensure_selectors(name, &node.selected_if, io)?;
         let (address, len) = self.resolve_address(name, &node.addressing, io)?;
@@ -766,8 +965,20 @@ impl NodeMap {
         value: bool,
         io: &dyn RegisterIo,
     ) -> Result<(), GenApiError> {
-        let node = self.get_bool_node(name)?;
+        self.set_bool_by_id(self.require_id(name)?, value, io)
+    }
+
+    /// Write a boolean feature identified by `id`.
+    pub fn set_bool_by_id(
+        &mut self,
+        id: NodeId,
+        value: bool,
+        io: &dyn RegisterIo,
+    ) -> Result<(), GenApiError> {
+        let name = self.name_of(id)?;
+        let node = self.bool_node(id)?;
         ensure_writable(&node.access, name)?;
+        let record = self.enforce_policy(name, || Some(FeatureValue::Boolean(value)))?;
         self.ensure_selectors(name, &node.selected_if, io)?;
         let (address, len) = self.resolve_address(name, &node.addressing, io)?;
         let encoded = if value { 1 } else { 0 };
@@ -795,13 +1006,40 @@ impl NodeMap {
         })?;
         node.cache.replace(Some(value));
         node.raw_cache.replace(Some(raw));
+        if self.should_verify(&node.access) {
+            let readback = self.read_back(address, len, io)?;
+            let actual =
+                extract(&readback, node.bitfield).map_err(|err| map_bitops_error(name, err))? != 0;
+            if actual != value {
+                node.cache.replace(Some(actual));
+                node.raw_cache.replace(Some(readback));
+                return Err(self.write_mismatch(
+                    name,
+                    FeatureValue::Boolean(value),
+                    FeatureValue::Boolean(actual),
+                    record,
+                ));
+            }
+        }
         self.invalidate_dependents(name);
+        self.audit_write(record);
         Ok(())
     }
 
     /// Execute a command feature by writing a one-valued payload.
     pub fn exec_command(&mut self, name: &str, io: &dyn RegisterIo) -> Result<(), GenApiError> {
-        let node = self.get_command_node(name)?;
+        self.exec_command_by_id(self.require_id(name)?, io)
+    }
+
+    /// Execute the command feature identified by `id`.
+    pub fn exec_command_by_id(
+        &mut self,
+        id: NodeId,
+        io: &dyn RegisterIo,
+    ) -> Result<(), GenApiError> {
+        let name = self.name_of(id)?;
+        let node = self.command_node(id)?;
+        let record = self.enforce_policy(name, || None)?;
         if node.len == 0 {
             return Err(GenApiError::Parse(format!(
                 "command node {name} has zero length"
@@ -817,46 +1055,77 @@ impl NodeMap {
             other => other,
         })?;
         self.invalidate_dependents(name);
+        self.audit_write(record);
         Ok(())
     }
 
     fn get_integer_node(&self, name: &str) -> Result<&IntegerNode, GenApiError> {
-        match self.nodes.get(name) {
+        self.integer_node(self.require_id(name)?)
+    }
+
+    fn integer_node(&self, id: NodeId) -> Result<&IntegerNode, GenApiError> {
+        match self.nodes.get(id.index()) {
             Some(Node::Integer(node)) => Ok(node),
-            Some(_) => Err(GenApiError::Type(name.to_string())),
-            None => Err(GenApiError::NodeNotFound(name.to_string())),
+            Some(_) => Err(GenApiError::Type(
+                self.names.name(id).unwrap_or_default().to_string(),
+            )),
+            None => Err(GenApiError::NodeNotFound(id.to_string())),
         }
     }
 
     fn get_float_node(&self, name: &str) -> Result<&FloatNode, GenApiError> {
-        match self.nodes.get(name) {
+        self.float_node(self.require_id(name)?)
+    }
+
+    fn float_node(&self, id: NodeId) -> Result<&FloatNode, GenApiError> {
+        match self.nodes.get(id.index()) {
             Some(Node::Float(node)) => Ok(node),
-            Some(_) => Err(GenApiError::Type(name.to_string())),
-            None => Err(GenApiError::NodeNotFound(name.to_string())),
+            Some(_) => Err(GenApiError::Type(
+                self.names.name(id).unwrap_or_default().to_string(),
+            )),
+            None => Err(GenApiError::NodeNotFound(id.to_string())),
         }
     }
 
     fn get_enum_node(&self, name: &str) -> Result<&EnumNode, GenApiError> {
-        match self.nodes.get(name) {
+        self.enum_node(self.require_id(name)?)
+    }
+
+    fn enum_node(&self, id: NodeId) -> Result<&EnumNode, GenApiError> {
+        match self.nodes.get(id.index()) {
             Some(Node::Enum(node)) => Ok(node),
-            Some(_) => Err(GenApiError::Type(name.to_string())),
-            None => Err(GenApiError::NodeNotFound(name.to_string())),
+            Some(_) => Err(GenApiError::Type(
+                self.names.name(id).unwrap_or_default().to_string(),
+            )),
+            None => Err(GenApiError::NodeNotFound(id.to_string())),
         }
     }
 
     fn get_bool_node(&self, name: &str) -> Result<&BooleanNode, GenApiError> {
-        match self.nodes.get(name) {
+        self.bool_node(self.require_id(name)?)
+    }
+
+    fn bool_node(&self, id: NodeId) -> Result<&BooleanNode, GenApiError> {
+        match self.nodes.get(id.index()) {
             Some(Node::Boolean(node)) => Ok(node),
-            Some(_) => Err(GenApiError::Type(name.to_string())),
-            None => Err(GenApiError::NodeNotFound(name.to_string())),
+            Some(_) => Err(GenApiError::Type(
+                self.names.name(id).unwrap_or_default().to_string(),
+            )),
+            None => Err(GenApiError::NodeNotFound(id.to_string())),
         }
     }
 
     fn get_command_node(&self, name: &str) -> Result<&CommandNode, GenApiError> {
-        match self.nodes.get(name) {
+        self.command_node(self.require_id(name)?)
+    }
+
+    fn command_node(&self, id: NodeId) -> Result<&CommandNode, GenApiError> {
+        match self.nodes.get(id.index()) {
             Some(Node::Command(node)) => Ok(node),
-            Some(_) => Err(GenApiError::Type(name.to_string())),
-            None => Err(GenApiError::NodeNotFound(name.to_string())),
+            Some(_) => Err(GenApiError::Type(
+                self.names.name(id).unwrap_or_default().to_string(),
+            )),
+            None => Err(GenApiError::NodeNotFound(id.to_string())),
         }
     }
 
@@ -904,7 +1173,7 @@ impl NodeMap {
             }
         }
         Err(GenApiError::EnumValueUnknown {
-            node: node.name.clone(),
+            node: node.name.to_string(),
             value: raw_value,
         })
     }
@@ -1035,7 +1304,7 @@ impl NodeMap {
         selector: &str,
         io: &dyn RegisterIo,
     ) -> Result<String, GenApiError> {
-        match self.nodes.get(selector) {
+        match self.lookup(selector) {
             Some(Node::Enum(_)) => self.get_enum(selector, io),
             Some(Node::Boolean(_)) => Ok(self.get_bool(selector, io)?.to_string()),
             Some(Node::Integer(_)) => Ok(self.get_integer(selector, io)?.to_string()),
@@ -1048,48 +1317,42 @@ impl NodeMap {
 
     fn evaluate_swissknife(
         &self,
+        id: NodeId,
         node: &SkNode,
         io: &dyn RegisterIo,
-        stack: &mut HashSet<String>,
+        stack: &mut Vec<NodeId>,
     ) -> Result<f64, GenApiError> {
         if let Some((value, gen)) = *node.cache.borrow() {
             if gen == self.generation.get() {
                 return Ok(value);
             }
         }
-        if !stack.insert(node.name.clone()) {
-            stack.remove(&node.name);
+        if stack.contains(&id) {
             return Err(GenApiError::ExprEval {
-                name: node.name.clone(),
+                name: node.name.to_string(),
                 msg: "cyclic dependency".into(),
             });
         }
+        stack.push(id);
         let current_gen = self.generation.get();
         let result = (|| {
-            let mut values: HashMap<String, f64> = HashMap::new();
-            let mut inputs = Vec::new();
-            for (var, provider) in &node.vars {
-                let value = self.resolve_numeric(provider, io, stack)?;
-                values.insert(var.clone(), value);
-                inputs.push((var.clone(), value));
+            let mut inputs = Vec::with_capacity(node.providers.len());
+            for (provider, (_, provider_name)) in node.providers.iter().zip(&node.vars) {
+                let provider =
+                    provider.ok_or_else(|| GenApiError::NodeNotFound(provider_name.clone()))?;
+                inputs.push(self.resolve_numeric(provider, io, stack)?);
             }
-            let mut resolver = |ident: &str| -> Result<f64, SkEvalError> {
-                values
-                    .get(ident)
-                    .copied()
-                    .ok_or_else(|| SkEvalError::UnknownVariable(ident.to_string()))
-            };
-            let value = match eval_ast(&node.ast, &mut resolver) {
+            let value = match node.program.run(&inputs) {
                 Ok(value) => value,
                 Err(SkEvalError::UnknownVariable(var)) => {
                     return Err(GenApiError::UnknownVariable {
-                        name: node.name.clone(),
+                        name: node.name.to_string(),
                         var,
                     });
                 }
                 Err(SkEvalError::DivisionByZero) => {
                     return Err(GenApiError::ExprEval {
-                        name: node.name.clone(),
+                        name: node.name.to_string(),
                         msg: "division by zero".into(),
                     });
                 }
@@ -1097,7 +1360,7 @@ impl NodeMap {
             debug!(node = %node.name, inputs = ?inputs, output = value, "evaluate SwissKnife");
             Ok(value)
         })();
-        stack.remove(&node.name);
+        stack.pop();
         match result {
             Ok(value) => {
                 node.cache.replace(Some((value, current_gen)));
@@ -1109,22 +1372,25 @@ impl NodeMap {
 
     fn resolve_numeric(
         &self,
-        provider: &str,
+        provider: NodeId,
         io: &dyn RegisterIo,
-        stack: &mut HashSet<String>,
+        stack: &mut Vec<NodeId>,
     ) -> Result<f64, GenApiError> {
-        match self.nodes.get(provider) {
-            Some(Node::Integer(_)) => self.get_integer(provider, io).map(|v| v as f64),
-            Some(Node::Float(_)) => self.get_float(provider, io),
-            Some(Node::Boolean(_)) => Ok(if self.get_bool(provider, io)? {
+        match &self.nodes[provider.index()] {
+            Node::Integer(_) => self.get_integer_by_id(provider, io).map(|v| v as f64),
+            Node::Float(_) => self.get_float_by_id(provider, io),
+            Node::Boolean(_) => Ok(if self.get_bool_by_id(provider, io)? {
                 1.0
             } else {
                 0.0
             }),
-            Some(Node::Enum(_)) => self.get_enum_numeric(provider, io).map(|v| v as f64),
-            Some(Node::SwissKnife(node)) => self.evaluate_swissknife(node, io, stack),
-            Some(_) => Err(GenApiError::Type(provider.to_string())),
-            None => Err(GenApiError::NodeNotFound(provider.to_string())),
+            Node::Enum(_) => self
+                .get_enum_numeric(self.name_of(provider)?, io)
+                .map(|v| v as f64),
+            Node::SwissKnife(node) => self.evaluate_swissknife(provider, node, io, stack),
+            Node::Command(_) | Node::Category(_) => {
+                Err(GenApiError::Type(self.name_of(provider)?.to_string()))
+            }
         }
     }
 
@@ -1148,27 +1414,95 @@ impl NodeMap {
         })
     }
 
+    fn lookup(&self, name: &str) -> Option<&Node> {
+        self.names.get(name).map(|id| &self.nodes[id.index()])
+    }
+
+    fn require_id(&self, name: &str) -> Result<NodeId, GenApiError> {
+        self.names
+            .get(name)
+            .ok_or_else(|| GenApiError::NodeNotFound(name.to_string()))
+    }
+
+    fn name_of(&self, id: NodeId) -> Result<&str, GenApiError> {
+        self.names
+            .name(id)
+            .ok_or_else(|| GenApiError::NodeNotFound(id.to_string()))
+    }
+
     fn invalidate_dependents(&self, name: &str) {
         self.bump_generation();
-        if let Some(children) = self.dependents.get(name) {
+        if let Some(id) = self.names.get(name) {
             let mut visited = HashSet::new();
-            for child in children {
-                self.invalidate_recursive(child, &mut visited);
+            for child in &self.dependents[id.index()] {
+                self.invalidate_recursive(*child, &mut visited);
             }
         }
     }
 
-    fn invalidate_recursive(&self, name: &str, visited: &mut HashSet<String>) {
-        if !visited.insert(name.to_string()) {
+    fn invalidate_recursive(&self, id: NodeId, visited: &mut HashSet<NodeId>) {
+        if !visited.insert(id) {
             return;
         }
-        if let Some(node) = self.nodes.get(name) {
-            node.invalidate_cache();
+        self.nodes[id.index()].invalidate_cache();
+        for child in &self.dependents[id.index()] {
+            self.invalidate_recursive(*child, visited);
         }
-        if let Some(children) = self.dependents.get(name) {
-            for child in children {
-                self.invalidate_recursive(child, visited);
-            }
+    }
+
+    /// Enable or disable read-back verification of feature writes.
+    ///
+    /// When enabled, every integer, float, enumeration and boolean write is
+    /// followed by a read of the same register; a decoded value differing
+    /// from the one written fails with [`GenApiError::WriteMismatch`].
+    /// Write-only features and commands are never verified.
+    pub fn set_verify_writes(&mut self, enabled: bool) {
+        self.verify_writes = enabled;
+    }
+
+    /// Whether writes are verified by reading them back.
+    pub fn verify_writes(&self) -> bool {
+        self.verify_writes
+    }
+
+    fn should_verify(&self, access: &AccessMode) -> bool {
+        self.verify_writes && !matches!(access, AccessMode::WO)
+    }
+
+    fn read_back(
+        &self,
+        address: u64,
+        len: u32,
+        io: &dyn RegisterIo,
+    ) -> Result<Vec<u8>, GenApiError> {
+        io.read(address, len as usize)
+    }
+
+    /// Finish a write the device did not apply as requested.
+    ///
+    /// The caller has already cached the read-back value; the write still
+    /// reached the device, so dependents are invalidated and it is audited.
+    fn write_mismatch(
+        &self,
+        name: &str,
+        requested: FeatureValue,
+        actual: FeatureValue,
+        record: Option<WriteRecord>,
+    ) -> GenApiError {
+        warn!(node = %name, %requested, %actual, "device did not accept write");
+        self.invalidate_dependents(name);
+        self.audit_write(record);
+        GenApiError::WriteMismatch {
+            name: name.to_string(),
+            requested,
+            actual,
+        }
+    }
+
+    fn invalidate_all(&self) {
+        self.bump_generation();
+        for node in &self.nodes {
+            node.invalidate_cache();
         }
     }
 
@@ -1178,9 +1512,11 @@ impl NodeMap {
     }
 }
 
-impl From<XmlModel> for NodeMap {
-    fn from(model: XmlModel) -> Self {
-        NodeMap::try_from_xml(model).expect("invalid GenApi model")
+impl TryFrom<XmlModel> for NodeMap {
+    type Error = GenApiError;
+
+    fn try_from(model: XmlModel) -> Result<Self, Self::Error> {
+        NodeMap::try_from_xml(model)
     }
 }
 
@@ -1242,6 +1578,16 @@ fn ensure_writable(access: &AccessMode, name: &str) -> Result<(), GenApiError> {
     Ok(())
 }
 
+fn decode_integer(name: &str, node: &IntegerNode, raw: &[u8]) -> Result<i64, GenApiError> {
+    match node.bitfield {
+        Some(bitfield) => {
+            let extracted = extract(raw, bitfield).map_err(|err| map_bitops_error(name, err))?;
+            interpret_bitfield_value(name, extracted, bitfield.bit_length, node.min < 0)
+        }
+        None => bytes_to_i64(name, raw),
+    }
+}
+
 fn bytes_to_i64(name: &str, bytes: &[u8]) -> Result<i64, GenApiError> {
     if bytes.is_empty() {
         return Err(GenApiError::Parse(format!(
@@ -1411,7 +1757,7 @@ fn encode_float(node: &FloatNode, value: f64) -> Result<i64, GenApiError> {
     }
     let rounded = raw.round();
     if (raw - rounded).abs() > 1e-6 {
-        return Err(GenApiError::Range(node.name.clone()));
+        return Err(GenApiError::Range(node.name.to_string()));
     }
     let raw_i64 = rounded as i64;
     Ok(raw_i64)
@@ -1420,6 +1766,7 @@ fn encode_float(node: &FloatNode, value: f64) -> Result<i64, GenApiError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::rc::Rc;
 
     const FIXTURE: &str = r#"
         <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="2" SchemaSubMinorVersion="3">
@@ -1638,27 +1985,27 @@ mod tests {
 
     fn build_nodemap() -> NodeMap {
         let model = genapi_xml::parse(FIXTURE).expect("parse fixture");
-        NodeMap::from(model)
+        NodeMap::try_from_xml(model).expect("build nodemap")
     }
 
     fn build_indirect_nodemap() -> NodeMap {
         let model = genapi_xml::parse(INDIRECT_FIXTURE).expect("parse indirect fixture");
-        NodeMap::from(model)
+        NodeMap::try_from_xml(model).expect("build nodemap")
     }
 
     fn build_enum_pvalue_nodemap() -> NodeMap {
         let model = genapi_xml::parse(ENUM_PVALUE_FIXTURE).expect("parse enum pvalue fixture");
-        NodeMap::from(model)
+        NodeMap::try_from_xml(model).expect("build nodemap")
     }
 
     fn build_bitfield_nodemap() -> NodeMap {
         let model = genapi_xml::parse(BITFIELD_FIXTURE).expect("parse bitfield fixture");
-        NodeMap::from(model)
+        NodeMap::try_from_xml(model).expect("build nodemap")
     }
 
     fn build_swissknife_nodemap() -> NodeMap {
         let model = genapi_xml::parse(SWISSKNIFE_FIXTURE).expect("parse swissknife fixture");
-        NodeMap::from(model)
+        NodeMap::try_from_xml(model).expect("build nodemap")
     }
 
     #[test]
@@ -1681,6 +2028,31 @@ mod tests {
         assert_eq!(io.read_count(0x100), 1, "write should update cache");
     }
 
+    #[test]
+    fn id_accessors_match_name_accessors() {
+        let mut nodemap = build_nodemap();
+        let io = MockIo::with_registers(&[(0x100, vec![0, 0, 4, 0])]);
+        let width = nodemap.node_id("Width").expect("width id");
+        assert_eq!(nodemap.node_name(width), Some("Width"));
+        let Some(Node::Integer(node)) = nodemap.node_by_id(width) else {
+            panic!("Width is an integer node");
+        };
+        assert!(Arc::ptr_eq(&node.name, &nodemap.names.shared(width)));
+        assert_eq!(nodemap.get_integer_by_id(width, &io).unwrap(), 1024);
+
+        nodemap
+            .set_integer_by_id(width, 1030, &io)
+            .expect("write width by id");
+        assert_eq!(nodemap.get_integer("Width", &io).unwrap(), 1030);
+
+        let gain_selector = nodemap.node_id("GainSelector").unwrap();
+        assert!(matches!(
+            nodemap.get_integer_by_id(gain_selector, &io),
+            Err(GenApiError::Type(name)) if name == "GainSelector"
+        ));
+        assert!(nodemap.node_id("Missing").is_none());
+    }
+
     #[test]
     fn float_conversion_roundtrip() {
         let mut nodemap = build_nodemap();
@@ -2066,6 +2438,157 @@ mod tests {
         }
     }
 
+    #[test]
+    fn swissknife_cycle_is_rejected() {
+        const XML: &str = r#"
+            <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+                <SwissKnife Name="A">
+                    <Expression>B + 1</Expression>
+                    <pVariable Name="B">B</pVariable>
+                </SwissKnife>
+                <SwissKnife Name="B">
+                    <Expression>A * 2</Expression>
+                    <pVariable Name="A">A</pVariable>
+                </SwissKnife>
+            </RegisterDescription>
+        "#;
+
+        let model = genapi_xml::parse(XML).expect("parse cyclic swissknife");
+        let err = NodeMap::try_from_xml(model).expect_err("cycle");
+        match err {
+            GenApiError::DependencyCycle { path } => {
+                assert_eq!(path, vec!["A", "B", "A"]);
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn address_cycle_is_rejected() {
+        const XML: &str = r#"
+            <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+                <Integer Name="LoopA">
+                    <pAddress>LoopB</pAddress>
+                    <Length>4</Length>
+                    <Min>0</Min>
+                    <Max>100</Max>
+                </Integer>
+                <Integer Name="LoopB">
+                    <pAddress>LoopA</pAddress>
+                    <Length>4</Length>
+                    <Min>0</Min>
+                    <Max>100</Max>
+                </Integer>
+            </RegisterDescription>
+        "#;
+
+        let model = genapi_xml::parse(XML).expect("parse cyclic addresses");
+        match NodeMap::try_from_xml(model) {
+            Err(GenApiError::DependencyCycle { path }) => {
+                assert_eq!(path, vec!["LoopA", "LoopB", "LoopA"]);
+            }
+            other => panic!("unexpected result: {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn selector_cycle_is_rejected() {
+        const XML: &str = r#"
+            <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+                <Integer Name="Row">
+                    <Address>0x100</Address>
+                    <Length>4</Length>
+                    <Min>0</Min>
+                    <Max>7</Max>
+                    <pSelected>Column</pSelected>
+                </Integer>
+                <Integer Name="Column">
+                    <Address>0x104</Address>
+                    <Length>4</Length>
+                    <Min>0</Min>
+                    <Max>7</Max>
+                    <pSelected>Row</pSelected>
+                </Integer>
+            </RegisterDescription>
+        "#;
+
+        let model = genapi_xml::parse(XML).expect("parse cyclic selectors");
+        let err = NodeMap::try_from(model).expect_err("cycle");
+        assert!(
+            matches!(&err, GenApiError::DependencyCycle { path } if path.len() == 3),
+            "unexpected error: {err:?}"
+        );
+    }
+
+    #[test]
+    fn verified_write_reports_clamped_value() {
+        let model = genapi_xml::parse(FIXTURE).expect("parse fixture");
+        let mut io = crate::MemoryRegisterIo::from_model(&model).unwrap();
+        io.on_write(0x100, |io, addr, data| {
+            let requested = bytes_to_i64("Width", data).unwrap();
+            let clamped = i64_to_bytes("Width", requested.min(1024), 4).unwrap();
+            io.poke(addr, &clamped);
+        });
+        let mut nodemap = NodeMap::try_from_xml(model).expect("build nodemap");
+        let audited = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&audited);
+        nodemap.set_write_policy(
+            WritePolicy::allow_by_default()
+                .with_audit(move |record| sink.borrow_mut().push(record.value.clone())),
+        );
+
+        nodemap
+            .set_integer("Width", 2048, &io)
+            .expect("unverified write");
+        nodemap.set_verify_writes(true);
+        nodemap
+            .set_integer("Width", 640, &io)
+            .expect("accepted write");
+        let err = nodemap.set_integer("Width", 2048, &io).unwrap_err();
+        match err {
+            GenApiError::WriteMismatch {
+                name,
+                requested,
+                actual,
+            } => {
+                assert_eq!(name, "Width");
+                assert_eq!(requested, FeatureValue::Integer(2048));
+                assert_eq!(actual, FeatureValue::Integer(1024));
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+        io.poke(0x100, &[0, 0, 0, 0]);
+        assert_eq!(nodemap.get_integer("Width", &io).unwrap(), 1024);
+        assert_eq!(
+            *audited.borrow(),
+            vec![
+                Some(FeatureValue::Integer(2048)),
+                Some(FeatureValue::Integer(640)),
+                Some(FeatureValue::Integer(2048)),
+            ]
+        );
+    }
+
+    #[test]
+    fn verified_bitfield_write_ignores_neighbouring_bits() {
+        let model = genapi_xml::parse(BITFIELD_FIXTURE).expect("parse bitfield fixture");
+        let mut io = crate::MemoryRegisterIo::from_model(&model).unwrap();
+        io.on_write(0x5006, |io, addr, data| {
+            let mut stuck = data.to_vec();
+            stuck[0] ^= 0x01;
+            io.poke(addr, &stuck);
+        });
+        let mut nodemap = NodeMap::try_from_xml(model).expect("build nodemap");
+        nodemap.set_verify_writes(true);
+
+        nodemap
+            .set_bool("PackedFlag", true, &io)
+            .expect("flag accepted");
+        nodemap
+            .set_bool("PackedFlag", false, &io)
+            .expect("flag cleared");
+    }
+
     #[test]
     fn swissknife_division_by_zero() {
         let nodemap = build_swissknife_nodemap();
@@ -0,0 +1,434 @@
+//! Static consistency checks for vendor GenApi XML models.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use genapi_xml::{Addressing, EnumValueSrc, NodeDecl, XmlModel};
+
+use crate::deps::{decl_name, decl_references, dependency_cycles, DependencyKind};
+
+/// Name of the category every feature should be reachable from.
+const ROOT_CATEGORY: &str = "Root";
+
+/// Severity attached to a [`LintFinding`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintSeverity {
+    /// The model is likely usable but contains a questionable construct.
+    Warning,
+    /// The model contains a defect that breaks feature access at runtime.
+    Error,
+}
+
+impl fmt::Display for LintSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintSeverity::Warning => f.write_str("warning"),
+            LintSeverity::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// Category of problem reported by [`lint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintKind {
+    /// A `pValue`, `pAddress`, `pSelected` or `pFeature` names a missing node.
+    DanglingReference,
+    /// A SwissKnife variable is bound to a missing node.
+    MissingVariableNode,
+    /// Nodes depend on each other in a cycle.
+    Cycle,
+    /// Two entries of an enumeration share the same literal value.
+    DuplicateEnumValue,
+    /// Register blocks of different nodes overlap with conflicting extents.
+    RegisterOverlap,
+    /// A register block extends past the end of the 64-bit address space.
+    AddressOverflow,
+    /// The node cannot be reached from the `Root` category.
+    Unreachable,
+    /// The model declares categories but none named `Root`.
+    MissingRoot,
+}
+
+/// Single problem detected by [`lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    /// How serious the problem is.
+    pub severity: LintSeverity,
+    /// Problem category.
+    pub kind: LintKind,
+    /// Node the finding is attached to.
+    pub node: String,
+    /// Human readable description.
+    pub message: String,
+}
+
+impl fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.severity, self.node, self.message)
+    }
+}
+
+/// Run static checks over `model` and report every problem found.
+///
+/// Findings are ordered by severity (errors first) and then by node name.
+pub fn lint(model: &XmlModel) -> Vec<LintFinding> {
+    let mut findings = Vec::new();
+    let index: HashMap<&str, usize> = model
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(idx, decl)| (decl_name(decl), idx))
+        .collect();
+
+    check_references(model, &index, &mut findings);
+    check_cycles(model, &mut findings);
+    check_enum_values(model, &mut findings);
+    check_register_overlaps(model, &mut findings);
+    check_reachability(model, &index, &mut findings);
+
+    findings.sort_by(|a, b| b.severity.cmp(&a.severity).then(a.node.cmp(&b.node)));
+    findings
+}
+
+fn check_references(
+    model: &XmlModel,
+    index: &HashMap<&str, usize>,
+    findings: &mut Vec<LintFinding>,
+) {
+    for decl in &model.nodes {
+        let name = decl_name(decl);
+        if let NodeDecl::SwissKnife(sk) = decl {
+            for (var, provider) in &sk.variables {
+                if !index.contains_key(provider.as_str()) {
+                    findings.push(LintFinding {
+                        severity: LintSeverity::Error,
+                        kind: LintKind::MissingVariableNode,
+                        node: name.to_string(),
+                        message: format!("variable '{var}' is bound to missing node '{provider}'"),
+                    });
+                }
+            }
+            continue;
+        }
+        for reference in decl_references(decl) {
+            if index.contains_key(reference.target) {
+                continue;
+            }
+            let tag = match reference.kind {
+                DependencyKind::Selector => "pSelected",
+                DependencyKind::Address => "pAddress",
+                DependencyKind::Value => "pValue",
+                DependencyKind::Category => "pFeature",
+                DependencyKind::Variable => "pVariable",
+            };
+            findings.push(LintFinding {
+                severity: LintSeverity::Error,
+                kind: LintKind::DanglingReference,
+                node: name.to_string(),
+                message: format!("<{tag}> refers to missing node '{}'", reference.target),
+            });
+        }
+    }
+}
+
+fn check_cycles(model: &XmlModel, findings: &mut Vec<LintFinding>) {
+    for mut path in dependency_cycles(&model.nodes) {
+        let node = path[0].to_string();
+        path.push(path[0]);
+        findings.push(LintFinding {
+            severity: LintSeverity::Error,
+            kind: LintKind::Cycle,
+            node,
+            message: format!("dependency cycle {}", path.join(" -> ")),
+        });
+    }
+}
+
+fn check_enum_values(model: &XmlModel, findings: &mut Vec<LintFinding>) {
+    for decl in &model.nodes {
+        let NodeDecl::Enum { name, entries, .. } = decl else {
+            continue;
+        };
+        let mut seen: HashMap<i64, &str> = HashMap::new();
+        for entry in entries {
+            let EnumValueSrc::Literal(value) = entry.value else {
+                continue;
+            };
+            if let Some(kept) = seen.get(&value) {
+                findings.push(LintFinding {
+                    severity: LintSeverity::Warning,
+                    kind: LintKind::DuplicateEnumValue,
+                    node: name.clone(),
+                    message: format!("entries '{kept}' and '{}' share value {value}", entry.name),
+                });
+            } else {
+                seen.insert(value, &entry.name);
+            }
+        }
+    }
+}
+
+fn check_register_overlaps(model: &XmlModel, findings: &mut Vec<LintFinding>) {
+    let mut blocks: Vec<(u64, u64, &str)> = Vec::new();
+    for decl in &model.nodes {
+        let name = decl_name(decl);
+        match decl {
+            NodeDecl::Integer { addressing, .. }
+            | NodeDecl::Float { addressing, .. }
+            | NodeDecl::Enum { addressing, .. }
+            | NodeDecl::Boolean { addressing, .. } => match addressing {
+                Addressing::Fixed { address, len } => blocks.push((*address, *len as u64, name)),
+                Addressing::BySelector { map, .. } => {
+                    for (_, (address, len)) in map {
+                        blocks.push((*address, *len as u64, name));
+                    }
+                }
+                Addressing::Indirect { .. } => {}
+            },
+            NodeDecl::Command { address, len, .. } => blocks.push((*address, *len as u64, name)),
+            NodeDecl::Category { .. } | NodeDecl::SwissKnife(_) => {}
+        }
+    }
+    blocks.sort();
+    blocks.dedup();
+
+    let mut reported = HashSet::new();
+    for (idx, (address, len, name)) in blocks.iter().enumerate() {
+        let Some(end) = address.checked_add(*len) else {
+            findings.push(LintFinding {
+                severity: LintSeverity::Error,
+                kind: LintKind::AddressOverflow,
+                node: name.to_string(),
+                message: format!("register 0x{address:X}+{len} exceeds the address space"),
+            });
+            continue;
+        };
+        for (other_addr, other_len, other) in blocks[idx + 1..].iter() {
+            if *other_addr >= end {
+                break;
+            }
+            if name == other || (address, len) == (other_addr, other_len) {
+                continue;
+            }
+            if !reported.insert((*name, *other)) {
+                continue;
+            }
+            findings.push(LintFinding {
+                severity: LintSeverity::Warning,
+                kind: LintKind::RegisterOverlap,
+                node: name.to_string(),
+                message: format!(
+                    "register 0x{address:X}+{len} overlaps '{other}' at 0x{other_addr:X}+{other_len}"
+                ),
+            });
+        }
+    }
+}
+
+fn check_reachability(
+    model: &XmlModel,
+    index: &HashMap<&str, usize>,
+    findings: &mut Vec<LintFinding>,
+) {
+    let Some(&root) = index.get(ROOT_CATEGORY) else {
+        if model
+            .nodes
+            .iter()
+            .any(|decl| matches!(decl, NodeDecl::Category { .. }))
+        {
+            findings.push(LintFinding {
+                severity: LintSeverity::Warning,
+                kind: LintKind::MissingRoot,
+                node: ROOT_CATEGORY.to_string(),
+                message: "model declares categories but no Root category".into(),
+            });
+        }
+        return;
+    };
+
+    let mut visited = vec![false; model.nodes.len()];
+    let mut queue = VecDeque::from([root]);
+    visited[root] = true;
+    while let Some(current) = queue.pop_front() {
+        for reference in decl_references(&model.nodes[current]) {
+            if let Some(&next) = index.get(reference.target) {
+                if !visited[next] {
+                    visited[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    for (decl, reached) in model.nodes.iter().zip(visited) {
+        if !reached {
+            findings.push(LintFinding {
+                severity: LintSeverity::Warning,
+                kind: LintKind::Unreachable,
+                node: decl_name(decl).to_string(),
+                message: "not reachable from the Root category".into(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLEAN: &str = r#"
+        <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+            <Category Name="Root">
+                <pFeature>Gain</pFeature>
+                <pFeature>Computed</pFeature>
+            </Category>
+            <Enumeration Name="GainSelector">
+                <Address>0x300</Address>
+                <Length>4</Length>
+                <EnumEntry Name="All" Value="0" />
+                <EnumEntry Name="Red" Value="1" />
+            </Enumeration>
+            <Integer Name="Gain">
+                <Length>4</Length>
+                <Min>0</Min>
+                <Max>48</Max>
+                <pSelected>GainSelector</pSelected>
+                <Selected>All</Selected>
+                <Address>0x310</Address>
+                <Selected>Red</Selected>
+                <Address>0x314</Address>
+            </Integer>
+            <SwissKnife Name="Computed">
+                <Expression>G * 2</Expression>
+                <pVariable Name="G">Gain</pVariable>
+            </SwissKnife>
+        </RegisterDescription>
+    "#;
+
+    const BROKEN: &str = r#"
+        <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+            <Category Name="Root">
+                <pFeature>Width</pFeature>
+                <pFeature>Mode</pFeature>
+                <pFeature>Missing</pFeature>
+                <pFeature>LoopA</pFeature>
+            </Category>
+            <Integer Name="Width">
+                <Address>0x100</Address>
+                <Length>4</Length>
+                <Min>0</Min>
+                <Max>100</Max>
+                <pSelected>NoSuchSelector</pSelected>
+            </Integer>
+            <Integer Name="Height">
+                <Address>0x102</Address>
+                <Length>4</Length>
+                <Min>0</Min>
+                <Max>100</Max>
+            </Integer>
+            <Enumeration Name="Mode">
+                <Address>0x200</Address>
+                <Length>4</Length>
+                <EnumEntry Name="A" Value="1" />
+                <EnumEntry Name="B" Value="1" />
+                <EnumEntry Name="C">
+                    <pValue>Ghost</pValue>
+                </EnumEntry>
+            </Enumeration>
+            <Integer Name="LoopA">
+                <pAddress>LoopB</pAddress>
+                <Length>4</Length>
+                <Min>0</Min>
+                <Max>100</Max>
+            </Integer>
+            <Integer Name="LoopB">
+                <pAddress>LoopA</pAddress>
+                <Length>4</Length>
+                <Min>0</Min>
+                <Max>100</Max>
+            </Integer>
+            <SwissKnife Name="Sk">
+                <Expression>X + 1</Expression>
+                <pVariable Name="X">Nowhere</pVariable>
+            </SwissKnife>
+        </RegisterDescription>
+    "#;
+
+    fn kinds(findings: &[LintFinding], node: &str) -> Vec<LintKind> {
+        findings
+            .iter()
+            .filter(|finding| finding.node == node)
+            .map(|finding| finding.kind)
+            .collect()
+    }
+
+    #[test]
+    fn clean_model_has_no_findings() {
+        let model = genapi_xml::parse(CLEAN).expect("parse clean model");
+        assert!(lint(&model).is_empty());
+    }
+
+    #[test]
+    fn broken_model_reports_every_issue() {
+        let model = genapi_xml::parse(BROKEN).expect("parse broken model");
+        let findings = lint(&model);
+        assert_eq!(findings[0].severity, LintSeverity::Error);
+
+        assert_eq!(kinds(&findings, "Root"), vec![LintKind::DanglingReference]);
+        assert_eq!(
+            kinds(&findings, "Width"),
+            vec![LintKind::DanglingReference, LintKind::RegisterOverlap]
+        );
+        assert_eq!(kinds(&findings, "Height"), vec![LintKind::Unreachable]);
+        assert_eq!(
+            kinds(&findings, "Mode"),
+            vec![LintKind::DanglingReference, LintKind::DuplicateEnumValue]
+        );
+        assert_eq!(
+            kinds(&findings, "Sk"),
+            vec![LintKind::MissingVariableNode, LintKind::Unreachable]
+        );
+        let cycle = findings
+            .iter()
+            .find(|finding| finding.kind == LintKind::Cycle)
+            .expect("cycle finding");
+        assert_eq!(cycle.message, "dependency cycle LoopA -> LoopB -> LoopA");
+    }
+
+    #[test]
+    fn register_past_address_space_is_reported() {
+        let xml = r#"
+            <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+                <Integer Name="Edge">
+                    <Address>0xFFFFFFFFFFFFFFFE</Address>
+                    <Length>4</Length>
+                    <Min>0</Min>
+                    <Max>100</Max>
+                </Integer>
+                <Integer Name="Below">
+                    <Address>0xFFFFFFFFFFFFFFFC</Address>
+                    <Length>2</Length>
+                    <Min>0</Min>
+                    <Max>100</Max>
+                </Integer>
+            </RegisterDescription>
+        "#;
+        let model = genapi_xml::parse(xml).expect("parse model");
+        let findings = lint(&model);
+        assert_eq!(kinds(&findings, "Edge"), vec![LintKind::AddressOverflow]);
+        assert_eq!(findings[0].severity, LintSeverity::Error);
+        assert!(kinds(&findings, "Below").is_empty());
+    }
+
+    #[test]
+    fn missing_root_is_reported() {
+        let xml = r#"
+            <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+                <Category Name="Main" />
+            </RegisterDescription>
+        "#;
+        let model = genapi_xml::parse(xml).expect("parse model");
+        let findings = lint(&model);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, LintKind::MissingRoot);
+    }
+}
@@ -0,0 +1,506 @@
+//! Simulated register space backed by host memory.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use genapi_xml::{AccessMode, Addressing, EnumValueSrc, NodeDecl, XmlModel};
+use tracing::{debug, trace, warn};
+
+use crate::bitops::insert;
+use crate::{encode_bitfield_value, i64_to_bytes, map_bitops_error, GenApiError, RegisterIo};
+
+/// Side effect invoked after a successful write to a hooked address.
+///
+/// The hook receives the register space (so it may update other registers via
+/// [`MemoryRegisterIo::poke`]), the written address and the written payload.
+pub type WriteHook = Box<dyn Fn(&MemoryRegisterIo, u64, &[u8])>;
+
+#[derive(Debug, Clone)]
+struct Block {
+    data: Vec<u8>,
+    access: AccessMode,
+}
+
+/// In-memory [`RegisterIo`] implementation allocated from an [`XmlModel`].
+///
+/// Every fixed and selector-dependent register declared by the model is
+/// allocated up front and seeded with the node's declared default (enums) or
+/// minimum (integers and floats). Reads from write-only and writes to
+/// read-only blocks are rejected with [`GenApiError::Access`], mirroring the
+/// behaviour of a real device. Registers reached through `<pAddress>` cannot
+/// be known in advance; seed them with [`MemoryRegisterIo::poke`].
+pub struct MemoryRegisterIo {
+    blocks: RefCell<BTreeMap<u64, Block>>,
+    hooks: HashMap<u64, Vec<WriteHook>>,
+    commands: Vec<(u64, u32)>,
+}
+
+impl MemoryRegisterIo {
+    /// Create an empty register space without any allocated blocks.
+    pub fn new() -> Self {
+        Self {
+            blocks: RefCell::new(BTreeMap::new()),
+            hooks: HashMap::new(),
+            commands: Vec::new(),
+        }
+    }
+
+    /// Allocate and seed the register space declared by `model`.
+    ///
+    /// Fails when a declared register extends past the end of the 64-bit
+    /// address space.
+    pub fn from_model(model: &XmlModel) -> Result<Self, GenApiError> {
+        let mut io = Self::new();
+        for decl in &model.nodes {
+            io.allocate_decl(decl)?;
+        }
+        for decl in &model.nodes {
+            io.seed_decl(decl);
+        }
+        debug!(
+            blocks = io.blocks.borrow().len(),
+            "allocated simulated register space"
+        );
+        Ok(io)
+    }
+
+    /// Register a hook that clears every command register after it was written.
+    ///
+    /// Real devices typically self-clear command registers once the action
+    /// has been triggered; enabling this makes polling loops terminate.
+    pub fn with_self_clearing_commands(mut self) -> Self {
+        for (address, len) in self.commands.clone() {
+            let zero = vec![0u8; len as usize];
+            self.on_write(address, move |io, addr, _| io.poke(addr, &zero));
+        }
+        self
+    }
+
+    /// Attach a side-effect hook to writes starting at `address`.
+    pub fn on_write<F>(&mut self, address: u64, hook: F)
+    where
+        F: Fn(&MemoryRegisterIo, u64, &[u8]) + 'static,
+    {
+        self.hooks.entry(address).or_default().push(Box::new(hook));
+    }
+
+    /// Allocate a block with the provided access mode, merging overlaps.
+    pub fn allocate(
+        &self,
+        address: u64,
+        len: usize,
+        access: AccessMode,
+    ) -> Result<(), GenApiError> {
+        if len == 0 {
+            return Ok(());
+        }
+        let mut end = address.checked_add(len as u64).ok_or_else(|| {
+            GenApiError::Io(format!(
+                "register block {address:#x}+{len} exceeds the address space"
+            ))
+        })?;
+        let mut blocks = self.blocks.borrow_mut();
+        let mut start = address;
+        let mut merged_access = access;
+        let overlapping: Vec<u64> = blocks
+            .range(..end)
+            .filter(|(base, block)| **base + block.data.len() as u64 > address)
+            .map(|(base, _)| *base)
+            .collect();
+        let mut parts = Vec::with_capacity(overlapping.len());
+        for base in overlapping {
+            if let Some(block) = blocks.remove(&base) {
+                start = start.min(base);
+                end = end.max(base + block.data.len() as u64);
+                merged_access = merge_access(merged_access, block.access);
+                parts.push((base, block.data));
+            }
+        }
+        let mut data = vec![0u8; (end - start) as usize];
+        for (base, bytes) in parts {
+            let offset = (base - start) as usize;
+            data[offset..offset + bytes.len()].copy_from_slice(&bytes);
+        }
+        blocks.insert(
+            start,
+            Block {
+                data,
+                access: merged_access,
+            },
+        );
+        Ok(())
+    }
+
+    /// Read raw bytes bypassing access checks.
+    pub fn peek(&self, address: u64, len: usize) -> Option<Vec<u8>> {
+        let blocks = self.blocks.borrow();
+        let (base, block) = find_block(&blocks, address, len)?;
+        let offset = (address - base) as usize;
+        Some(block.data[offset..offset + len].to_vec())
+    }
+
+    /// Write raw bytes bypassing access checks and hooks.
+    ///
+    /// Unmapped ranges are allocated as read-write blocks.
+    pub fn poke(&self, address: u64, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        let mapped = find_block(&self.blocks.borrow(), address, data.len()).is_some();
+        if !mapped && self.allocate(address, data.len(), AccessMode::RW).is_err() {
+            warn!(
+                address,
+                len = data.len(),
+                "poke beyond the address space ignored"
+            );
+            return;
+        }
+        let mut blocks = self.blocks.borrow_mut();
+        if let Some((base, block)) = find_block_mut(&mut blocks, address, data.len()) {
+            let offset = (address - base) as usize;
+            block.data[offset..offset + data.len()].copy_from_slice(data);
+        }
+    }
+
+    fn allocate_decl(&mut self, decl: &NodeDecl) -> Result<(), GenApiError> {
+        match decl {
+            NodeDecl::Integer {
+                addressing, access, ..
+            }
+            | NodeDecl::Float {
+                addressing, access, ..
+            }
+            | NodeDecl::Enum {
+                addressing, access, ..
+            }
+            | NodeDecl::Boolean {
+                addressing, access, ..
+            } => {
+                for (address, len) in fixed_blocks(addressing) {
+                    self.allocate(address, len as usize, *access)?;
+                }
+            }
+            NodeDecl::Command { address, len, .. } => {
+                self.allocate(*address, *len as usize, AccessMode::WO)?;
+                self.commands.push((*address, *len));
+            }
+            NodeDecl::Category { .. } | NodeDecl::SwissKnife(_) => {}
+        }
+        Ok(())
+    }
+
+    fn seed_decl(&self, decl: &NodeDecl) {
+        match decl {
+            NodeDecl::Integer {
+                name,
+                addressing,
+                min,
+                bitfield,
+                ..
+            } => self.seed_value(name, addressing, *min, *bitfield, *min < 0),
+            NodeDecl::Float {
+                name,
+                addressing,
+                min,
+                scale,
+                offset,
+                ..
+            } => {
+                let mut raw = *min - offset.unwrap_or(0.0);
+                if let Some((num, den)) = scale {
+                    if *num == 0 {
+                        return;
+                    }
+                    raw *= *den as f64 / *num as f64;
+                }
+                self.seed_value(name, addressing, raw.round() as i64, None, false);
+            }
+            NodeDecl::Enum {
+                name,
+                addressing,
+                entries,
+                default,
+                ..
+            } => {
+                let literal = |entry: &genapi_xml::EnumEntryDecl| match entry.value {
+                    EnumValueSrc::Literal(value) => Some(value),
+                    EnumValueSrc::FromNode(_) => None,
+                };
+                let value = default
+                    .as_ref()
+                    .and_then(|default| entries.iter().find(|entry| &entry.name == default))
+                    .and_then(literal)
+                    .or_else(|| entries.iter().find_map(literal));
+                if let Some(value) = value {
+                    self.seed_value(name, addressing, value, None, false);
+                }
+            }
+            NodeDecl::Boolean { .. }
+            | NodeDecl::Command { .. }
+            | NodeDecl::Category { .. }
+            | NodeDecl::SwissKnife(_) => {}
+        }
+    }
+
+    fn seed_value(
+        &self,
+        name: &str,
+        addressing: &Addressing,
+        value: i64,
+        bitfield: Option<genapi_xml::BitField>,
+        signed: bool,
+    ) {
+        for (address, block_len) in fixed_blocks(addressing) {
+            let result = match bitfield {
+                Some(bitfield) => encode_bitfield_value(name, value, bitfield.bit_length, signed)
+                    .and_then(|encoded| {
+                        let mut raw = self
+                            .peek(address, block_len as usize)
+                            .unwrap_or_else(|| vec![0u8; block_len as usize]);
+                        insert(&mut raw, bitfield, encoded)
+                            .map(|_| raw)
+                            .map_err(|err| map_bitops_error(name, err))
+                    }),
+                None => i64_to_bytes(name, value, block_len),
+            };
+            match result {
+                Ok(bytes) => {
+                    trace!(node = %name, address = format_args!("0x{address:X}"), value, "seed register");
+                    self.poke(address, &bytes);
+                }
+                Err(err) => {
+                    warn!(node = %name, error = %err, "unable to seed simulated register");
+                }
+            }
+        }
+    }
+}
+
+impl Default for MemoryRegisterIo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for MemoryRegisterIo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryRegisterIo")
+            .field("blocks", &self.blocks.borrow().len())
+            .field("hooks", &self.hooks.len())
+            .finish()
+    }
+}
+
+impl RegisterIo for MemoryRegisterIo {
+    fn read(&self, addr: u64, len: usize) -> Result<Vec<u8>, GenApiError> {
+        let blocks = self.blocks.borrow();
+        let (base, block) = find_block(&blocks, addr, len)
+            .ok_or_else(|| GenApiError::Io(format!("read miss at 0x{addr:08X}")))?;
+        if matches!(block.access, AccessMode::WO) {
+            return Err(GenApiError::Access(format!(
+                "register 0x{addr:08X} is write-only"
+            )));
+        }
+        let offset = (addr - base) as usize;
+        Ok(block.data[offset..offset + len].to_vec())
+    }
+
+    fn write(&self, addr: u64, data: &[u8]) -> Result<(), GenApiError> {
+        {
+            let mut blocks = self.blocks.borrow_mut();
+            let (base, block) = find_block_mut(&mut blocks, addr, data.len())
+                .ok_or_else(|| GenApiError::Io(format!("write miss at 0x{addr:08X}")))?;
+            if matches!(block.access, AccessMode::RO) {
+                return Err(GenApiError::Access(format!(
+                    "register 0x{addr:08X} is read-only"
+                )));
+            }
+            let offset = (addr - base) as usize;
+            block.data[offset..offset + data.len()].copy_from_slice(data);
+        }
+        if let Some(hooks) = self.hooks.get(&addr) {
+            for hook in hooks {
+                hook(self, addr, data);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn fixed_blocks(addressing: &Addressing) -> Vec<(u64, u32)> {
+    match addressing {
+        Addressing::Fixed { address, len } => vec![(*address, *len)],
+        Addressing::BySelector { map, .. } => map.iter().map(|(_, block)| *block).collect(),
+        Addressing::Indirect { .. } => Vec::new(),
+    }
+}
+
+fn merge_access(a: AccessMode, b: AccessMode) -> AccessMode {
+    if a == b {
+        a
+    } else {
+        AccessMode::RW
+    }
+}
+
+fn find_block(blocks: &BTreeMap<u64, Block>, addr: u64, len: usize) -> Option<(u64, &Block)> {
+    let (base, block) = blocks.range(..=addr).next_back()?;
+    let end = addr.checked_add(len as u64)?;
+    (end <= *base + block.data.len() as u64).then_some((*base, block))
+}
+
+fn find_block_mut(
+    blocks: &mut BTreeMap<u64, Block>,
+    addr: u64,
+    len: usize,
+) -> Option<(u64, &mut Block)> {
+    let (base, block) = blocks.range_mut(..=addr).next_back()?;
+    let end = addr.checked_add(len as u64)?;
+    (end <= *base + block.data.len() as u64).then_some((*base, block))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NodeMap;
+    use std::rc::Rc;
+
+    const FIXTURE: &str = r#"
+        <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+            <Integer Name="Width">
+                <Address>0x100</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>16</Min>
+                <Max>4096</Max>
+                <Inc>2</Inc>
+            </Integer>
+            <Integer Name="SensorWidth">
+                <Address>0x104</Address>
+                <Length>4</Length>
+                <AccessMode>RO</AccessMode>
+                <Min>2048</Min>
+                <Max>2048</Max>
+            </Integer>
+            <Float Name="ExposureTime">
+                <Address>0x200</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>10.0</Min>
+                <Max>100000.0</Max>
+                <Scale>1/1000</Scale>
+            </Float>
+            <Enumeration Name="TriggerMode">
+                <Address>0x300</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <EnumEntry Name="Off" Value="0" />
+                <EnumEntry Name="On" Value="1" />
+            </Enumeration>
+            <Enumeration Name="GainSelector">
+                <Address>0x310</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <EnumEntry Name="All" Value="0" />
+                <EnumEntry Name="Red" Value="1" />
+            </Enumeration>
+            <Integer Name="Gain">
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>1</Min>
+                <Max>48</Max>
+                <pSelected>GainSelector</pSelected>
+                <Selected>All</Selected>
+                <Address>0x320</Address>
+                <Selected>Red</Selected>
+                <Address>0x324</Address>
+            </Integer>
+            <Boolean Name="Flag">
+                <Address>0x400</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Bit>3</Bit>
+            </Boolean>
+            <Command Name="AcquisitionStart">
+                <Address>0x500</Address>
+                <Length>4</Length>
+            </Command>
+        </RegisterDescription>
+    "#;
+
+    fn build() -> (NodeMap, MemoryRegisterIo) {
+        let model = genapi_xml::parse(FIXTURE).expect("parse fixture");
+        let io = MemoryRegisterIo::from_model(&model).unwrap();
+        (NodeMap::try_from_xml(model).expect("build nodemap"), io)
+    }
+
+    #[test]
+    fn seeds_minimum_and_default_values() {
+        let (nodemap, io) = build();
+        assert_eq!(nodemap.get_integer("Width", &io).unwrap(), 16);
+        assert_eq!(nodemap.get_integer("SensorWidth", &io).unwrap(), 2048);
+        assert!((nodemap.get_float("ExposureTime", &io).unwrap() - 10.0).abs() < 1e-9);
+        assert_eq!(nodemap.get_enum("TriggerMode", &io).unwrap(), "Off");
+        assert_eq!(nodemap.get_integer("Gain", &io).unwrap(), 1);
+        assert!(!nodemap.get_bool("Flag", &io).unwrap());
+    }
+
+    #[test]
+    fn selector_blocks_and_bitfields_roundtrip() {
+        let (mut nodemap, io) = build();
+        nodemap.set_enum("GainSelector", "Red", &io).unwrap();
+        nodemap.set_integer("Gain", 24, &io).unwrap();
+        nodemap.set_enum("GainSelector", "All", &io).unwrap();
+        assert_eq!(nodemap.get_integer("Gain", &io).unwrap(), 1);
+        assert_eq!(io.peek(0x324, 4).unwrap(), vec![0, 0, 0, 24]);
+
+        nodemap.set_bool("Flag", true, &io).unwrap();
+        assert_eq!(io.peek(0x400, 4).unwrap(), vec![0x08, 0, 0, 0]);
+    }
+
+    #[test]
+    fn access_modes_are_enforced() {
+        let (_, io) = build();
+        let err = io.write(0x104, &[0, 0, 0, 1]).unwrap_err();
+        assert!(matches!(err, GenApiError::Access(_)));
+        let err = io.read(0x500, 4).unwrap_err();
+        assert!(matches!(err, GenApiError::Access(_)));
+        let err = io.read(0x9000, 4).unwrap_err();
+        assert!(matches!(err, GenApiError::Io(_)));
+
+        io.poke(0x104, &[0, 0, 0x10, 0]);
+        assert_eq!(io.peek(0x104, 4).unwrap(), vec![0, 0, 0x10, 0]);
+    }
+
+    #[test]
+    fn blocks_past_the_address_space_are_rejected() {
+        let io = MemoryRegisterIo::new();
+        let err = io.allocate(u64::MAX - 1, 4, AccessMode::RW).unwrap_err();
+        assert!(matches!(err, GenApiError::Io(_)));
+        io.allocate(u64::MAX - 4, 4, AccessMode::RW).unwrap();
+
+        let xml = FIXTURE.replace(
+            "<Address>0x104</Address>",
+            "<Address>0xFFFFFFFFFFFFFFFE</Address>",
+        );
+        let model = genapi_xml::parse(&xml).expect("parse fixture");
+        assert!(MemoryRegisterIo::from_model(&model).is_err());
+    }
+
+    #[test]
+    fn command_hooks_run_after_write() {
+        let model = genapi_xml::parse(FIXTURE).expect("parse fixture");
+        let mut io = MemoryRegisterIo::from_model(&model)
+            .unwrap()
+            .with_self_clearing_commands();
+        let started = Rc::new(RefCell::new(0u32));
+        let counter = Rc::clone(&started);
+        io.on_write(0x500, move |_, _, _| *counter.borrow_mut() += 1);
+        let mut nodemap = NodeMap::try_from_xml(model).expect("build nodemap");
+
+        nodemap.exec_command("AcquisitionStart", &io).unwrap();
+        assert_eq!(*started.borrow(), 1);
+        assert_eq!(io.peek(0x500, 4).unwrap(), vec![0, 0, 0, 0]);
+    }
+}
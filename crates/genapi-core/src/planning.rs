@@ -0,0 +1,218 @@
+//! Dry-run planning of feature writes without touching the device.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use tracing::debug;
+
+use crate::selected::FeatureValue;
+use crate::{GenApiError, NodeMap, RegisterIo};
+
+/// Register write a configuration change would perform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlannedWrite {
+    /// Start address of the write.
+    pub address: u64,
+    /// Bytes that would be written.
+    pub data: Vec<u8>,
+    /// Feature whose write produced the access; empty when unattributed.
+    pub feature: String,
+}
+
+/// [`RegisterIo`] that records writes instead of forwarding them.
+///
+/// Reads go to the wrapped transport, typically a
+/// [`ReplayRegisterIo`](crate::ReplayRegisterIo) or a live device, with
+/// planned writes overlaid so later reads observe the values the plan would
+/// have written. Bytes fully covered by planned writes are served without
+/// consulting the wrapped transport.
+pub struct PlanningRegisterIo<'a> {
+    inner: &'a dyn RegisterIo,
+    overlay: RefCell<BTreeMap<u64, u8>>,
+    writes: RefCell<Vec<PlannedWrite>>,
+    feature: RefCell<String>,
+}
+
+impl<'a> PlanningRegisterIo<'a> {
+    /// Plan on top of the register contents provided by `inner`.
+    pub fn new(inner: &'a dyn RegisterIo) -> Self {
+        Self {
+            inner,
+            overlay: RefCell::new(BTreeMap::new()),
+            writes: RefCell::new(Vec::new()),
+            feature: RefCell::new(String::new()),
+        }
+    }
+
+    /// Attribute subsequent writes to `feature`.
+    pub fn set_feature(&self, feature: &str) {
+        self.feature.replace(feature.to_string());
+    }
+
+    /// Writes recorded so far, in the order they were issued.
+    pub fn writes(&self) -> Vec<PlannedWrite> {
+        self.writes.borrow().clone()
+    }
+
+    /// Consume the planner and return the recorded writes.
+    pub fn into_writes(self) -> Vec<PlannedWrite> {
+        self.writes.into_inner()
+    }
+}
+
+impl RegisterIo for PlanningRegisterIo<'_> {
+    fn read(&self, addr: u64, len: usize) -> Result<Vec<u8>, GenApiError> {
+        let overlay = self.overlay.borrow();
+        let covered: Option<Vec<u8>> = (0..len as u64)
+            .map(|offset| overlay.get(&(addr + offset)).copied())
+            .collect();
+        if let Some(bytes) = covered {
+            return Ok(bytes);
+        }
+        let mut bytes = self.inner.read(addr, len)?;
+        for (offset, byte) in bytes.iter_mut().enumerate() {
+            if let Some(planned) = overlay.get(&(addr + offset as u64)) {
+                *byte = *planned;
+            }
+        }
+        Ok(bytes)
+    }
+
+    fn write(&self, addr: u64, data: &[u8]) -> Result<(), GenApiError> {
+        let mut overlay = self.overlay.borrow_mut();
+        for (offset, byte) in data.iter().enumerate() {
+            overlay.insert(addr + offset as u64, *byte);
+        }
+        self.writes.borrow_mut().push(PlannedWrite {
+            address: addr,
+            data: data.to_vec(),
+            feature: self.feature.borrow().clone(),
+        });
+        Ok(())
+    }
+}
+
+impl NodeMap {
+    /// Compute the register writes that applying `writes` in order would issue.
+    ///
+    /// Range, access, availability and [`WritePolicy`](crate::WritePolicy)
+    /// checks run as for a real write, so the first rejected value is returned
+    /// as the error. Reads are served by `io`; nothing is written to it.
+    /// Cached values are dropped afterwards so the nodemap does not retain
+    /// planned state.
+    pub fn plan_set(
+        &mut self,
+        writes: &[(&str, FeatureValue)],
+        io: &dyn RegisterIo,
+    ) -> Result<Vec<PlannedWrite>, GenApiError> {
+        let planner = PlanningRegisterIo::new(io);
+        // Planned writes are checked against the policy but never audited.
+        let policy = self.policy.take();
+        let result = writes.iter().try_for_each(|(name, value)| {
+            if let Some(policy) = &policy {
+                policy.check(name, Some(value))?;
+            }
+            planner.set_feature(name);
+            self.set_value(name, value, &planner)
+        });
+        self.policy = policy;
+        self.invalidate_all();
+        result?;
+        let planned = planner.into_writes();
+        debug!(
+            features = writes.len(),
+            writes = planned.len(),
+            "planned writes"
+        );
+        Ok(planned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryRegisterIo;
+
+    const FIXTURE: &str = r#"
+        <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+            <Enumeration Name="GainSelector">
+                <Address>0x300</Address>
+                <Length>2</Length>
+                <AccessMode>RW</AccessMode>
+                <EnumEntry Name="All" Value="0" />
+                <EnumEntry Name="Red" Value="1" />
+            </Enumeration>
+            <Integer Name="Gain">
+                <Length>2</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>0</Min>
+                <Max>48</Max>
+                <pSelected>GainSelector</pSelected>
+                <Selected>All</Selected>
+                <Address>0x310</Address>
+                <Selected>Red</Selected>
+                <Address>0x314</Address>
+            </Integer>
+            <Integer Name="Width">
+                <Address>0x100</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>0</Min>
+                <Max>4096</Max>
+            </Integer>
+        </RegisterDescription>
+    "#;
+
+    fn setup() -> (NodeMap, MemoryRegisterIo) {
+        let model = genapi_xml::parse(FIXTURE).expect("parse fixture");
+        let io = MemoryRegisterIo::from_model(&model).unwrap();
+        (NodeMap::try_from_xml(model).expect("build nodemap"), io)
+    }
+
+    #[test]
+    fn plans_ordered_writes_without_touching_device() {
+        let (mut nodemap, io) = setup();
+        nodemap.set_integer("Width", 640, &io).unwrap();
+
+        let plan = nodemap
+            .plan_set(
+                &[
+                    ("Width", FeatureValue::Integer(1024)),
+                    ("GainSelector", FeatureValue::Enum("Red".into())),
+                    ("Gain", FeatureValue::Integer(9)),
+                ],
+                &io,
+            )
+            .expect("plan");
+        let summary: Vec<(u64, &str)> = plan
+            .iter()
+            .map(|write| (write.address, write.feature.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![(0x100, "Width"), (0x300, "GainSelector"), (0x314, "Gain")]
+        );
+        assert_eq!(plan[2].data.len(), 2);
+
+        assert_eq!(nodemap.get_integer("Width", &io).unwrap(), 640);
+        assert_eq!(nodemap.get_enum("GainSelector", &io).unwrap(), "All");
+        assert_eq!(io.read(0x314, 2).unwrap(), vec![0, 0]);
+    }
+
+    #[test]
+    fn rejected_value_fails_the_plan() {
+        let (mut nodemap, io) = setup();
+        let err = nodemap
+            .plan_set(
+                &[
+                    ("Width", FeatureValue::Integer(320)),
+                    ("Gain", FeatureValue::Integer(99)),
+                ],
+                &io,
+            )
+            .expect_err("gain out of range");
+        assert!(matches!(err, GenApiError::Range(name) if name == "Gain"));
+        assert_eq!(nodemap.get_integer("Width", &io).unwrap(), 0);
+    }
+}
@@ -0,0 +1,306 @@
+//! Allow/deny rules restricting which features may be written.
+
+use std::fmt;
+
+use tracing::{debug, warn};
+use wildmatch::WildMatch;
+
+use crate::selected::FeatureValue;
+use crate::{GenApiError, NodeMap};
+
+/// Callback receiving every write accepted by a [`WritePolicy`].
+pub type AuditHook = Box<dyn Fn(&WriteRecord)>;
+
+/// Accepted write reported to the audit hook.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteRecord {
+    /// Feature that was written.
+    pub feature: String,
+    /// Written value; `None` for command execution.
+    pub value: Option<FeatureValue>,
+}
+
+#[derive(Debug, Clone)]
+enum RuleAction {
+    Allow,
+    AllowRange { min: f64, max: f64 },
+    Deny,
+}
+
+#[derive(Debug, Clone)]
+struct PolicyRule {
+    pattern: String,
+    action: RuleAction,
+}
+
+/// Ordered list of glob rules deciding whether a feature write is permitted.
+///
+/// Patterns support `*` (any run of characters) and `?` (one character). The
+/// first rule matching the feature name decides; writes matching no rule fall
+/// back to the policy default. Once installed with
+/// [`NodeMap::set_write_policy`] the policy applies to every write issued
+/// through the nodemap, including selector changes made internally by helpers
+/// such as [`NodeMap::read_all_selected`].
+pub struct WritePolicy {
+    rules: Vec<PolicyRule>,
+    default_allow: bool,
+    audit: Option<AuditHook>,
+}
+
+impl WritePolicy {
+    /// Policy permitting every write not matched by a deny rule.
+    pub fn allow_by_default() -> Self {
+        Self {
+            rules: Vec::new(),
+            default_allow: true,
+            audit: None,
+        }
+    }
+
+    /// Policy rejecting every write not matched by an allow rule.
+    pub fn deny_by_default() -> Self {
+        Self {
+            default_allow: false,
+            ..Self::allow_by_default()
+        }
+    }
+
+    /// Permit writes to features matching `pattern`.
+    pub fn allow(mut self, pattern: &str) -> Self {
+        self.push(pattern, RuleAction::Allow);
+        self
+    }
+
+    /// Permit numeric writes to features matching `pattern` within `min..=max`.
+    pub fn allow_range(mut self, pattern: &str, min: f64, max: f64) -> Self {
+        self.push(pattern, RuleAction::AllowRange { min, max });
+        self
+    }
+
+    /// Reject writes to features matching `pattern`.
+    pub fn deny(mut self, pattern: &str) -> Self {
+        self.push(pattern, RuleAction::Deny);
+        self
+    }
+
+    /// Report every accepted write to `hook`.
+    pub fn with_audit(mut self, hook: impl Fn(&WriteRecord) + 'static) -> Self {
+        self.audit = Some(Box::new(hook));
+        self
+    }
+
+    /// Decide whether writing `value` to `feature` is permitted.
+    ///
+    /// `value` is `None` for command execution. Rejections are reported as
+    /// [`GenApiError::PolicyViolation`].
+    pub fn check(&self, feature: &str, value: Option<&FeatureValue>) -> Result<(), GenApiError> {
+        let violation = |reason: String| GenApiError::PolicyViolation {
+            feature: feature.to_string(),
+            reason,
+        };
+        let Some(rule) = self
+            .rules
+            .iter()
+            .find(|rule| WildMatch::new(&rule.pattern).matches(feature))
+        else {
+            return if self.default_allow {
+                Ok(())
+            } else {
+                Err(violation("not covered by any allow rule".into()))
+            };
+        };
+        match rule.action {
+            RuleAction::Allow => Ok(()),
+            RuleAction::Deny => Err(violation(format!("denied by rule '{}'", rule.pattern))),
+            RuleAction::AllowRange { min, max } => {
+                let numeric = match value {
+                    Some(FeatureValue::Integer(value)) => *value as f64,
+                    Some(FeatureValue::Float(value)) => *value,
+                    _ => {
+                        return Err(violation(format!(
+                            "rule '{}' only permits numeric values",
+                            rule.pattern
+                        )))
+                    }
+                };
+                if (min..=max).contains(&numeric) {
+                    Ok(())
+                } else {
+                    Err(violation(format!(
+                        "value {numeric} outside [{min}, {max}] permitted by rule '{}'",
+                        rule.pattern
+                    )))
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, pattern: &str, action: RuleAction) {
+        self.rules.push(PolicyRule {
+            pattern: pattern.to_string(),
+            action,
+        });
+    }
+}
+
+impl fmt::Debug for WritePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WritePolicy")
+            .field("rules", &self.rules)
+            .field("default_allow", &self.default_allow)
+            .field("audit", &self.audit.is_some())
+            .finish()
+    }
+}
+
+impl NodeMap {
+    /// Install `policy`, replacing any previous one.
+    pub fn set_write_policy(&mut self, policy: WritePolicy) {
+        self.policy = Some(policy);
+    }
+
+    /// Remove the installed write policy and return it.
+    pub fn clear_write_policy(&mut self) -> Option<WritePolicy> {
+        self.policy.take()
+    }
+
+    /// Currently installed write policy, if any.
+    pub fn write_policy(&self) -> Option<&WritePolicy> {
+        self.policy.as_ref()
+    }
+
+    /// Check a pending write against the policy.
+    ///
+    /// Returns the record to pass to [`NodeMap::audit_write`] once the write
+    /// succeeded; `value` is only evaluated when a policy is installed.
+    pub(crate) fn enforce_policy(
+        &self,
+        feature: &str,
+        value: impl FnOnce() -> Option<FeatureValue>,
+    ) -> Result<Option<WriteRecord>, GenApiError> {
+        let Some(policy) = &self.policy else {
+            return Ok(None);
+        };
+        let value = value();
+        if let Err(err) = policy.check(feature, value.as_ref()) {
+            warn!(feature, error = %err, "write rejected by policy");
+            return Err(err);
+        }
+        Ok(Some(WriteRecord {
+            feature: feature.to_string(),
+            value,
+        }))
+    }
+
+    /// Report a completed write to the audit hook.
+    pub(crate) fn audit_write(&self, record: Option<WriteRecord>) {
+        let (Some(record), Some(policy)) = (record, &self.policy) else {
+            return;
+        };
+        debug!(feature = %record.feature, value = ?record.value, "audited write");
+        if let Some(hook) = &policy.audit {
+            hook(&record);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::MemoryRegisterIo;
+
+    const FIXTURE: &str = r#"
+        <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+            <Enumeration Name="PixelFormat">
+                <Address>0x300</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <EnumEntry Name="Mono8" Value="1" />
+                <EnumEntry Name="Mono16" Value="2" />
+            </Enumeration>
+            <Integer Name="GevSCPSPacketSize">
+                <Address>0x100</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>576</Min>
+                <Max>9000</Max>
+            </Integer>
+            <Float Name="ExposureTime">
+                <Address>0x200</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>10</Min>
+                <Max>1000000</Max>
+            </Float>
+        </RegisterDescription>
+    "#;
+
+    fn setup() -> (NodeMap, MemoryRegisterIo) {
+        let model = genapi_xml::parse(FIXTURE).expect("parse fixture");
+        let io = MemoryRegisterIo::from_model(&model).unwrap();
+        (NodeMap::try_from_xml(model).expect("build nodemap"), io)
+    }
+
+    #[test]
+    fn glob_patterns() {
+        let policy = WritePolicy::deny_by_default()
+            .allow("GevSCPS*")
+            .allow("*Time")
+            .allow("Gain?")
+            .allow("*a*e*")
+            .allow("Pixel*Format");
+        for feature in ["GevSCPSPacketSize", "ExposureTime", "Gain1", "PacketSize"] {
+            assert!(policy.check(feature, None).is_ok(), "{feature}");
+        }
+        assert!(policy.check("Gain", None).is_err());
+        assert!(policy.check("PixelFormatInfo", None).is_err());
+    }
+
+    #[test]
+    fn denied_and_out_of_range_writes_are_rejected() {
+        let (mut nodemap, io) = setup();
+        nodemap.set_write_policy(
+            WritePolicy::allow_by_default()
+                .deny("PixelFormat")
+                .deny("GevSCPS*")
+                .allow_range("Exposure*", 100.0, 20_000.0),
+        );
+
+        let err = nodemap.set_enum("PixelFormat", "Mono16", &io).unwrap_err();
+        assert!(
+            matches!(err, GenApiError::PolicyViolation { ref feature, .. } if feature == "PixelFormat")
+        );
+        assert!(nodemap.set_integer("GevSCPSPacketSize", 1500, &io).is_err());
+        assert!(nodemap.set_float("ExposureTime", 50_000.0, &io).is_err());
+        nodemap.set_float("ExposureTime", 5_000.0, &io).unwrap();
+        assert_eq!(nodemap.get_enum("PixelFormat", &io).unwrap(), "Mono8");
+
+        nodemap.clear_write_policy();
+        nodemap.set_enum("PixelFormat", "Mono16", &io).unwrap();
+    }
+
+    #[test]
+    fn audit_hook_sees_accepted_writes_only() {
+        let (mut nodemap, io) = setup();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&log);
+        nodemap.set_write_policy(
+            WritePolicy::deny_by_default()
+                .allow("ExposureTime")
+                .with_audit(move |record| sink.borrow_mut().push(record.clone())),
+        );
+
+        nodemap.set_float("ExposureTime", 250.0, &io).unwrap();
+        assert!(nodemap.set_integer("GevSCPSPacketSize", 1500, &io).is_err());
+        assert_eq!(
+            *log.borrow(),
+            vec![WriteRecord {
+                feature: "ExposureTime".into(),
+                value: Some(FeatureValue::Float(250.0)),
+            }]
+        );
+    }
+}
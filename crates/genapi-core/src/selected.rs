@@ -0,0 +1,326 @@
+//! Typed value reads and selector-expanded feature audits.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::{GenApiError, Node, NodeMap, RegisterIo, SkOutput};
+
+/// Value of a feature read without knowing its type up front.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FeatureValue {
+    /// Value of an integer feature or integer SwissKnife.
+    Integer(i64),
+    /// Value of a float feature or float SwissKnife.
+    Float(f64),
+    /// Current entry name of an enumeration.
+    Enum(String),
+    /// Value of a boolean feature.
+    Boolean(bool),
+}
+
+impl fmt::Display for FeatureValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeatureValue::Integer(value) => write!(f, "{value}"),
+            FeatureValue::Float(value) => write!(f, "{value}"),
+            FeatureValue::Enum(entry) => f.write_str(entry),
+            FeatureValue::Boolean(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// Selector assignment identifying one reading of a selected feature, as
+/// `(selector, value)` pairs in the order of the node's selector list.
+pub type SelectorCombination = Vec<(String, String)>;
+
+/// Most values enumerated for an integer selector.
+const MAX_INTEGER_SELECTOR_VALUES: i64 = 256;
+
+impl NodeMap {
+    /// Read any value-carrying feature using its declared type.
+    pub fn get_value(&self, name: &str, io: &dyn RegisterIo) -> Result<FeatureValue, GenApiError> {
+        match self.lookup(name) {
+            Some(Node::Integer(_)) => self.get_integer(name, io).map(FeatureValue::Integer),
+            Some(Node::Float(_)) => self.get_float(name, io).map(FeatureValue::Float),
+            Some(Node::Enum(_)) => self.get_enum(name, io).map(FeatureValue::Enum),
+            Some(Node::Boolean(_)) => self.get_bool(name, io).map(FeatureValue::Boolean),
+            Some(Node::SwissKnife(sk)) => match sk.output {
+                SkOutput::Integer => self.get_integer(name, io).map(FeatureValue::Integer),
+                SkOutput::Float => self.get_float(name, io).map(FeatureValue::Float),
+            },
+            Some(Node::Command(_) | Node::Category(_)) => Err(GenApiError::Type(name.to_string())),
+            None => Err(GenApiError::NodeNotFound(name.to_string())),
+        }
+    }
+
+    /// Write a value previously obtained from [`NodeMap::get_value`].
+    pub fn set_value(
+        &mut self,
+        name: &str,
+        value: &FeatureValue,
+        io: &dyn RegisterIo,
+    ) -> Result<(), GenApiError> {
+        match value {
+            FeatureValue::Integer(value) => self.set_integer(name, *value, io),
+            FeatureValue::Float(value) => self.set_float(name, *value, io),
+            FeatureValue::Enum(entry) => self.set_enum(name, entry, io),
+            FeatureValue::Boolean(value) => self.set_bool(name, *value, io),
+        }
+    }
+
+    /// Values a selector can take, in the textual form used by
+    /// [`SelectorCombination`].
+    ///
+    /// Enumerations yield their entry names, integers every value from `Min`
+    /// to `Max` in steps of `Inc` (at most 256 of them) and booleans `false`
+    /// and `true`.
+    pub fn selector_values(&self, name: &str) -> Result<Vec<String>, GenApiError> {
+        match self.lookup(name) {
+            Some(Node::Enum(_)) => self.enum_entries(name),
+            Some(Node::Integer(node)) => {
+                let step = node.inc.filter(|inc| *inc > 0).unwrap_or(1);
+                Ok((0..MAX_INTEGER_SELECTOR_VALUES)
+                    .map_while(|k| k.checked_mul(step).and_then(|o| node.min.checked_add(o)))
+                    .take_while(|value| *value <= node.max)
+                    .map(|value| value.to_string())
+                    .collect())
+            }
+            Some(Node::Boolean(_)) => Ok(vec!["false".into(), "true".into()]),
+            Some(_) => Err(GenApiError::Type(name.to_string())),
+            None => Err(GenApiError::NodeNotFound(name.to_string())),
+        }
+    }
+
+    /// Read `name` for every combination of its selectors.
+    ///
+    /// Selectors take the values listed by [`NodeMap::selector_values`].
+    /// Combinations under which the feature is unavailable are omitted. The
+    /// selectors are restored to their original values before returning, even
+    /// when a read fails. Since this is a read, the selector switches are
+    /// neither checked against the write policy nor audited. A feature without selectors yields a single entry
+    /// keyed by the empty combination.
+    pub fn read_all_selected(
+        &mut self,
+        name: &str,
+        io: &dyn RegisterIo,
+    ) -> Result<BTreeMap<SelectorCombination, FeatureValue>, GenApiError> {
+        let mut selectors: Vec<String> = Vec::new();
+        for selector in self.node_selectors(name)? {
+            if !selectors.contains(selector) {
+                selectors.push(selector.clone());
+            }
+        }
+        let mut choices = Vec::with_capacity(selectors.len());
+        let mut originals = Vec::with_capacity(selectors.len());
+        for selector in &selectors {
+            choices.push(self.selector_values(selector)?);
+            originals.push(self.get_selector_value(selector, io)?);
+        }
+
+        let policy = self.policy.take();
+        let mut values = BTreeMap::new();
+        let mut combination = Vec::with_capacity(selectors.len());
+        let result = self.read_combinations(
+            name,
+            io,
+            &selectors,
+            &choices,
+            &mut combination,
+            &mut values,
+        );
+        for (selector, original) in selectors.iter().zip(&originals) {
+            let restored = self.set_selector(selector, original, io);
+            if result.is_ok() {
+                if let Err(err) = restored {
+                    self.policy = policy;
+                    return Err(err);
+                }
+            }
+        }
+        self.policy = policy;
+        result.map(|_| values)
+    }
+
+    fn read_combinations(
+        &mut self,
+        name: &str,
+        io: &dyn RegisterIo,
+        selectors: &[String],
+        choices: &[Vec<String>],
+        combination: &mut SelectorCombination,
+        values: &mut BTreeMap<SelectorCombination, FeatureValue>,
+    ) -> Result<(), GenApiError> {
+        let depth = combination.len();
+        let Some(selector) = selectors.get(depth) else {
+            match self.get_value(name, io) {
+                Ok(value) => {
+                    values.insert(combination.clone(), value);
+                }
+                Err(GenApiError::Unavailable(_)) => {}
+                Err(err) => return Err(err),
+            }
+            return Ok(());
+        };
+        for entry in &choices[depth] {
+            match self.set_selector(selector, entry, io) {
+                Ok(()) => {}
+                Err(GenApiError::Unavailable(_)) => continue,
+                Err(err) => return Err(err),
+            }
+            combination.push((selector.clone(), entry.clone()));
+            let result = self.read_combinations(name, io, selectors, choices, combination, values);
+            combination.pop();
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Put `selector` on one of the values from [`NodeMap::selector_values`].
+    pub(crate) fn set_selector(
+        &mut self,
+        selector: &str,
+        value: &str,
+        io: &dyn RegisterIo,
+    ) -> Result<(), GenApiError> {
+        let invalid = || GenApiError::Type(selector.to_string());
+        match self.lookup(selector) {
+            Some(Node::Integer(_)) => {
+                let value = value.parse().map_err(|_| invalid())?;
+                self.set_integer(selector, value, io)
+            }
+            Some(Node::Boolean(_)) => {
+                let value = value.parse().map_err(|_| invalid())?;
+                self.set_bool(selector, value, io)
+            }
+            _ => self.set_enum(selector, value, io),
+        }
+    }
+
+    fn node_selectors(&self, name: &str) -> Result<&[String], GenApiError> {
+        match self.lookup(name) {
+            Some(Node::Integer(node)) => Ok(&node.selectors),
+            Some(Node::Float(node)) => Ok(&node.selectors),
+            Some(Node::Enum(node)) => Ok(&node.selectors),
+            Some(Node::Boolean(node)) => Ok(&node.selectors),
+            Some(_) => Err(GenApiError::Type(name.to_string())),
+            None => Err(GenApiError::NodeNotFound(name.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryRegisterIo;
+
+    const FIXTURE: &str = r#"
+        <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+            <Enumeration Name="GainSelector">
+                <Address>0x300</Address>
+                <Length>2</Length>
+                <AccessMode>RW</AccessMode>
+                <EnumEntry Name="All" Value="0" />
+                <EnumEntry Name="Red" Value="1" />
+                <EnumEntry Name="Blue" Value="2" />
+            </Enumeration>
+            <Integer Name="Gain">
+                <Length>2</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>0</Min>
+                <Max>48</Max>
+                <pSelected>GainSelector</pSelected>
+                <Selected>All</Selected>
+                <Address>0x310</Address>
+                <Selected>Red</Selected>
+                <Address>0x314</Address>
+                <Selected>Blue</Selected>
+            </Integer>
+            <Integer Name="LUTIndex">
+                <Address>0x400</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>0</Min>
+                <Max>6</Max>
+                <Inc>2</Inc>
+            </Integer>
+            <SwissKnife Name="LUTAddress">
+                <pVariable Name="I">LUTIndex</pVariable>
+                <Expression>1280 + I * 2</Expression>
+                <Output>Integer</Output>
+            </SwissKnife>
+            <Integer Name="LUTValue">
+                <pAddress>LUTAddress</pAddress>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>0</Min>
+                <Max>4095</Max>
+                <pSelected>LUTIndex</pSelected>
+            </Integer>
+            <Integer Name="Width">
+                <Address>0x100</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>0</Min>
+                <Max>4096</Max>
+            </Integer>
+        </RegisterDescription>
+    "#;
+
+    fn setup() -> (NodeMap, MemoryRegisterIo) {
+        let model = genapi_xml::parse(FIXTURE).expect("parse fixture");
+        let io = MemoryRegisterIo::from_model(&model).unwrap();
+        (NodeMap::try_from_xml(model).expect("build nodemap"), io)
+    }
+
+    #[test]
+    fn reads_every_selector_entry_and_restores_selector() {
+        let (mut nodemap, io) = setup();
+        nodemap.set_enum("GainSelector", "Red", &io).unwrap();
+        nodemap.set_integer("Gain", 12, &io).unwrap();
+        nodemap.set_enum("GainSelector", "All", &io).unwrap();
+        nodemap.set_integer("Gain", 4, &io).unwrap();
+        nodemap.set_enum("GainSelector", "Red", &io).unwrap();
+
+        let values = nodemap.read_all_selected("Gain", &io).expect("read all");
+        let key = |entry: &str| vec![("GainSelector".to_string(), entry.to_string())];
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[&key("All")], FeatureValue::Integer(4));
+        assert_eq!(values[&key("Red")], FeatureValue::Integer(12));
+        assert_eq!(nodemap.get_enum("GainSelector", &io).unwrap(), "Red");
+    }
+
+    #[test]
+    fn enumerates_integer_selectors_by_increment() {
+        let (mut nodemap, io) = setup();
+        io.poke(0x500, &[0; 16]);
+        assert_eq!(
+            nodemap.selector_values("LUTIndex").unwrap(),
+            vec!["0", "2", "4", "6"]
+        );
+        for index in [0, 2, 4, 6] {
+            nodemap.set_integer("LUTIndex", index, &io).unwrap();
+            nodemap.set_integer("LUTValue", index * 100, &io).unwrap();
+        }
+        nodemap.set_integer("LUTIndex", 4, &io).unwrap();
+
+        let values = nodemap
+            .read_all_selected("LUTValue", &io)
+            .expect("read all");
+        let key = |index: &str| vec![("LUTIndex".to_string(), index.to_string())];
+        assert_eq!(values.len(), 4);
+        assert_eq!(values[&key("2")], FeatureValue::Integer(200));
+        assert_eq!(values[&key("6")], FeatureValue::Integer(600));
+        assert_eq!(nodemap.get_integer("LUTIndex", &io).unwrap(), 4);
+    }
+
+    #[test]
+    fn unselected_feature_yields_single_value() {
+        let (mut nodemap, io) = setup();
+        nodemap.set_integer("Width", 640, &io).unwrap();
+        let values = nodemap.read_all_selected("Width", &io).expect("read all");
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[&Vec::new()], FeatureValue::Integer(640));
+        assert_eq!(values[&Vec::new()].to_string(), "640");
+    }
+}
@@ -0,0 +1,447 @@
+//! Snapshots of writable feature values with diff and rollback support.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use tracing::{debug, warn};
+
+use crate::selected::{FeatureValue, SelectorCombination};
+use crate::{AccessMode, GenApiError, Node, NodeMap, RegisterIo};
+
+/// Value of one feature under one selector combination.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnapshotEntry {
+    /// Feature name.
+    pub name: String,
+    /// Selector entries active when the value was read; empty when unselected.
+    pub selectors: SelectorCombination,
+    /// Recorded value.
+    pub value: FeatureValue,
+}
+
+/// Set of readable and writable feature values captured by [`NodeMap::snapshot`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot {
+    /// Entries sorted by feature name and selector combination.
+    pub entries: Vec<SnapshotEntry>,
+}
+
+impl Snapshot {
+    /// Look up the value of `name` under `selectors`.
+    pub fn get(&self, name: &str, selectors: &[(String, String)]) -> Option<&FeatureValue> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name && entry.selectors == selectors)
+            .map(|entry| &entry.value)
+    }
+
+    fn by_key(&self) -> BTreeMap<(&str, &SelectorCombination), &FeatureValue> {
+        self.entries
+            .iter()
+            .map(|entry| ((entry.name.as_str(), &entry.selectors), &entry.value))
+            .collect()
+    }
+}
+
+/// Difference of one feature between two snapshots.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnapshotChange {
+    /// Feature name.
+    pub name: String,
+    /// Selector combination the value belongs to.
+    pub selectors: SelectorCombination,
+    /// Value in the first snapshot, if present.
+    pub before: Option<FeatureValue>,
+    /// Value in the second snapshot, if present.
+    pub after: Option<FeatureValue>,
+}
+
+/// How [`NodeMap::restore`] reacts to a failing write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RestoreMode {
+    /// Keep going and report every failure.
+    #[default]
+    BestEffort,
+    /// Stop at the first failure and write back the values changed so far.
+    Transactional,
+}
+
+/// Outcome of [`NodeMap::restore`].
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    /// Changes written to the device.
+    pub applied: Vec<SnapshotChange>,
+    /// Changes that could not be written, with the reason.
+    pub failed: Vec<(SnapshotChange, GenApiError)>,
+}
+
+/// List features whose values differ between `a` and `b`.
+///
+/// Features present in only one snapshot are reported with the missing side
+/// set to `None`.
+pub fn diff(a: &Snapshot, b: &Snapshot) -> Vec<SnapshotChange> {
+    let left = a.by_key();
+    let right = b.by_key();
+    let keys: BTreeSet<_> = left.keys().chain(right.keys()).copied().collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let before = left.get(&key).copied();
+            let after = right.get(&key).copied();
+            (before != after).then(|| SnapshotChange {
+                name: key.0.to_string(),
+                selectors: key.1.clone(),
+                before: before.cloned(),
+                after: after.cloned(),
+            })
+        })
+        .collect()
+}
+
+impl NodeMap {
+    /// Capture every readable and writable feature, expanding selectors.
+    ///
+    /// Selectors are switched through their values as in
+    /// [`NodeMap::read_all_selected`], without policy checks or auditing, and
+    /// end up where they started.
+    pub fn snapshot(&mut self, io: &dyn RegisterIo) -> Result<Snapshot, GenApiError> {
+        let mut names: Vec<String> = self
+            .names
+            .iter()
+            .filter(|(id, _)| is_read_write(&self.nodes[id.index()]))
+            .map(|(_, name)| name.to_string())
+            .collect();
+        names.sort();
+        let mut snapshot = Snapshot::default();
+        for name in names {
+            for (selectors, value) in self.read_all_selected(&name, io)? {
+                snapshot.entries.push(SnapshotEntry {
+                    name: name.clone(),
+                    selectors,
+                    value,
+                });
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// Bring the device back to `snapshot`, writing only differing values.
+    ///
+    /// Values are written in dependency order so selectors and address or
+    /// value providers are set before the features that use them; selectors
+    /// end up at their snapshot values. In [`RestoreMode::Transactional`] the
+    /// first failure rolls back the writes already made and is returned as
+    /// the error.
+    pub fn restore(
+        &mut self,
+        snapshot: &Snapshot,
+        io: &dyn RegisterIo,
+        mode: RestoreMode,
+    ) -> Result<RestoreReport, GenApiError> {
+        let current = self.snapshot(io)?;
+        let rank: HashMap<String, usize> = self
+            .dependency_graph()
+            .topological_order()
+            .into_iter()
+            .enumerate()
+            .map(|(idx, name)| (name, idx))
+            .collect();
+        let mut changes: Vec<SnapshotChange> = diff(&current, snapshot)
+            .into_iter()
+            .filter(|change| change.after.is_some())
+            .collect();
+        changes.sort_by_key(|change| rank.get(&change.name).copied().unwrap_or(usize::MAX));
+
+        let mut report = RestoreReport::default();
+        let mut touched: Vec<String> = Vec::new();
+        for change in changes {
+            for (selector, _) in &change.selectors {
+                if !touched.contains(selector) {
+                    touched.push(selector.clone());
+                }
+            }
+            let target = change.after.as_ref().expect("filtered above");
+            match self.write_entry(&change.name, &change.selectors, target, io) {
+                Ok(()) => {
+                    debug!(feature = %change.name, "restored feature");
+                    report.applied.push(change);
+                }
+                Err(err) if mode == RestoreMode::Transactional => {
+                    warn!(feature = %change.name, error = %err, "restore failed; rolling back");
+                    self.roll_back(&report.applied, &touched, &current, io);
+                    return Err(err);
+                }
+                Err(err) => {
+                    warn!(feature = %change.name, error = %err, "failed to restore feature");
+                    report.failed.push((change, err));
+                }
+            }
+        }
+        for selector in &touched {
+            let Some(target) = snapshot
+                .get(selector, &[])
+                .or_else(|| current.get(selector, &[]))
+            else {
+                continue;
+            };
+            match self.set_value(selector, target, io) {
+                Ok(()) => {}
+                Err(err) if mode == RestoreMode::Transactional => {
+                    warn!(selector = %selector, error = %err, "restore failed; rolling back");
+                    self.roll_back(&report.applied, &touched, &current, io);
+                    return Err(err);
+                }
+                Err(err) => {
+                    warn!(selector = %selector, error = %err, "failed to restore selector");
+                    let change = SnapshotChange {
+                        name: selector.clone(),
+                        selectors: Vec::new(),
+                        before: current.get(selector, &[]).cloned(),
+                        after: Some(target.clone()),
+                    };
+                    report.failed.push((change, err));
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    fn write_entry(
+        &mut self,
+        name: &str,
+        selectors: &[(String, String)],
+        value: &FeatureValue,
+        io: &dyn RegisterIo,
+    ) -> Result<(), GenApiError> {
+        for (selector, entry) in selectors {
+            self.set_selector(selector, entry, io)?;
+        }
+        self.set_value(name, value, io)
+    }
+
+    fn roll_back(
+        &mut self,
+        applied: &[SnapshotChange],
+        touched: &[String],
+        original: &Snapshot,
+        io: &dyn RegisterIo,
+    ) {
+        for change in applied.iter().rev() {
+            let Some(before) = &change.before else {
+                continue;
+            };
+            if let Err(err) = self.write_entry(&change.name, &change.selectors, before, io) {
+                warn!(feature = %change.name, error = %err, "rollback write failed");
+            }
+        }
+        for selector in touched {
+            if let Some(value) = original.get(selector, &[]) {
+                if let Err(err) = self.set_value(selector, value, io) {
+                    warn!(feature = %selector, error = %err, "rollback of selector failed");
+                }
+            }
+        }
+    }
+}
+
+fn is_read_write(node: &Node) -> bool {
+    let access = match node {
+        Node::Integer(node) => node.access,
+        Node::Float(node) => node.access,
+        Node::Enum(node) => node.access,
+        Node::Boolean(node) => node.access,
+        Node::Command(_) | Node::Category(_) | Node::SwissKnife(_) => return false,
+    };
+    access == AccessMode::RW
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemoryRegisterIo, WritePolicy};
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    const FIXTURE: &str = r#"
+        <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+            <Enumeration Name="GainSelector">
+                <Address>0x300</Address>
+                <Length>2</Length>
+                <AccessMode>RW</AccessMode>
+                <EnumEntry Name="All" Value="0" />
+                <EnumEntry Name="Red" Value="1" />
+            </Enumeration>
+            <Integer Name="Gain">
+                <Length>2</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>0</Min>
+                <Max>48</Max>
+                <pSelected>GainSelector</pSelected>
+                <Selected>All</Selected>
+                <Address>0x310</Address>
+                <Selected>Red</Selected>
+                <Address>0x314</Address>
+            </Integer>
+            <Integer Name="Width">
+                <Address>0x100</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>0</Min>
+                <Max>4096</Max>
+            </Integer>
+            <Integer Name="Height">
+                <Address>0x104</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>0</Min>
+                <Max>4096</Max>
+            </Integer>
+            <Integer Name="SensorTemperature">
+                <Address>0x108</Address>
+                <Length>4</Length>
+                <AccessMode>RO</AccessMode>
+                <Min>0</Min>
+                <Max>100</Max>
+            </Integer>
+        </RegisterDescription>
+    "#;
+
+    /// Register space failing the `nth` write (counting from 1) to `addr`.
+    struct FailingWrite {
+        inner: MemoryRegisterIo,
+        addr: u64,
+        nth: Cell<u32>,
+    }
+
+    impl RegisterIo for FailingWrite {
+        fn read(&self, addr: u64, len: usize) -> Result<Vec<u8>, GenApiError> {
+            self.inner.read(addr, len)
+        }
+
+        fn write(&self, addr: u64, data: &[u8]) -> Result<(), GenApiError> {
+            if addr == self.addr {
+                let left = self.nth.get().saturating_sub(1);
+                self.nth.set(left);
+                if left == 0 {
+                    return Err(GenApiError::Io("injected write failure".into()));
+                }
+            }
+            self.inner.write(addr, data)
+        }
+    }
+
+    fn setup() -> (NodeMap, MemoryRegisterIo) {
+        let model = genapi_xml::parse(FIXTURE).expect("parse fixture");
+        let io = MemoryRegisterIo::from_model(&model).unwrap();
+        (NodeMap::try_from_xml(model).expect("build nodemap"), io)
+    }
+
+    fn red() -> SelectorCombination {
+        vec![("GainSelector".to_string(), "Red".to_string())]
+    }
+
+    #[test]
+    fn snapshot_diff_and_restore_round_trip() {
+        let (mut nodemap, io) = setup();
+        nodemap.set_integer("Width", 640, &io).unwrap();
+        let before = nodemap.snapshot(&io).expect("snapshot");
+        assert_eq!(before.entries.len(), 5);
+        assert!(before.get("SensorTemperature", &[]).is_none());
+
+        nodemap.set_integer("Width", 1024, &io).unwrap();
+        nodemap.set_enum("GainSelector", "Red", &io).unwrap();
+        nodemap.set_integer("Gain", 9, &io).unwrap();
+        let after = nodemap.snapshot(&io).expect("snapshot");
+
+        let changes = diff(&before, &after);
+        let names: Vec<(&str, usize)> = changes
+            .iter()
+            .map(|change| (change.name.as_str(), change.selectors.len()))
+            .collect();
+        assert_eq!(names, vec![("Gain", 1), ("GainSelector", 0), ("Width", 0)]);
+        assert_eq!(changes[0].selectors, red());
+        assert_eq!(changes[0].after, Some(FeatureValue::Integer(9)));
+
+        let report = nodemap
+            .restore(&before, &io, RestoreMode::Transactional)
+            .expect("restore");
+        assert_eq!(report.applied.len(), 3);
+        assert!(report.failed.is_empty());
+        assert!(diff(&before, &nodemap.snapshot(&io).unwrap()).is_empty());
+        assert_eq!(nodemap.get_enum("GainSelector", &io).unwrap(), "All");
+    }
+
+    #[test]
+    fn snapshot_bypasses_write_policy_and_audit() {
+        let (mut nodemap, io) = setup();
+        let audited = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&audited);
+        nodemap.set_write_policy(
+            WritePolicy::allow_by_default()
+                .deny("GainSelector")
+                .with_audit(move |record| sink.borrow_mut().push(record.feature.clone())),
+        );
+
+        let snapshot = nodemap.snapshot(&io).expect("snapshot");
+        assert!(snapshot.get("Gain", &red()).is_some());
+        assert!(audited.borrow().is_empty());
+        assert!(nodemap.set_enum("GainSelector", "Red", &io).is_err());
+    }
+
+    #[test]
+    fn transactional_restore_rolls_back_on_error() {
+        let (mut nodemap, io) = setup();
+        let current = nodemap.snapshot(&io).unwrap();
+        let mut target = current.clone();
+        for entry in &mut target.entries {
+            match entry.name.as_str() {
+                "Height" => entry.value = FeatureValue::Integer(480),
+                "Width" => entry.value = FeatureValue::Integer(99_999),
+                _ => {}
+            }
+        }
+
+        let err = nodemap
+            .restore(&target, &io, RestoreMode::Transactional)
+            .expect_err("width out of range");
+        assert!(matches!(err, GenApiError::Range(name) if name == "Width"));
+        assert_eq!(nodemap.get_integer("Height", &io).unwrap(), 0);
+
+        let report = nodemap
+            .restore(&target, &io, RestoreMode::BestEffort)
+            .expect("best effort restore");
+        assert_eq!(report.applied.len(), 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(nodemap.get_integer("Height", &io).unwrap(), 480);
+    }
+
+    #[test]
+    fn transactional_restore_rolls_back_when_selector_restore_fails() {
+        let (mut nodemap, inner) = setup();
+        let current = nodemap.snapshot(&inner).unwrap();
+        let mut target = current.clone();
+        for entry in &mut target.entries {
+            match entry.name.as_str() {
+                "Height" => entry.value = FeatureValue::Integer(480),
+                "Gain" if entry.selectors == red() => entry.value = FeatureValue::Integer(9),
+                _ => {}
+            }
+        }
+        // The first GainSelector write selects Red for Gain; the second puts
+        // the selector back and fails.
+        let io = FailingWrite {
+            inner,
+            addr: 0x300,
+            nth: Cell::new(2),
+        };
+
+        let err = nodemap
+            .restore(&target, &io, RestoreMode::Transactional)
+            .expect_err("selector restore fails");
+        assert!(matches!(err, GenApiError::Io(_)));
+        assert_eq!(io.inner.peek(0x104, 4).unwrap(), vec![0; 4]);
+        assert_eq!(io.inner.peek(0x314, 2).unwrap(), vec![0; 2]);
+        assert_eq!(io.inner.peek(0x300, 2).unwrap(), vec![0; 2]);
+    }
+}
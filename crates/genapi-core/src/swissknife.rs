@@ -1,3 +1,5 @@
+//! SwissKnife expression parsing, interpretation and compilation to bytecode.
+
 use std::collections::HashSet;
 use std::fmt;
 
@@ -96,6 +98,10 @@ pub fn parse_expression(input: &str) -> Result<AstNode, ParseError> {
 ///
 /// The resolver receives variable identifiers and must return their numeric
 /// value. Returning [`EvalError::UnknownVariable`] is propagated to the caller.
+///
+/// Nodemaps run compiled [`Program`]s; this interpreter is the reference the
+/// tests and the benchmark compare them against.
+#[cfg_attr(not(feature = "bench-internals"), allow(dead_code))]
 pub fn evaluate(
     ast: &AstNode,
     vars: &mut dyn FnMut(&str) -> Result<f64, EvalError>,
@@ -113,17 +119,7 @@ pub fn evaluate(
         AstNode::Binary { op, left, right } => {
             let lhs = evaluate(left, vars)?;
             let rhs = evaluate(right, vars)?;
-            match op {
-                BinaryOp::Add => Ok(lhs + rhs),
-                BinaryOp::Sub => Ok(lhs - rhs),
-                BinaryOp::Mul => Ok(lhs * rhs),
-                BinaryOp::Div => {
-                    if rhs == 0.0 {
-                        return Err(EvalError::DivisionByZero);
-                    }
-                    Ok(lhs / rhs)
-                }
-            }
+            apply_binary(*op, lhs, rhs)
         }
     }
 }
@@ -143,6 +139,156 @@ pub fn collect_identifiers(ast: &AstNode, out: &mut HashSet<String>) {
     }
 }
 
+/// Instruction of a compiled SwissKnife [`Program`].
+///
+/// Programs run on a value stack in postfix order: operands are pushed and
+/// operators replace their operands with the result.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    /// Push a constant.
+    Const(f64),
+    /// Push the input bound to the given slot.
+    Load(usize),
+    /// Negate the top of the stack.
+    Neg,
+    /// Apply a binary operator to the two topmost values.
+    Binary(BinaryOp),
+}
+
+/// Stack depth served without a heap allocation.
+const INLINE_STACK: usize = 16;
+
+/// SwissKnife expression compiled to flat postfix bytecode.
+///
+/// Variables are replaced by input slots resolved once at compile time and
+/// sub-expressions without variables are folded into constants.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    ops: Vec<Op>,
+    max_stack: usize,
+}
+
+impl Program {
+    /// Instructions in execution order.
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    /// Value of the program when it folded down to a single constant.
+    pub fn constant(&self) -> Option<f64> {
+        match self.ops.as_slice() {
+            [Op::Const(value)] => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Execute the program with `inputs` indexed by slot.
+    pub fn run(&self, inputs: &[f64]) -> Result<f64, EvalError> {
+        if self.max_stack <= INLINE_STACK {
+            self.execute(inputs, &mut [0.0; INLINE_STACK])
+        } else {
+            self.execute(inputs, &mut vec![0.0; self.max_stack])
+        }
+    }
+
+    fn execute(&self, inputs: &[f64], stack: &mut [f64]) -> Result<f64, EvalError> {
+        let mut top = 0;
+        for op in &self.ops {
+            match *op {
+                Op::Const(value) => {
+                    stack[top] = value;
+                    top += 1;
+                }
+                Op::Load(slot) => {
+                    stack[top] = *inputs
+                        .get(slot)
+                        .ok_or_else(|| EvalError::UnknownVariable(format!("slot {slot}")))?;
+                    top += 1;
+                }
+                Op::Neg => stack[top - 1] = -stack[top - 1],
+                Op::Binary(op) => {
+                    top -= 1;
+                    stack[top - 1] = apply_binary(op, stack[top - 1], stack[top])?;
+                }
+            }
+        }
+        Ok(stack[0])
+    }
+}
+
+/// Compile `ast` into a [`Program`], mapping each variable to an input slot.
+///
+/// `slot` returns the input index of a variable name; unknown names fail with
+/// [`EvalError::UnknownVariable`]. Divisions by a constant zero are kept so
+/// they still fail at run time like the interpreted path.
+pub fn compile(ast: &AstNode, slot: &dyn Fn(&str) -> Option<usize>) -> Result<Program, EvalError> {
+    let mut ops = Vec::new();
+    emit(ast, slot, &mut ops)?;
+    let mut depth = 0usize;
+    let mut max_stack = 0usize;
+    for op in &ops {
+        match op {
+            Op::Const(_) | Op::Load(_) => depth += 1,
+            Op::Neg => {}
+            Op::Binary(_) => depth -= 1,
+        }
+        max_stack = max_stack.max(depth);
+    }
+    Ok(Program { ops, max_stack })
+}
+
+fn emit(
+    ast: &AstNode,
+    slot: &dyn Fn(&str) -> Option<usize>,
+    ops: &mut Vec<Op>,
+) -> Result<(), EvalError> {
+    match ast {
+        AstNode::Number(value) => ops.push(Op::Const(*value)),
+        AstNode::Variable(name) => {
+            let index = slot(name).ok_or_else(|| EvalError::UnknownVariable(name.clone()))?;
+            ops.push(Op::Load(index));
+        }
+        AstNode::Unary { op, expr } => {
+            emit(expr, slot, ops)?;
+            if *op == UnaryOp::Minus {
+                match ops.last_mut() {
+                    Some(Op::Const(value)) => *value = -*value,
+                    _ => ops.push(Op::Neg),
+                }
+            }
+        }
+        AstNode::Binary { op, left, right } => {
+            emit(left, slot, ops)?;
+            emit(right, slot, ops)?;
+            // In postfix order a trailing constant is a whole operand, so two
+            // trailing constants are exactly the two operands of `op`.
+            if let [.., Op::Const(lhs), Op::Const(rhs)] = ops.as_slice() {
+                if let Ok(value) = apply_binary(*op, *lhs, *rhs) {
+                    ops.truncate(ops.len() - 2);
+                    ops.push(Op::Const(value));
+                    return Ok(());
+                }
+            }
+            ops.push(Op::Binary(*op));
+        }
+    }
+    Ok(())
+}
+
+fn apply_binary(op: BinaryOp, lhs: f64, rhs: f64) -> Result<f64, EvalError> {
+    match op {
+        BinaryOp::Add => Ok(lhs + rhs),
+        BinaryOp::Sub => Ok(lhs - rhs),
+        BinaryOp::Mul => Ok(lhs * rhs),
+        BinaryOp::Div => {
+            if rhs == 0.0 {
+                return Err(EvalError::DivisionByZero);
+            }
+            Ok(lhs / rhs)
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 enum Token {
     Number(f64),
@@ -423,4 +569,45 @@ mod tests {
         let err = evaluate(&ast, &mut vars).expect_err("division by zero");
         assert!(matches!(err, EvalError::DivisionByZero));
     }
+
+    fn slots(name: &str) -> Option<usize> {
+        ["A", "B"].iter().position(|var| *var == name)
+    }
+
+    #[test]
+    fn compiled_program_matches_interpreter() {
+        for expr in ["(A + 2) * 3 - B / 4", "-A + 10 / (B - 5)", "-(A * -B) + +A"] {
+            let ast = parse_expression(expr).expect("parse");
+            let program = compile(&ast, &slots).expect("compile");
+            let mut vars = |name: &str| match name {
+                "A" => Ok(3.0),
+                "B" => Ok(7.0),
+                _ => Err(EvalError::UnknownVariable(name.to_string())),
+            };
+            let expected = evaluate(&ast, &mut vars).expect("interpret");
+            assert_eq!(program.run(&[3.0, 7.0]).expect("run"), expected, "{expr}");
+        }
+    }
+
+    #[test]
+    fn constants_are_folded() {
+        let program = compile(&parse_expression("(2 + 3) * -4").unwrap(), &slots).unwrap();
+        assert_eq!(program.constant(), Some(-20.0));
+
+        let program = compile(&parse_expression("A * (1024 / 8)").unwrap(), &slots).unwrap();
+        assert_eq!(
+            program.ops(),
+            &[Op::Load(0), Op::Const(128.0), Op::Binary(BinaryOp::Mul)]
+        );
+
+        let program = compile(&parse_expression("A + 1 / 0").unwrap(), &slots).unwrap();
+        assert!(matches!(
+            program.run(&[1.0, 0.0]),
+            Err(EvalError::DivisionByZero)
+        ));
+        assert!(matches!(
+            compile(&parse_expression("C + 1").unwrap(), &slots),
+            Err(EvalError::UnknownVariable(var)) if var == "C"
+        ));
+    }
 }
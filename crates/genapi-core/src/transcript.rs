@@ -0,0 +1,526 @@
+//! Recording and replay of register access transcripts.
+//!
+//! A transcript is a line-oriented text file with one register access per
+//! line:
+//!
+//! ```text
+//! # genapi-core transcript v1
+//! R 1520 0x00000100 4 ok 00000400
+//! W 2210 0x00000100 4 ok 00000406
+//! R 2950 0x00000300 2 err io read miss at 0x00000300
+//! ```
+//!
+//! Columns are the operation (`R`/`W`), microseconds since the recording
+//! started, the register address, the payload length, and the outcome. For
+//! successful reads the payload holds the returned bytes, for writes the bytes
+//! sent to the device. Failed accesses keep the error kind and message so the
+//! replay reproduces them.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use tracing::{debug, warn};
+
+use crate::{GenApiError, RegisterIo};
+
+const HEADER: &str = "# genapi-core transcript v1";
+
+/// Kind of register access captured in a transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TranscriptOp {
+    /// Register read.
+    Read,
+    /// Register write.
+    Write,
+}
+
+/// Outcome of a recorded register access.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptResult {
+    /// The access succeeded.
+    Ok,
+    /// The access was rejected because of the register access mode.
+    Access(String),
+    /// The transport reported a failure.
+    Io(String),
+}
+
+impl TranscriptResult {
+    fn from_error(err: &GenApiError) -> Self {
+        match err {
+            GenApiError::Access(msg) => TranscriptResult::Access(msg.clone()),
+            GenApiError::Io(msg) => TranscriptResult::Io(msg.clone()),
+            other => TranscriptResult::Io(other.to_string()),
+        }
+    }
+
+    fn to_error(&self) -> Option<GenApiError> {
+        match self {
+            TranscriptResult::Ok => None,
+            TranscriptResult::Access(msg) => Some(GenApiError::Access(msg.clone())),
+            TranscriptResult::Io(msg) => Some(GenApiError::Io(msg.clone())),
+        }
+    }
+}
+
+/// Single register access recorded in a transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptEntry {
+    /// Read or write.
+    pub op: TranscriptOp,
+    /// Microseconds elapsed since the recording started.
+    pub timestamp_us: u64,
+    /// Register address.
+    pub address: u64,
+    /// Requested length in bytes.
+    pub len: usize,
+    /// Bytes returned by a read or sent by a write (empty for failed reads).
+    pub data: Vec<u8>,
+    /// Outcome reported by the transport.
+    pub result: TranscriptResult,
+}
+
+impl TranscriptEntry {
+    fn to_line(&self) -> String {
+        let op = match self.op {
+            TranscriptOp::Read => 'R',
+            TranscriptOp::Write => 'W',
+        };
+        let mut line = format!(
+            "{op} {} 0x{:08X} {} ",
+            self.timestamp_us, self.address, self.len
+        );
+        match &self.result {
+            TranscriptResult::Ok => {
+                line.push_str("ok ");
+                line.push_str(&encode_hex(&self.data));
+            }
+            TranscriptResult::Access(msg) => {
+                let _ = write!(line, "err access {}", sanitize(msg));
+            }
+            TranscriptResult::Io(msg) => {
+                let _ = write!(line, "err io {}", sanitize(msg));
+            }
+        }
+        line
+    }
+
+    fn parse_line(line_no: usize, line: &str) -> Result<Self, GenApiError> {
+        let invalid =
+            |msg: &str| GenApiError::Parse(format!("transcript line {line_no}: {msg}: {line}"));
+        let mut parts = line.splitn(5, ' ');
+        let op = match parts.next() {
+            Some("R") => TranscriptOp::Read,
+            Some("W") => TranscriptOp::Write,
+            _ => return Err(invalid("unknown operation")),
+        };
+        let timestamp_us = parts
+            .next()
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| invalid("invalid timestamp"))?;
+        let address = parts
+            .next()
+            .and_then(|value| value.strip_prefix("0x"))
+            .and_then(|value| u64::from_str_radix(value, 16).ok())
+            .ok_or_else(|| invalid("invalid address"))?;
+        let len = parts
+            .next()
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| invalid("invalid length"))?;
+        let outcome = parts.next().ok_or_else(|| invalid("missing outcome"))?;
+        let (data, result) = if let Some(hex) = outcome.strip_prefix("ok") {
+            let data = decode_hex(hex.trim()).ok_or_else(|| invalid("invalid payload"))?;
+            (data, TranscriptResult::Ok)
+        } else if let Some(rest) = outcome.strip_prefix("err ") {
+            let (kind, msg) = rest.split_once(' ').unwrap_or((rest, ""));
+            let result = match kind {
+                "access" => TranscriptResult::Access(msg.to_string()),
+                "io" => TranscriptResult::Io(msg.to_string()),
+                _ => return Err(invalid("unknown error kind")),
+            };
+            (Vec::new(), result)
+        } else {
+            return Err(invalid("invalid outcome"));
+        };
+        Ok(TranscriptEntry {
+            op,
+            timestamp_us,
+            address,
+            len,
+            data,
+            result,
+        })
+    }
+}
+
+/// [`RegisterIo`] wrapper logging every access of the wrapped transport.
+///
+/// Each access is appended to the sink as soon as it completes so the
+/// transcript survives a crash of the application under investigation.
+/// Failures to write the transcript are logged and never affect the access.
+pub struct RecordingRegisterIo<T: RegisterIo> {
+    inner: T,
+    sink: RefCell<Box<dyn Write>>,
+    started: Instant,
+}
+
+impl<T: RegisterIo> RecordingRegisterIo<T> {
+    /// Wrap `inner` and write the transcript to `sink`.
+    pub fn new(inner: T, sink: impl Write + 'static) -> Self {
+        let mut sink: Box<dyn Write> = Box::new(sink);
+        if let Err(err) = writeln!(sink, "{HEADER}") {
+            warn!(error = %err, "failed to write transcript header");
+        }
+        Self {
+            inner,
+            sink: RefCell::new(sink),
+            started: Instant::now(),
+        }
+    }
+
+    /// Wrap `inner` and write the transcript to a newly created file.
+    pub fn create(inner: T, path: impl AsRef<Path>) -> Result<Self, GenApiError> {
+        let path = path.as_ref();
+        let file = File::create(path).map_err(|err| {
+            GenApiError::Io(format!("create transcript {}: {err}", path.display()))
+        })?;
+        debug!(path = %path.display(), "recording register transcript");
+        Ok(Self::new(inner, BufWriter::new(file)))
+    }
+
+    /// Return a reference to the wrapped transport.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Flush the transcript and return the wrapped transport.
+    pub fn into_inner(self) -> T {
+        if let Err(err) = self.sink.borrow_mut().flush() {
+            warn!(error = %err, "failed to flush transcript");
+        }
+        self.inner
+    }
+
+    fn record(
+        &self,
+        op: TranscriptOp,
+        address: u64,
+        len: usize,
+        data: &[u8],
+        err: Option<&GenApiError>,
+    ) {
+        let entry = TranscriptEntry {
+            op,
+            timestamp_us: self.started.elapsed().as_micros() as u64,
+            address,
+            len,
+            data: if err.is_some() {
+                Vec::new()
+            } else {
+                data.to_vec()
+            },
+            result: err.map_or(TranscriptResult::Ok, TranscriptResult::from_error),
+        };
+        let mut sink = self.sink.borrow_mut();
+        if let Err(err) = writeln!(sink, "{}", entry.to_line()).and_then(|_| sink.flush()) {
+            warn!(error = %err, "failed to append to transcript");
+        }
+    }
+}
+
+impl<T: RegisterIo> RegisterIo for RecordingRegisterIo<T> {
+    fn read(&self, addr: u64, len: usize) -> Result<Vec<u8>, GenApiError> {
+        let result = self.inner.read(addr, len);
+        match &result {
+            Ok(data) => self.record(TranscriptOp::Read, addr, len, data, None),
+            Err(err) => self.record(TranscriptOp::Read, addr, len, &[], Some(err)),
+        }
+        result
+    }
+
+    fn write(&self, addr: u64, data: &[u8]) -> Result<(), GenApiError> {
+        let result = self.inner.write(addr, data);
+        self.record(
+            TranscriptOp::Write,
+            addr,
+            data.len(),
+            data,
+            result.as_ref().err(),
+        );
+        result
+    }
+}
+
+/// Strategy used by [`ReplayRegisterIo`] to match accesses to the transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplayMode {
+    /// Accesses must occur in exactly the recorded order; any deviation fails.
+    #[default]
+    Strict,
+    /// Accesses are matched per address. Reads serve the recorded values in
+    /// order and repeat the last one once exhausted; unrecorded writes succeed.
+    ByAddress,
+}
+
+/// [`RegisterIo`] implementation serving a recorded transcript.
+#[derive(Debug)]
+pub struct ReplayRegisterIo {
+    mode: ReplayMode,
+    entries: Vec<TranscriptEntry>,
+    cursor: Cell<usize>,
+    by_address: RefCell<HashMap<(TranscriptOp, u64), VecDeque<usize>>>,
+    last_read: RefCell<HashMap<u64, usize>>,
+}
+
+impl ReplayRegisterIo {
+    /// Build a strict replay from parsed entries.
+    pub fn new(entries: Vec<TranscriptEntry>) -> Self {
+        let mut by_address: HashMap<_, VecDeque<usize>> = HashMap::new();
+        for (idx, entry) in entries.iter().enumerate() {
+            by_address
+                .entry((entry.op, entry.address))
+                .or_default()
+                .push_back(idx);
+        }
+        Self {
+            mode: ReplayMode::Strict,
+            entries,
+            cursor: Cell::new(0),
+            by_address: RefCell::new(by_address),
+            last_read: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Parse a transcript from its textual representation.
+    pub fn parse(text: &str) -> Result<Self, GenApiError> {
+        let mut entries = Vec::new();
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            entries.push(TranscriptEntry::parse_line(idx + 1, line)?);
+        }
+        Ok(Self::new(entries))
+    }
+
+    /// Load a transcript file written by [`RecordingRegisterIo`].
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, GenApiError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| GenApiError::Io(format!("read transcript {}: {err}", path.display())))?;
+        Self::parse(&text)
+    }
+
+    /// Select how accesses are matched against the transcript.
+    pub fn with_mode(mut self, mode: ReplayMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Recorded entries in transcript order.
+    pub fn entries(&self) -> &[TranscriptEntry] {
+        &self.entries
+    }
+
+    /// Number of entries not yet consumed in strict mode.
+    pub fn remaining(&self) -> usize {
+        self.entries.len() - self.cursor.get()
+    }
+
+    fn next_strict(
+        &self,
+        op: TranscriptOp,
+        addr: u64,
+        len: usize,
+    ) -> Result<&TranscriptEntry, GenApiError> {
+        let idx = self.cursor.get();
+        let entry = self.entries.get(idx).ok_or_else(|| {
+            GenApiError::Io(format!(
+                "transcript exhausted at {op:?} 0x{addr:08X} ({len} bytes)"
+            ))
+        })?;
+        if entry.op != op || entry.address != addr || entry.len != len {
+            return Err(GenApiError::Io(format!(
+                "transcript mismatch at entry {idx}: expected {:?} 0x{:08X} ({} bytes), got {op:?} 0x{addr:08X} ({len} bytes)",
+                entry.op, entry.address, entry.len
+            )));
+        }
+        self.cursor.set(idx + 1);
+        Ok(entry)
+    }
+
+    fn next_by_address(&self, op: TranscriptOp, addr: u64) -> Option<&TranscriptEntry> {
+        let idx = self
+            .by_address
+            .borrow_mut()
+            .get_mut(&(op, addr))
+            .and_then(VecDeque::pop_front);
+        match (op, idx) {
+            (TranscriptOp::Read, Some(idx)) => {
+                self.last_read.borrow_mut().insert(addr, idx);
+                self.entries.get(idx)
+            }
+            (TranscriptOp::Read, None) => {
+                let idx = *self.last_read.borrow().get(&addr)?;
+                self.entries.get(idx)
+            }
+            (TranscriptOp::Write, idx) => idx.and_then(|idx| self.entries.get(idx)),
+        }
+    }
+}
+
+impl RegisterIo for ReplayRegisterIo {
+    fn read(&self, addr: u64, len: usize) -> Result<Vec<u8>, GenApiError> {
+        let entry = match self.mode {
+            ReplayMode::Strict => self.next_strict(TranscriptOp::Read, addr, len)?,
+            ReplayMode::ByAddress => self
+                .next_by_address(TranscriptOp::Read, addr)
+                .ok_or_else(|| GenApiError::Io(format!("read miss at 0x{addr:08X}")))?,
+        };
+        if let Some(err) = entry.result.to_error() {
+            return Err(err);
+        }
+        if entry.data.len() != len {
+            return Err(GenApiError::Io(format!(
+                "length mismatch at 0x{addr:08X}: expected {len}, have {}",
+                entry.data.len()
+            )));
+        }
+        Ok(entry.data.clone())
+    }
+
+    fn write(&self, addr: u64, data: &[u8]) -> Result<(), GenApiError> {
+        let entry = match self.mode {
+            ReplayMode::Strict => self.next_strict(TranscriptOp::Write, addr, data.len())?,
+            ReplayMode::ByAddress => match self.next_by_address(TranscriptOp::Write, addr) {
+                Some(entry) => entry,
+                None => return Ok(()),
+            },
+        };
+        if let Some(err) = entry.result.to_error() {
+            return Err(err);
+        }
+        if self.mode == ReplayMode::Strict && entry.data != data {
+            return Err(GenApiError::Io(format!(
+                "transcript write mismatch at 0x{addr:08X}: expected {}, got {}",
+                encode_hex(&entry.data),
+                encode_hex(data)
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn sanitize(msg: &str) -> String {
+    msg.replace(['\r', '\n'], " ")
+}
+
+fn encode_hex(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() * 2);
+    for byte in data {
+        let _ = write!(out, "{byte:02X}");
+    }
+    out
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    text.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let digits = std::str::from_utf8(pair).ok().filter(|_| pair.len() == 2)?;
+            u8::from_str_radix(digits, 16).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemoryRegisterIo, NodeMap};
+    use std::rc::Rc;
+
+    const FIXTURE: &str = r#"
+        <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+            <Integer Name="Width">
+                <Address>0x100</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>16</Min>
+                <Max>4096</Max>
+            </Integer>
+        </RegisterDescription>
+    "#;
+
+    #[derive(Clone, Default)]
+    struct SharedSink(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedSink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn record_session() -> String {
+        let model = genapi_xml::parse(FIXTURE).expect("parse fixture");
+        let sink = SharedSink::default();
+        let io =
+            RecordingRegisterIo::new(MemoryRegisterIo::from_model(&model).unwrap(), sink.clone());
+        let mut nodemap = NodeMap::try_from_xml(model).expect("build nodemap");
+        assert_eq!(nodemap.get_integer("Width", &io).unwrap(), 16);
+        nodemap.set_integer("Width", 640, &io).unwrap();
+        assert!(io.read(0x9000, 4).is_err());
+        let text = String::from_utf8(sink.0.borrow().clone()).unwrap();
+        text
+    }
+
+    #[test]
+    fn recording_captures_reads_writes_and_errors() {
+        let text = record_session();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines[0], HEADER);
+        assert!(lines[1].starts_with("R ") && lines[1].ends_with("0x00000100 4 ok 00000010"));
+        assert!(lines[2].starts_with("W ") && lines[2].ends_with("0x00000100 4 ok 00000280"));
+        assert!(lines[3].ends_with("0x00009000 4 err io read miss at 0x00009000"));
+    }
+
+    #[test]
+    fn strict_replay_reproduces_session() {
+        let replay = ReplayRegisterIo::parse(&record_session()).expect("parse transcript");
+        let model = genapi_xml::parse(FIXTURE).expect("parse fixture");
+        let mut nodemap = NodeMap::try_from_xml(model).expect("build nodemap");
+        assert_eq!(nodemap.get_integer("Width", &replay).unwrap(), 16);
+        nodemap.set_integer("Width", 640, &replay).unwrap();
+        let err = replay.read(0x9000, 4).unwrap_err();
+        assert!(matches!(err, GenApiError::Io(msg) if msg.contains("read miss")));
+        assert_eq!(replay.remaining(), 0);
+    }
+
+    #[test]
+    fn strict_replay_rejects_deviation() {
+        let replay = ReplayRegisterIo::parse(&record_session()).expect("parse transcript");
+        replay.read(0x100, 4).unwrap();
+        let err = replay.write(0x100, &[0, 0, 0, 1]).unwrap_err();
+        assert!(matches!(err, GenApiError::Io(msg) if msg.contains("write mismatch")));
+    }
+
+    #[test]
+    fn by_address_replay_repeats_last_read() {
+        let replay = ReplayRegisterIo::parse(&record_session())
+            .expect("parse transcript")
+            .with_mode(ReplayMode::ByAddress);
+        replay.write(0x200, &[1]).unwrap();
+        assert_eq!(replay.read(0x100, 4).unwrap(), vec![0, 0, 0, 0x10]);
+        assert_eq!(replay.read(0x100, 4).unwrap(), vec![0, 0, 0, 0x10]);
+    }
+}
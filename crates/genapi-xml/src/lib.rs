@@ -162,6 +162,95 @@ impl SkOutput {
     }
 }
 
+/// How a numeric value should be presented to and parsed from users.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Representation {
+    /// Plain number on a linear scale (slider).
+    Linear,
+    /// Plain number on a logarithmic scale.
+    Logarithmic,
+    /// Zero or one shown as a checkbox.
+    Boolean,
+    /// Plain number without slider semantics.
+    PureNumber,
+    /// Number shown in hexadecimal notation.
+    HexNumber,
+    /// IPv4 address stored as a 32-bit integer.
+    IPV4Address,
+    /// MAC address stored as a 48-bit integer.
+    MACAddress,
+}
+
+impl Representation {
+    fn parse(tag: &str) -> Option<Self> {
+        match tag.trim() {
+            "Linear" => Some(Representation::Linear),
+            "Logarithmic" => Some(Representation::Logarithmic),
+            "Boolean" => Some(Representation::Boolean),
+            "PureNumber" => Some(Representation::PureNumber),
+            "HexNumber" => Some(Representation::HexNumber),
+            "IPV4Address" => Some(Representation::IPV4Address),
+            "MACAddress" => Some(Representation::MACAddress),
+            _ => None,
+        }
+    }
+}
+
+/// Notation used when formatting floating point values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayNotation {
+    /// Pick fixed or scientific notation depending on magnitude.
+    #[default]
+    Automatic,
+    /// Always use fixed-point notation.
+    Fixed,
+    /// Always use scientific notation.
+    Scientific,
+}
+
+impl DisplayNotation {
+    fn parse(tag: &str) -> Option<Self> {
+        match tag.trim() {
+            "Automatic" => Some(DisplayNotation::Automatic),
+            "Fixed" => Some(DisplayNotation::Fixed),
+            "Scientific" => Some(DisplayNotation::Scientific),
+            _ => None,
+        }
+    }
+}
+
+/// Presentation metadata attached to numeric features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DisplayHints {
+    /// Declared `<Representation>`.
+    pub representation: Option<Representation>,
+    /// Declared `<DisplayNotation>` (floats only).
+    pub notation: DisplayNotation,
+    /// Declared `<DisplayPrecision>` (floats only).
+    pub precision: Option<u32>,
+}
+
+impl DisplayHints {
+    fn note(&mut self, tag: &[u8], text: &str) -> Result<(), XmlError> {
+        match tag {
+            b"Representation" => match Representation::parse(text) {
+                Some(repr) => self.representation = Some(repr),
+                None => warn!(value = text.trim(), "ignoring unknown <Representation>"),
+            },
+            b"DisplayNotation" => match DisplayNotation::parse(text) {
+                Some(notation) => self.notation = notation,
+                None => warn!(value = text.trim(), "ignoring unknown <DisplayNotation>"),
+            },
+            b"DisplayPrecision" => {
+                let precision = parse_u64(text)?;
+                self.precision = u32::try_from(precision).ok();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
 /// Declaration of a SwissKnife node consisting of an arithmetic expression.
 #[derive(Debug, Clone)]
 pub struct SwissKnifeDecl {
@@ -196,6 +285,8 @@ pub enum NodeDecl {
         inc: Option<i64>,
         /// Engineering unit (if provided).
         unit: Option<String>,
+        /// Presentation hints (`<Representation>`).
+        display: DisplayHints,
         /// Optional bitfield metadata describing the active bit range.
         bitfield: Option<BitField>,
         /// Selector nodes referencing this feature.
@@ -211,6 +302,9 @@ pub enum NodeDecl {
         min: f64,
         max: f64,
         unit: Option<String>,
+        /// Presentation hints (`<Representation>`, `<DisplayNotation>`,
+        /// `<DisplayPrecision>`).
+        display: DisplayHints,
         /// Optional rational scale applied to the raw register value.
         scale: Option<(i64, i64)>,
         /// Optional additive offset applied after scaling.
@@ -291,6 +385,7 @@ where
 }
 
 /// Parse a GenICam XML snippet and collect minimal metadata.
+#[allow(clippy::collapsible_match)]
 pub fn parse_into_minimal_nodes(xml: &str) -> Result<MinimalXmlInfo, XmlError> {
     let mut reader = Reader::from_str(xml);
     reader.trim_text(true);
@@ -769,6 +864,7 @@ fn parse_integer(reader: &mut Reader<&[u8]>, start: BytesStart<'_>) -> Result<No
     let mut max = None;
     let mut inc = None;
     let mut unit = None;
+    let mut display = DisplayHints::default();
     let mut selectors = Vec::new();
     let mut selected_if: Vec<(String, Vec<String>)> = Vec::new();
     let mut last_selector: Option<usize> = None;
@@ -836,6 +932,11 @@ fn parse_integer(reader: &mut Reader<&[u8]>, start: BytesStart<'_>) -> Result<No
                         unit = Some(trimmed.to_string());
                     }
                 }
+                b"Representation" | b"DisplayNotation" | b"DisplayPrecision" => {
+                    let tag = e.name().as_ref().to_vec();
+                    let text = read_text_start(reader, e)?;
+                    display.note(&tag, &text)?;
+                }
                 TAG_LSB => {
                     let text = read_text_start(reader, e)?;
                     let value = parse_u64(&text)?;
@@ -1052,6 +1153,7 @@ fn parse_integer(reader: &mut Reader<&[u8]>, start: BytesStart<'_>) -> Result<No
         max,
         inc,
         unit,
+        display,
         bitfield,
         selectors,
         selected_if,
@@ -1074,6 +1176,7 @@ fn parse_float(reader: &mut Reader<&[u8]>, start: BytesStart<'_>) -> Result<Node
     let mut min = None;
     let mut max = None;
     let mut unit = None;
+    let mut display = DisplayHints::default();
     let mut scale_num: Option<i64> = None;
     let mut scale_den: Option<i64> = None;
     let mut offset = None;
@@ -1124,6 +1227,11 @@ fn parse_float(reader: &mut Reader<&[u8]>, start: BytesStart<'_>) -> Result<Node
                         unit = Some(trimmed.to_string());
                     }
                 }
+                b"Representation" | b"DisplayNotation" | b"DisplayPrecision" => {
+                    let tag = e.name().as_ref().to_vec();
+                    let text = read_text_start(reader, e)?;
+                    display.note(&tag, &text)?;
+                }
                 b"Scale" => {
                     let text = read_text_start(reader, e)?;
                     let (num, den) = parse_scale(&text)?;
@@ -1275,6 +1383,7 @@ fn parse_float(reader: &mut Reader<&[u8]>, start: BytesStart<'_>) -> Result<Node
         min,
         max,
         unit,
+        display,
         scale,
         offset,
         selectors,
@@ -2214,6 +2323,7 @@ fn parse_f64(value: &str) -> Result<f64, XmlError> {
         .map_err(|err| XmlError::Invalid(format!("invalid float: {err}")))
 }
 
+#[allow(clippy::collapsible_match)]
 fn skip_element(reader: &mut Reader<&[u8]>, name: &[u8]) -> Result<(), XmlError> {
     let mut depth = 1usize;
     let mut buf = Vec::new();
@@ -2513,6 +2623,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_display_hints() {
+        const XML: &str = r#"
+            <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+                <Integer Name="GevCurrentIPAddress">
+                    <Address>0x0024</Address>
+                    <Length>4</Length>
+                    <AccessMode>RO</AccessMode>
+                    <Min>0</Min>
+                    <Max>4294967295</Max>
+                    <Representation>IPV4Address</Representation>
+                </Integer>
+                <Float Name="ExposureTime">
+                    <Address>0x1000</Address>
+                    <Length>4</Length>
+                    <AccessMode>RW</AccessMode>
+                    <Min>10.0</Min>
+                    <Max>1000000.0</Max>
+                    <Unit>us</Unit>
+                    <Representation>Logarithmic</Representation>
+                    <DisplayNotation>Fixed</DisplayNotation>
+                    <DisplayPrecision>2</DisplayPrecision>
+                </Float>
+            </RegisterDescription>
+        "#;
+
+        let model = parse(XML).expect("parse display hints");
+        match &model.nodes[0] {
+            NodeDecl::Integer { display, .. } => {
+                assert_eq!(display.representation, Some(Representation::IPV4Address));
+                assert_eq!(display.precision, None);
+            }
+            other => panic!("unexpected node: {other:?}"),
+        }
+        match &model.nodes[1] {
+            NodeDecl::Float { display, .. } => {
+                assert_eq!(
+                    *display,
+                    DisplayHints {
+                        representation: Some(Representation::Logarithmic),
+                        notation: DisplayNotation::Fixed,
+                        precision: Some(2),
+                    }
+                );
+            }
+            other => panic!("unexpected node: {other:?}"),
+        }
+    }
+
     #[test]
     fn parse_enum_entry_with_pvalue() {
         const XML: &str = r#"
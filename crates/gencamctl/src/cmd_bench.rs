@@ -13,12 +13,11 @@ use genicam::gige::gvsp::{self, GvspPacket};
 use genicam::pfnc::PixelFormat;
 use genicam::{parse_chunk_bytes, Frame, StreamBuilder, StreamDest};
 
-use crate::common::{self, DEFAULT_DISCOVERY_TIMEOUT_MS};
+use crate::common::{self, DeviceTarget, DEFAULT_DISCOVERY_TIMEOUT_MS};
 
 #[derive(Debug, Clone)]
 pub struct BenchArgs {
-    pub ip: Option<Ipv4Addr>,
-    pub index: Option<usize>,
+    pub target: DeviceTarget,
     pub iface: Option<Ipv4Addr>,
     pub mode: String,
     pub group: Option<Ipv4Addr>,
@@ -53,14 +52,12 @@ pub async fn run(args: BenchArgs, emit_json: bool) -> Result<()> {
         .iface
         .ok_or_else(|| anyhow!("bench requires --iface or global --iface"))?;
     let timeout = Duration::from_millis(DEFAULT_DISCOVERY_TIMEOUT_MS);
-    let device = common::select_device(args.ip, args.index, Some(iface_ip), timeout).await?;
+    let device = common::select_device(&args.target, Some(iface_ip), timeout).await?;
     info!(ip = %device.ip, "opening camera for benchmark");
     let mut camera = common::open_camera(&device)
         .await
         .context("open camera for bench")?;
-    let mut stream_device = common::open_stream_device(&device)
-        .await
-        .context("open control channel for bench")?;
+    let mut stream_device = common::stream_device(&camera);
 
     let iface = common::resolve_iface(Some(iface_ip))?
         .ok_or_else(|| anyhow!("failed to resolve capture interface"))?;
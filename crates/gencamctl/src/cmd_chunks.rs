@@ -5,7 +5,7 @@ use anyhow::{Context, Result};
 use serde::Serialize;
 use tracing::info;
 
-use crate::common::{self, DEFAULT_DISCOVERY_TIMEOUT_MS};
+use crate::common::{self, DeviceTarget, DEFAULT_DISCOVERY_TIMEOUT_MS};
 
 #[derive(Serialize)]
 struct ChunkStatus {
@@ -22,8 +22,7 @@ fn parse_selectors(csv: &str) -> Vec<String> {
 }
 
 pub async fn run(
-    ip: Option<Ipv4Addr>,
-    index: Option<usize>,
+    target: DeviceTarget,
     enable: bool,
     selectors: String,
     iface: Option<Ipv4Addr>,
@@ -31,7 +30,7 @@ pub async fn run(
 ) -> Result<()> {
     let selected = parse_selectors(&selectors);
     let timeout = Duration::from_millis(DEFAULT_DISCOVERY_TIMEOUT_MS);
-    let device = common::select_device(ip, index, iface, timeout).await?;
+    let device = common::select_device(&target, iface, timeout).await?;
     info!(ip = %device.ip, enable, "configuring chunk mode");
     let mut camera = common::open_camera(&device)
         .await
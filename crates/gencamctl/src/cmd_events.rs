@@ -5,7 +5,7 @@ use anyhow::{Context, Result};
 use serde::Serialize;
 use tracing::{info, warn};
 
-use crate::common::{self, DEFAULT_DISCOVERY_TIMEOUT_MS};
+use crate::common::{self, DeviceTarget, DEFAULT_DISCOVERY_TIMEOUT_MS};
 
 #[derive(Serialize)]
 struct EventRecord {
@@ -25,8 +25,7 @@ fn parse_events(csv: &str) -> Vec<String> {
 }
 
 pub async fn run(
-    ip: Option<Ipv4Addr>,
-    index: Option<usize>,
+    target: DeviceTarget,
     iface: Ipv4Addr,
     port: u16,
     enable: String,
@@ -34,7 +33,7 @@ pub async fn run(
     json: bool,
 ) -> Result<()> {
     let timeout = Duration::from_millis(DEFAULT_DISCOVERY_TIMEOUT_MS);
-    let device = common::select_device(ip, index, Some(iface), timeout).await?;
+    let device = common::select_device(&target, Some(iface), timeout).await?;
     info!(ip = %device.ip, port, "configuring events");
     let mut camera = common::open_camera(&device)
         .await
@@ -5,7 +5,7 @@ use anyhow::{Context, Result};
 use serde::Serialize;
 use tracing::info;
 
-use crate::common::{self, DEFAULT_DISCOVERY_TIMEOUT_MS};
+use crate::common::{self, DeviceTarget, DEFAULT_DISCOVERY_TIMEOUT_MS};
 
 #[derive(Serialize)]
 struct FeatureValue<'a> {
@@ -14,14 +14,13 @@ struct FeatureValue<'a> {
 }
 
 pub async fn run(
-    ip: Option<Ipv4Addr>,
-    index: Option<usize>,
+    target: DeviceTarget,
     name: String,
     iface: Option<Ipv4Addr>,
     json: bool,
 ) -> Result<()> {
     let timeout = Duration::from_millis(DEFAULT_DISCOVERY_TIMEOUT_MS);
-    let device = common::select_device(ip, index, iface, timeout).await?;
+    let device = common::select_device(&target, iface, timeout).await?;
     info!(ip = %device.ip, "opening camera for get");
     let camera = common::open_camera(&device)
         .await
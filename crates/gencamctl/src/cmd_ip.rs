@@ -0,0 +1,191 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tl_gige::gvcp::{ControlAccess, GigeDevice};
+use tl_gige::ipconfig::{self, IpConfig, IpSettings};
+use tl_gige::nic::Iface;
+use tl_gige::GVCP_PORT;
+use tracing::info;
+
+use crate::common::{self, DeviceTarget, DEFAULT_DISCOVERY_TIMEOUT_MS};
+
+#[derive(Serialize)]
+struct AddressEntry {
+    ip: String,
+    subnet: String,
+    gateway: String,
+}
+
+impl From<IpSettings> for AddressEntry {
+    fn from(settings: IpSettings) -> Self {
+        Self {
+            ip: settings.ip.to_string(),
+            subnet: settings.subnet.to_string(),
+            gateway: settings.gateway.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct IpReport {
+    mac: String,
+    current: AddressEntry,
+    persistent: AddressEntry,
+    persistent_enabled: bool,
+    dhcp_enabled: bool,
+    lla_enabled: bool,
+}
+
+async fn open_control(target: &DeviceTarget, iface: Option<Ipv4Addr>) -> Result<GigeDevice> {
+    let timeout = Duration::from_millis(DEFAULT_DISCOVERY_TIMEOUT_MS);
+    let device = common::select_device(target, iface, timeout).await?;
+    let addr = SocketAddr::new(IpAddr::V4(device.ip), GVCP_PORT);
+    GigeDevice::open_with_access(addr, ControlAccess::Control)
+        .await
+        .with_context(|| format!("acquire control of {}", device.ip))
+}
+
+/// Give up control of the device whether or not `result` succeeded.
+///
+/// The error of `result` takes precedence over a failed release.
+async fn release<T>(control: &mut GigeDevice, result: Result<T>) -> Result<T> {
+    let released = control.release_control().await.context("release control");
+    let value = result?;
+    released?;
+    Ok(value)
+}
+
+pub async fn show(target: DeviceTarget, iface: Option<Ipv4Addr>, json: bool) -> Result<()> {
+    let timeout = Duration::from_millis(DEFAULT_DISCOVERY_TIMEOUT_MS);
+    let device = common::select_device(&target, iface, timeout).await?;
+    let addr = SocketAddr::new(IpAddr::V4(device.ip), GVCP_PORT);
+    let mut control = GigeDevice::open(addr)
+        .await
+        .with_context(|| format!("connect GVCP control channel at {}", device.ip))?;
+    let current = control.current_ip().await.context("read current IP")?;
+    let persistent = control
+        .persistent_ip()
+        .await
+        .context("read persistent IP")?;
+    let config = control.ip_config().await.context("read IP configuration")?;
+
+    if json {
+        let report = IpReport {
+            mac: common::format_mac(&device.mac),
+            current: current.into(),
+            persistent: persistent.into(),
+            persistent_enabled: config.persistent,
+            dhcp_enabled: config.dhcp,
+            lla_enabled: config.lla,
+        };
+        common::print_json(&report)?;
+        return Ok(());
+    }
+
+    let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+    println!("MAC:        {}", common::format_mac(&device.mac));
+    println!(
+        "Current:    {} / {} via {}",
+        current.ip, current.subnet, current.gateway
+    );
+    println!(
+        "Persistent: {} / {} via {}",
+        persistent.ip, persistent.subnet, persistent.gateway
+    );
+    println!(
+        "Modes:      persistent {}, DHCP {}, LLA {}",
+        on_off(config.persistent),
+        on_off(config.dhcp),
+        on_off(config.lla)
+    );
+    Ok(())
+}
+
+pub async fn force(
+    mac: String,
+    address: Ipv4Addr,
+    subnet: Ipv4Addr,
+    gateway: Ipv4Addr,
+    iface: Option<Ipv4Addr>,
+) -> Result<()> {
+    let mac = common::parse_mac(&mac)?;
+    info!(mac = %common::format_mac(&mac), %address, "forcing IP address");
+    if let Some(ip) = iface {
+        let iface = Iface::from_ipv4(ip).context("resolve interface from IPv4 address")?;
+        ipconfig::force_ip_on_interface(iface.name(), mac, address, subnet, gateway)
+            .await
+            .context("FORCEIP")?;
+    } else {
+        ipconfig::force_ip(mac, address, subnet, gateway)
+            .await
+            .context("FORCEIP")?;
+    }
+    println!(
+        "{} now answers at {address}/{subnet} (until the next reset)",
+        common::format_mac(&mac)
+    );
+    Ok(())
+}
+
+pub async fn set_persistent(
+    target: DeviceTarget,
+    address: Ipv4Addr,
+    subnet: Ipv4Addr,
+    gateway: Ipv4Addr,
+    iface: Option<Ipv4Addr>,
+) -> Result<()> {
+    let mut control = open_control(&target, iface).await?;
+    let result = async {
+        control
+            .set_persistent_ip(IpSettings {
+                ip: address,
+                subnet,
+                gateway,
+            })
+            .await
+            .context("write persistent IP")?;
+        let config = control.ip_config().await.context("read IP configuration")?;
+        control
+            .set_ip_config(IpConfig {
+                persistent: true,
+                ..config
+            })
+            .await
+            .context("enable persistent IP")
+    }
+    .await;
+    release(&mut control, result).await?;
+    println!("Persistent IP set to {address}/{subnet} via {gateway}; reset the device to apply");
+    Ok(())
+}
+
+pub async fn set_mode(
+    target: DeviceTarget,
+    persistent: Option<bool>,
+    dhcp: Option<bool>,
+    iface: Option<Ipv4Addr>,
+) -> Result<()> {
+    let mut control = open_control(&target, iface).await?;
+    let result = async {
+        let current = control.ip_config().await.context("read IP configuration")?;
+        let config = IpConfig {
+            persistent: persistent.unwrap_or(current.persistent),
+            dhcp: dhcp.unwrap_or(current.dhcp),
+            ..current
+        };
+        control
+            .set_ip_config(config)
+            .await
+            .context("write IP configuration")?;
+        Ok(config)
+    }
+    .await;
+    let config = release(&mut control, result).await?;
+    println!(
+        "IP configuration: persistent {}, DHCP {}; reset the device to apply",
+        config.persistent, config.dhcp
+    );
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use genicam::genapi::{self, LintSeverity};
+use serde::Serialize;
+use tracing::info;
+
+use crate::common::{self, DeviceTarget, DEFAULT_DISCOVERY_TIMEOUT_MS};
+
+#[derive(Serialize)]
+struct FindingOut {
+    severity: String,
+    kind: String,
+    node: String,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct LintReport {
+    errors: usize,
+    warnings: usize,
+    findings: Vec<FindingOut>,
+}
+
+pub async fn run(
+    xml: Option<PathBuf>,
+    target: DeviceTarget,
+    iface: Option<Ipv4Addr>,
+    json: bool,
+) -> Result<()> {
+    let text = if let Some(path) = xml {
+        std::fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?
+    } else {
+        let timeout = Duration::from_millis(DEFAULT_DISCOVERY_TIMEOUT_MS);
+        let device = common::select_device(&target, iface, timeout).await?;
+        info!(ip = %device.ip, "fetching GenApi XML for lint");
+        common::load_device_xml(&device).await?
+    };
+    let model = genapi_xml::parse(&text).context("parse GenApi XML")?;
+    let findings = genapi::lint(&model);
+    let errors = findings
+        .iter()
+        .filter(|finding| finding.severity == LintSeverity::Error)
+        .count();
+    let warnings = findings.len() - errors;
+
+    if json {
+        let report = LintReport {
+            errors,
+            warnings,
+            findings: findings
+                .iter()
+                .map(|finding| FindingOut {
+                    severity: finding.severity.to_string(),
+                    kind: format!("{:?}", finding.kind),
+                    node: finding.node.clone(),
+                    message: finding.message.clone(),
+                })
+                .collect(),
+        };
+        common::print_json(&report)?;
+    } else {
+        for finding in &findings {
+            println!("{finding}");
+        }
+        println!("{errors} error(s), {warnings} warning(s)");
+    }
+
+    if errors > 0 {
+        bail!("nodemap lint found {errors} error(s)");
+    }
+    Ok(())
+}
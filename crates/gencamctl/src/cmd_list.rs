@@ -14,6 +14,10 @@ struct DeviceEntry {
     mac: String,
     manufacturer: Option<String>,
     model: Option<String>,
+    serial_number: Option<String>,
+    user_name: Option<String>,
+    interface: Option<String>,
+    reachable: bool,
 }
 
 pub async fn run(timeout_ms: u64, iface: Option<Ipv4Addr>, json: bool) -> Result<()> {
@@ -31,6 +35,10 @@ pub async fn run(timeout_ms: u64, iface: Option<Ipv4Addr>, json: bool) -> Result
                 mac: common::format_mac(&dev.mac),
                 manufacturer: dev.manufacturer.clone(),
                 model: dev.model.clone(),
+                serial_number: dev.serial_number.clone(),
+                user_name: dev.user_name.clone(),
+                interface: dev.interface.clone(),
+                reachable: dev.reachable,
             })
             .collect();
         common::print_json(&entries)?;
@@ -43,16 +51,29 @@ pub async fn run(timeout_ms: u64, iface: Option<Ipv4Addr>, json: bool) -> Result
     }
 
     println!(
-        "{:<6} {:<16} {:<18} {:<20} Model",
-        "INDEX", "IP", "MAC", "Manufacturer"
+        "{:<6} {:<16} {:<18} {:<20} {:<24} {:<12} Name",
+        "INDEX", "IP", "MAC", "Manufacturer", "Model", "Serial"
     );
     for (idx, dev) in devices.iter().enumerate() {
         println!(
-            "{idx:<6} {:<16} {:<18} {:<20} {}",
+            "{idx:<6} {:<16} {:<18} {:<20} {:<24} {:<12} {}",
             dev.ip,
             common::format_mac(&dev.mac),
             dev.manufacturer.as_deref().unwrap_or("-"),
             dev.model.as_deref().unwrap_or("-"),
+            dev.serial_number.as_deref().unwrap_or("-"),
+            dev.user_name.as_deref().unwrap_or("-"),
+        );
+    }
+    let unreachable: Vec<_> = devices.iter().filter(|dev| !dev.reachable).collect();
+    for dev in unreachable {
+        println!(
+            "warning: {} ({}) is outside the subnet of {}; fix it with `gencamctl ip force`",
+            dev.ip,
+            common::format_mac(&dev.mac),
+            dev.interface
+                .as_deref()
+                .unwrap_or("the receiving interface"),
         );
     }
 
@@ -2,7 +2,6 @@ use std::fs::File;
 use std::io::Write;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
-use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
 use anyhow::{anyhow, bail, Context, Result};
@@ -14,12 +13,13 @@ use std::convert::TryInto;
 use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
 use tl_gige::discover_on_interface;
+use tl_gige::discovery::{self, DeviceSelector};
 use tl_gige::gvcp::GigeDevice;
 use tl_gige::nic::Iface;
+use tl_gige::ControlHandle;
 use tl_gige::DeviceInfo;
-use tl_gige::{discover, GVCP_PORT};
+use tl_gige::{discover, discover_unicast, GVCP_PORT};
 use tokio::runtime::Handle;
-use tokio::sync::Mutex;
 
 pub const DEFAULT_DISCOVERY_TIMEOUT_MS: u64 = 500;
 
@@ -30,6 +30,11 @@ pub fn format_mac(mac: &[u8; 6]) -> String {
         .join(":")
 }
 
+pub fn parse_mac(text: &str) -> Result<[u8; 6]> {
+    discovery::parse_mac(text)
+        .ok_or_else(|| anyhow!("invalid MAC address '{text}': expected six hex octets"))
+}
+
 pub async fn discover_devices(
     timeout: Duration,
     iface_ip: Option<Ipv4Addr>,
@@ -45,49 +50,58 @@ pub async fn discover_devices(
     Ok(devices)
 }
 
+/// How the user picked the camera on the command line.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceTarget {
+    pub ip: Option<Ipv4Addr>,
+    pub index: Option<usize>,
+    pub device: Option<DeviceSelector>,
+}
+
 pub async fn select_device(
-    ip: Option<Ipv4Addr>,
-    index: Option<usize>,
+    target: &DeviceTarget,
     iface_ip: Option<Ipv4Addr>,
     timeout: Duration,
 ) -> Result<DeviceInfo> {
-    match (ip, index) {
-        (Some(ip), None) => {
-            let mut devices = discover_devices(timeout, iface_ip).await?;
-            if let Some(found) = devices.drain(..).find(|dev| dev.ip == ip) {
-                return Ok(found);
-            }
-            Ok(DeviceInfo {
-                ip,
-                mac: [0; 6],
-                manufacturer: None,
-                model: None,
-            })
-        }
-        (None, Some(idx)) => {
-            let devices = discover_devices(timeout, iface_ip).await?;
-            let device = devices
-                .into_iter()
-                .nth(idx)
-                .ok_or_else(|| anyhow!("no device at index {idx}"))?;
-            Ok(device)
-        }
-        (Some(ip), Some(_)) => {
-            bail!("specify either --ip or --index, not both (using {ip})");
-        }
-        (None, None) => {
-            bail!("a camera must be selected via --ip or --index");
-        }
+    let chosen = [
+        target.ip.is_some(),
+        target.index.is_some(),
+        target.device.is_some(),
+    ];
+    if chosen.iter().filter(|set| **set).count() > 1 {
+        bail!("specify only one of --ip, --index or --device");
     }
+    if let Some(idx) = target.index {
+        let devices = discover_devices(timeout, iface_ip).await?;
+        let device = devices
+            .into_iter()
+            .nth(idx)
+            .ok_or_else(|| anyhow!("no device at index {idx}"))?;
+        return Ok(device);
+    }
+    let Some(selector) = target.ip.map(DeviceSelector::Ip).or(target.device.clone()) else {
+        bail!("a camera must be selected via --ip, --index or --device");
+    };
+    let devices = discover_devices(timeout, iface_ip).await?;
+    let DeviceSelector::Ip(ip) = selector else {
+        return Ok(selector.select(&devices)?.clone());
+    };
+    if let Some(found) = devices.into_iter().find(|dev| dev.ip == ip) {
+        return Ok(found);
+    }
+    // Broadcasts do not cross routers; ask the address directly.
+    let direct = discover_unicast(ip, timeout)
+        .await
+        .context("unicast discovery")?;
+    Ok(direct.unwrap_or_else(|| DeviceInfo::from_ip(ip)))
 }
 
-async fn fetch_xml(control: Arc<Mutex<GigeDevice>>) -> Result<String> {
+async fn fetch_xml(control: ControlHandle) -> Result<String> {
     genapi_xml::fetch_and_load_xml({
         move |address, length| {
-            let control = Arc::clone(&control);
+            let control = control.clone();
             async move {
-                let mut guard = control.lock().await;
-                guard
+                control
                     .read_mem(address, length)
                     .await
                     .map_err(|err| XmlError::Transport(err.to_string()))
@@ -98,28 +112,30 @@ async fn fetch_xml(control: Arc<Mutex<GigeDevice>>) -> Result<String> {
     .context("fetch GenApi XML")
 }
 
-pub async fn open_camera(device: &DeviceInfo) -> Result<Camera<GigeRegisterIo>> {
+async fn open_device(device: &DeviceInfo) -> Result<GigeDevice> {
     let addr = SocketAddr::new(IpAddr::V4(device.ip), GVCP_PORT);
-    let control =
-        Arc::new(Mutex::new(GigeDevice::open(addr).await.with_context(
-            || format!("connect GVCP control channel at {}", device.ip),
-        )?));
-    let xml = fetch_xml(control.clone()).await?;
+    GigeDevice::open(addr)
+        .await
+        .with_context(|| format!("connect GVCP control channel at {}", device.ip))
+}
+
+pub async fn load_device_xml(device: &DeviceInfo) -> Result<String> {
+    let control = open_device(device).await?;
+    fetch_xml(control.control().clone()).await
+}
+
+pub async fn open_camera(device: &DeviceInfo) -> Result<Camera<GigeRegisterIo>> {
+    let control = open_device(device).await?;
+    let xml = fetch_xml(control.control().clone()).await?;
     let model = genapi_xml::parse(&xml).context("parse GenApi XML")?;
-    let nodemap = NodeMap::from(model);
-    let handle = Handle::current();
-    let device = Arc::try_unwrap(control)
-        .map_err(|_| anyhow!("control connection still in use"))?
-        .into_inner();
-    let transport = GigeRegisterIo::new(handle, device);
+    let nodemap = NodeMap::try_from_xml(model).context("build nodemap")?;
+    let transport = GigeRegisterIo::new(Handle::current(), control);
     Ok(Camera::new(transport, nodemap))
 }
 
-pub async fn open_stream_device(device: &DeviceInfo) -> Result<GigeDevice> {
-    let addr = SocketAddr::new(IpAddr::V4(device.ip), GVCP_PORT);
-    GigeDevice::open(addr)
-        .await
-        .with_context(|| format!("open GVCP stream control at {}", device.ip))
+/// Device handle for stream configuration sharing the camera's control channel.
+pub fn stream_device(camera: &Camera<GigeRegisterIo>) -> GigeDevice {
+    GigeDevice::from_control(camera.transport().control().clone())
 }
 
 pub fn resolve_iface(ip: Option<Ipv4Addr>) -> Result<Option<Iface>> {
@@ -198,6 +214,15 @@ pub fn save_image(buffer: &[u8], path: &PathBuf) -> Result<()> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn mac_parsing() {
+        let mac = parse_mac("00:30:53:0a-FF:01").expect("parse");
+        assert_eq!(mac, [0x00, 0x30, 0x53, 0x0A, 0xFF, 0x01]);
+        assert_eq!(format_mac(&mac), "00:30:53:0A:FF:01");
+        assert!(parse_mac("00:30:53:0a:ff").is_err());
+        assert!(parse_mac("00:30:53:0a:ff:zz").is_err());
+    }
+
     #[test]
     fn pgm_header_is_correct() {
         let data = vec![0u8; 4];
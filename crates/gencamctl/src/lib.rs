@@ -2,6 +2,8 @@ pub mod cmd_bench;
 pub mod cmd_chunks;
 pub mod cmd_events;
 pub mod cmd_get;
+pub mod cmd_ip;
+pub mod cmd_lint;
 pub mod cmd_list;
 pub mod cmd_set;
 pub mod cmd_stream;
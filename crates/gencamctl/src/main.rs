@@ -2,16 +2,20 @@ use std::net::Ipv4Addr;
 use std::path::PathBuf;
 
 use anyhow::{anyhow, Result};
-use clap::{ArgAction, Parser, Subcommand};
+use clap::{ArgAction, Args, Parser, Subcommand};
 use tracing_subscriber::EnvFilter;
 
 use gencamctl::cmd_bench::{self, BenchArgs};
 use gencamctl::cmd_chunks;
 use gencamctl::cmd_events;
 use gencamctl::cmd_get;
+use gencamctl::cmd_ip;
+use gencamctl::cmd_lint;
 use gencamctl::cmd_list;
 use gencamctl::cmd_set;
 use gencamctl::cmd_stream::{self, StreamArgs};
+use gencamctl::common::DeviceTarget;
+use tl_gige::DeviceSelector;
 
 #[derive(Parser, Debug)]
 #[command(name = "gencamctl", version, about = "GenICam CLI")]
@@ -29,6 +33,30 @@ struct Cli {
     cmd: Cmd,
 }
 
+/// Camera selection shared by every device subcommand.
+#[derive(Args, Debug)]
+struct TargetArgs {
+    /// Camera IPv4 address
+    #[arg(long)]
+    ip: Option<Ipv4Addr>,
+    /// Camera position in discovery order
+    #[arg(long)]
+    index: Option<usize>,
+    /// Camera selector: sn:<serial>, mac:<addr>, name:<user name>, model:<pattern> or ip:<addr>
+    #[arg(long)]
+    device: Option<DeviceSelector>,
+}
+
+impl From<TargetArgs> for DeviceTarget {
+    fn from(args: TargetArgs) -> Self {
+        Self {
+            ip: args.ip,
+            index: args.index,
+            device: args.device,
+        }
+    }
+}
+
 #[derive(Subcommand, Debug)]
 enum Cmd {
     /// Discover cameras (GVCP)
@@ -40,19 +68,15 @@ enum Cmd {
     },
     /// Read a feature via GenApi NodeMap
     Get {
-        #[arg(long)]
-        ip: Option<Ipv4Addr>,
-        #[arg(long)]
-        index: Option<usize>,
+        #[command(flatten)]
+        target: TargetArgs,
         #[arg(long)]
         name: String,
     },
     /// Write a feature via GenApi NodeMap
     Set {
-        #[arg(long)]
-        ip: Option<Ipv4Addr>,
-        #[arg(long)]
-        index: Option<usize>,
+        #[command(flatten)]
+        target: TargetArgs,
         #[arg(long)]
         name: String,
         #[arg(long)]
@@ -60,10 +84,8 @@ enum Cmd {
     },
     /// Start GVSP stream (uni-/multicast)
     Stream {
-        #[arg(long)]
-        ip: Option<Ipv4Addr>,
-        #[arg(long)]
-        index: Option<usize>,
+        #[command(flatten)]
+        target: TargetArgs,
         #[arg(long)]
         iface: Option<Ipv4Addr>,
         #[arg(long, default_value = "unicast")]
@@ -83,10 +105,8 @@ enum Cmd {
     },
     /// Configure + read events (message channel)
     Events {
-        #[arg(long)]
-        ip: Option<Ipv4Addr>,
-        #[arg(long)]
-        index: Option<usize>,
+        #[command(flatten)]
+        target: TargetArgs,
         #[arg(long)]
         iface: Option<Ipv4Addr>,
         #[arg(long, default_value_t = 10020)]
@@ -98,21 +118,30 @@ enum Cmd {
     },
     /// Toggle ChunkModeActive + selectors
     Chunks {
-        #[arg(long)]
-        ip: Option<Ipv4Addr>,
-        #[arg(long)]
-        index: Option<usize>,
+        #[command(flatten)]
+        target: TargetArgs,
         #[arg(long)]
         enable: bool,
         #[arg(long, default_value = "Timestamp")]
         selectors: String,
     },
+    /// Check GenApi XML for dangling references, cycles and overlaps
+    Lint {
+        /// Lint a local XML file instead of fetching it from a camera
+        #[arg(long)]
+        xml: Option<PathBuf>,
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+    /// Inspect or change the camera IP configuration
+    Ip {
+        #[command(subcommand)]
+        action: IpCmd,
+    },
     /// Sustained stream soak/benchmark
     Bench {
-        #[arg(long)]
-        ip: Option<Ipv4Addr>,
-        #[arg(long)]
-        index: Option<usize>,
+        #[command(flatten)]
+        target: TargetArgs,
         #[arg(long)]
         iface: Option<Ipv4Addr>,
         #[arg(long, default_value = "unicast")]
@@ -128,6 +157,46 @@ enum Cmd {
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum IpCmd {
+    /// Show current and persistent addresses and enabled modes
+    Show {
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+    /// Assign a temporary address by MAC (FORCEIP), even across subnets
+    Force {
+        #[arg(long)]
+        mac: String,
+        #[arg(long)]
+        address: Ipv4Addr,
+        #[arg(long, default_value = "255.255.255.0")]
+        subnet: Ipv4Addr,
+        #[arg(long, default_value = "0.0.0.0")]
+        gateway: Ipv4Addr,
+    },
+    /// Store a persistent address and enable persistent IP
+    Persistent {
+        #[command(flatten)]
+        target: TargetArgs,
+        #[arg(long)]
+        address: Ipv4Addr,
+        #[arg(long, default_value = "255.255.255.0")]
+        subnet: Ipv4Addr,
+        #[arg(long, default_value = "0.0.0.0")]
+        gateway: Ipv4Addr,
+    },
+    /// Enable or disable persistent IP and DHCP
+    Mode {
+        #[command(flatten)]
+        target: TargetArgs,
+        #[arg(long)]
+        persistent: Option<bool>,
+        #[arg(long)]
+        dhcp: Option<bool>,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let Cli {
@@ -157,16 +226,14 @@ async fn main() -> Result<()> {
             let iface = cmd_iface.or(iface);
             cmd_list::run(timeout_ms, iface, json).await?
         }
-        Cmd::Get { ip, index, name } => cmd_get::run(ip, index, name, iface, json).await?,
+        Cmd::Get { target, name } => cmd_get::run(target.into(), name, iface, json).await?,
         Cmd::Set {
-            ip,
-            index,
+            target,
             name,
             value,
-        } => cmd_set::run(ip, index, name, value, iface, json).await?,
+        } => cmd_set::run(target.into(), name, value, iface, json).await?,
         Cmd::Stream {
-            ip,
-            index,
+            target,
             iface: cmd_iface,
             mode,
             group,
@@ -177,8 +244,7 @@ async fn main() -> Result<()> {
             duration_s,
         } => {
             let args = StreamArgs {
-                ip,
-                index,
+                target: target.into(),
                 iface: cmd_iface.or(iface),
                 mode,
                 group,
@@ -191,8 +257,7 @@ async fn main() -> Result<()> {
             cmd_stream::run(args).await?
         }
         Cmd::Events {
-            ip,
-            index,
+            target,
             iface: cmd_iface,
             port,
             enable,
@@ -201,17 +266,36 @@ async fn main() -> Result<()> {
             let iface = cmd_iface.or(iface).ok_or_else(|| {
                 anyhow!("events require --iface or a global --iface IPv4 address")
             })?;
-            cmd_events::run(ip, index, iface, port, enable, count, json).await?
+            cmd_events::run(target.into(), iface, port, enable, count, json).await?
         }
         Cmd::Chunks {
-            ip,
-            index,
+            target,
             enable,
             selectors,
-        } => cmd_chunks::run(ip, index, enable, selectors, iface, json).await?,
+        } => cmd_chunks::run(target.into(), enable, selectors, iface, json).await?,
+        Cmd::Lint { xml, target } => cmd_lint::run(xml, target.into(), iface, json).await?,
+        Cmd::Ip { action } => match action {
+            IpCmd::Show { target } => cmd_ip::show(target.into(), iface, json).await?,
+            IpCmd::Force {
+                mac,
+                address,
+                subnet,
+                gateway,
+            } => cmd_ip::force(mac, address, subnet, gateway, iface).await?,
+            IpCmd::Persistent {
+                target,
+                address,
+                subnet,
+                gateway,
+            } => cmd_ip::set_persistent(target.into(), address, subnet, gateway, iface).await?,
+            IpCmd::Mode {
+                target,
+                persistent,
+                dhcp,
+            } => cmd_ip::set_mode(target.into(), persistent, dhcp, iface).await?,
+        },
         Cmd::Bench {
-            ip,
-            index,
+            target,
             iface: cmd_iface,
             mode,
             group,
@@ -220,8 +304,7 @@ async fn main() -> Result<()> {
             json_out,
         } => {
             let args = BenchArgs {
-                ip,
-                index,
+                target: target.into(),
                 iface: cmd_iface.or(iface),
                 mode,
                 group,
@@ -273,6 +356,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_lint_xml_path() {
+        let cli = Cli::parse_from(["gencamctl", "lint", "--xml", "camera.xml"]);
+        match cli.cmd {
+            Cmd::Lint { xml, target } => {
+                assert_eq!(xml, Some(PathBuf::from("camera.xml")));
+                assert_eq!(target.ip, None);
+            }
+            other => panic!("unexpected variant: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_device_selector() {
+        let cli = Cli::parse_from([
+            "gencamctl",
+            "get",
+            "--device",
+            "sn:2134*",
+            "--name",
+            "Width",
+        ]);
+        match cli.cmd {
+            Cmd::Get { target, name } => {
+                assert_eq!(target.device, Some(DeviceSelector::Serial("2134*".into())));
+                assert_eq!(name, "Width");
+            }
+            other => panic!("unexpected variant: {other:?}"),
+        }
+        assert!(
+            Cli::try_parse_from(["gencamctl", "get", "--device", "sn:", "--name", "Width"])
+                .is_err()
+        );
+    }
+
     #[test]
     fn parse_bench_output_path() {
         let cli = Cli::parse_from(["gencamctl", "bench", "--json-out", "bench.json"]);
@@ -283,4 +401,33 @@ mod tests {
             other => panic!("unexpected variant: {other:?}"),
         }
     }
+
+    #[test]
+    fn parse_ip_force() {
+        let cli = Cli::parse_from([
+            "gencamctl",
+            "ip",
+            "force",
+            "--mac",
+            "00:30:53:01:02:03",
+            "--address",
+            "192.168.1.50",
+        ]);
+        match cli.cmd {
+            Cmd::Ip {
+                action:
+                    IpCmd::Force {
+                        mac,
+                        address,
+                        subnet,
+                        ..
+                    },
+            } => {
+                assert_eq!(mac, "00:30:53:01:02:03");
+                assert_eq!(address, Ipv4Addr::new(192, 168, 1, 50));
+                assert_eq!(subnet, Ipv4Addr::new(255, 255, 255, 0));
+            }
+            other => panic!("unexpected variant: {other:?}"),
+        }
+    }
 }
@@ -101,7 +101,7 @@ async fn build_camera(
     })
     .await?;
     let model = genapi_xml::parse(&xml)?;
-    let nodemap = genapi_core::NodeMap::from(model);
+    let nodemap = genapi_core::NodeMap::try_from_xml(model)?;
     let handle = tokio::runtime::Handle::current();
     let device = Arc::try_unwrap(control)
         .map_err(|_| "control connection still in use")?
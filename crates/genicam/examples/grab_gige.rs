@@ -167,7 +167,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     })
     .await?;
     let model = genapi_xml::parse(&xml)?;
-    let nodemap = NodeMap::from(model);
+    let nodemap = NodeMap::try_from_xml(model)?;
     let handle = tokio::runtime::Handle::current();
     let control_device = Arc::try_unwrap(control)
         .map_err(|_| "control connection still in use")?
@@ -175,7 +175,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let transport = GigeRegisterIo::new(handle.clone(), control_device);
     let mut camera = Camera::new(transport, nodemap);
 
-    let mut stream_device = genicam::gige::GigeDevice::open(control_addr).await?;
+    let mut stream_device =
+        genicam::gige::GigeDevice::from_control(camera.transport().control().clone());
     let mut builder = StreamBuilder::new(&mut stream_device).iface(iface.clone());
     if let Some(group) = args.multicast {
         builder = builder.multicast(Some(group));
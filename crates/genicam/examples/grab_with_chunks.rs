@@ -102,7 +102,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     })
     .await?;
     let model = genapi_xml::parse(&xml)?;
-    let nodemap = NodeMap::from(model);
+    let nodemap = NodeMap::try_from_xml(model)?;
     let handle = tokio::runtime::Handle::current();
     let control_device = match std::sync::Arc::try_unwrap(control) {
         Ok(mutex) => mutex.into_inner(),
@@ -153,7 +153,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     }
     println!("Chunk mode enabled for selectors: {:?}", enable_selectors);
 
-    let mut stream_device = genicam::gige::GigeDevice::open(control_addr).await?;
+    let mut stream_device =
+        genicam::gige::GigeDevice::from_control(camera.transport().control().clone());
     let stream = StreamBuilder::new(&mut stream_device)
         .iface(iface.clone())
         .build()
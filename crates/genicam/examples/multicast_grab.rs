@@ -174,7 +174,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     })
     .await?;
     let model = genapi_xml::parse(&xml)?;
-    let nodemap = NodeMap::from(model);
+    let nodemap = NodeMap::try_from_xml(model)?;
     let handle = tokio::runtime::Handle::current();
     let control_device = std::sync::Arc::try_unwrap(control)
         .map_err(|_| "control connection still in use")?
@@ -184,7 +184,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     camera.configure_stream_multicast(args.stream_idx, args.group, args.port)?;
 
-    let mut stream_device = genicam::gige::GigeDevice::open(control_addr).await?;
+    let mut stream_device =
+        genicam::gige::GigeDevice::from_control(camera.transport().control().clone());
     let mut builder = StreamBuilder::new(&mut stream_device)
         .iface(iface.clone())
         .dest(StreamDest::Multicast {
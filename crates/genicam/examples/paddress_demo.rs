@@ -47,7 +47,7 @@ fn run_mock() -> Result<()> {
     "#;
 
     let model = genapi_xml::parse(XML)?;
-    let mut nodemap = NodeMap::from(model);
+    let mut nodemap = NodeMap::try_from_xml(model)?;
     let transport = MockIo::with_registers(&[
         (0x2000, vec![0x00, 0x00, 0x30, 0x00]),
         (0x3000, vec![0x00, 0x00, 0x00, 0x7B]),
@@ -106,7 +106,7 @@ fn run_real() -> Result<()> {
         return Ok(());
     }
 
-    let nodemap = NodeMap::from(model);
+    let nodemap = NodeMap::try_from_xml(model)?;
     let handle = rt.handle().clone();
     let device = match std::sync::Arc::try_unwrap(device) {
         Ok(mutex) => mutex.into_inner(),
@@ -46,7 +46,7 @@ fn run_mock() -> Result<(), Box<dyn Error>> {
     "#;
 
     let model = genapi_xml::parse(XML)?;
-    let nodemap = NodeMap::from(model);
+    let nodemap = NodeMap::try_from_xml(model)?;
     let transport = MockIo::with_registers(&[
         (0x300, vec![0, 0]),
         (0x310, vec![0, 12]),
@@ -103,7 +103,7 @@ fn run_real() -> Result<(), Box<dyn Error>> {
         }
     }))?;
     let model = genapi_xml::parse(&xml)?;
-    let nodemap = NodeMap::from(model);
+    let nodemap = NodeMap::try_from_xml(model)?;
     let handle = rt.handle().clone();
     let device = match std::sync::Arc::try_unwrap(device) {
         Ok(mutex) => mutex.into_inner(),
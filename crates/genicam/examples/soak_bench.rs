@@ -243,7 +243,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     })
     .await?;
     let model = genapi_xml::parse(&xml)?;
-    let nodemap = NodeMap::from(model);
+    let nodemap = NodeMap::try_from_xml(model)?;
     let handle = tokio::runtime::Handle::current();
     let control_device = std::sync::Arc::try_unwrap(control)
         .map_err(|_| "control connection still in use")?
@@ -259,7 +259,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
         )?;
     }
 
-    let mut stream_device = genicam::gige::GigeDevice::open(control_addr).await?;
+    let mut stream_device =
+        genicam::gige::GigeDevice::from_control(camera.transport().control().clone());
     let dest = match args.mode {
         DestMode::Unicast => StreamDest::Unicast {
             dst_ip: args.iface,
@@ -93,7 +93,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     })
     .await?;
     let model = genapi_xml::parse(&xml)?;
-    let nodemap = NodeMap::from(model);
+    let nodemap = NodeMap::try_from_xml(model)?;
     let handle = tokio::runtime::Handle::current();
     let control_device = match Arc::try_unwrap(control) {
         Ok(mutex) => mutex.into_inner(),
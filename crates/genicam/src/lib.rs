@@ -78,11 +78,13 @@ pub use tl_gige as gige;
 pub mod chunks;
 pub mod events;
 pub mod frame;
+pub mod persistence;
 pub mod stream;
 pub mod time;
+mod units;
 
 use std::net::{IpAddr, Ipv4Addr};
-use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime};
 
 use crate::events::{
@@ -90,9 +92,12 @@ use crate::events::{
     configure_message_channel_raw as configure_message_channel_fallback,
     enable_event_raw as enable_event_fallback, parse_event_id,
 };
-use crate::genapi::{GenApiError, Node, NodeMap, RegisterIo, SkOutput};
+use crate::genapi::{
+    BoolFeature, CommandFeature, EnumFeature, FloatFeature, GenApiError, IntegerFeature, Node,
+    NodeMap, RegisterIo, SkOutput, WritePolicy,
+};
 use gige::gvcp::consts as gvcp_consts;
-use gige::GigeDevice;
+use gige::{ControlHandle, GigeDevice};
 use thiserror::Error;
 use tokio::time::sleep;
 use tracing::{debug, info, warn};
@@ -101,6 +106,8 @@ pub use chunks::{parse_chunk_bytes, ChunkKind, ChunkMap, ChunkValue};
 pub use events::{Event, EventStream};
 pub use frame::Frame;
 pub use gige::action::{AckSummary, ActionParams};
+pub use gige::discovery::{DeviceSelector, SelectorError};
+pub use persistence::{ClampedFeature, FailedFeature, FeatureLoadReport};
 pub use stream::{Stream, StreamBuilder, StreamDest};
 pub use time::TimeSync;
 
@@ -118,12 +125,18 @@ pub enum GenicamError {
     /// Parsing a user supplied value failed.
     #[error("parse error: {0}")]
     Parse(String),
+    /// Reading or writing a local file failed.
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
     /// Required chunk feature missing from the nodemap.
     #[error("chunk feature '{0}' not found; verify camera supports chunk data")]
     MissingChunkFeature(String),
     /// The camera reported a pixel format without a conversion path.
     #[error("unsupported pixel format: {0}")]
     UnsupportedPixelFormat(pfnc::PixelFormat),
+    /// No single discovered device matched a [`DeviceSelector`].
+    #[error(transparent)]
+    Selector(#[from] SelectorError),
 }
 
 impl GenicamError {
@@ -181,18 +194,79 @@ impl<T: RegisterIo> Camera<T> {
         &mut self.nodemap
     }
 
+    /// Restrict feature writes to those permitted by `policy`.
+    ///
+    /// Rejected writes fail with [`GenApiError::PolicyViolation`].
+    pub fn set_write_policy(&mut self, policy: WritePolicy) {
+        self.nodemap.set_write_policy(policy);
+    }
+
+    /// Remove the write policy and return it.
+    pub fn clear_write_policy(&mut self) -> Option<WritePolicy> {
+        self.nodemap.clear_write_policy()
+    }
+
+    /// Read back every feature write and fail on values the device altered.
+    ///
+    /// Mismatches are reported as [`GenApiError::WriteMismatch`].
+    pub fn set_verify_writes(&mut self, enabled: bool) {
+        self.nodemap.set_verify_writes(enabled);
+    }
+
+    /// Borrow an integer feature as a typed handle.
+    pub fn integer_feature(&mut self, name: &str) -> Result<IntegerFeature<'_>, GenicamError> {
+        self.nodemap
+            .integer_feature(name, &self.transport)
+            .map_err(Into::into)
+    }
+
+    /// Borrow a floating point feature as a typed handle.
+    pub fn float_feature(&mut self, name: &str) -> Result<FloatFeature<'_>, GenicamError> {
+        self.nodemap
+            .float_feature(name, &self.transport)
+            .map_err(Into::into)
+    }
+
+    /// Borrow an enumeration feature as a typed handle.
+    pub fn enum_feature(&mut self, name: &str) -> Result<EnumFeature<'_>, GenicamError> {
+        self.nodemap
+            .enum_feature(name, &self.transport)
+            .map_err(Into::into)
+    }
+
+    /// Borrow a boolean feature as a typed handle.
+    pub fn bool_feature(&mut self, name: &str) -> Result<BoolFeature<'_>, GenicamError> {
+        self.nodemap
+            .bool_feature(name, &self.transport)
+            .map_err(Into::into)
+    }
+
+    /// Borrow a command feature as a typed handle.
+    pub fn command_feature(&mut self, name: &str) -> Result<CommandFeature<'_>, GenicamError> {
+        self.nodemap
+            .command_feature(name, &self.transport)
+            .map_err(Into::into)
+    }
+
     /// List available entries for an enumeration feature.
     pub fn enum_entries(&self, name: &str) -> Result<Vec<String>, GenicamError> {
         self.nodemap.enum_entries(name).map_err(Into::into)
     }
 
     /// Retrieve a feature value as a string using the nodemap type to format it.
+    ///
+    /// Integers honour their `<Representation>` (hexadecimal, IPv4, MAC) and
+    /// floats their `<DisplayNotation>`/`<DisplayPrecision>`.
     pub fn get(&self, name: &str) -> Result<String, GenicamError> {
         match self.nodemap.node(name) {
-            Some(Node::Integer(_)) => {
-                Ok(self.nodemap.get_integer(name, &self.transport)?.to_string())
+            Some(Node::Integer(node)) => {
+                let value = self.nodemap.get_integer(name, &self.transport)?;
+                Ok(units::format_integer(value, &node.display))
+            }
+            Some(Node::Float(node)) => {
+                let value = self.nodemap.get_float(name, &self.transport)?;
+                Ok(units::format_float(value, &node.display))
             }
-            Some(Node::Float(_)) => Ok(self.nodemap.get_float(name, &self.transport)?.to_string()),
             Some(Node::Enum(_)) => self
                 .nodemap
                 .get_enum(name, &self.transport)
@@ -213,20 +287,21 @@ impl<T: RegisterIo> Camera<T> {
     }
 
     /// Set a feature value using a string representation.
+    ///
+    /// Numeric values may carry a unit (`"5ms"`, `"12.5 dB"`) that is converted
+    /// to the node unit, and integers accept `0x` hexadecimal, dotted IPv4 and
+    /// MAC address notation.
     pub fn set(&mut self, name: &str, value: &str) -> Result<(), GenicamError> {
         match self.nodemap.node(name) {
-            Some(Node::Integer(_)) => {
-                let parsed: i64 = value
-                    .parse()
-                    .map_err(|_| GenicamError::parse(format!("invalid integer for {name}")))?;
+            Some(Node::Integer(node)) => {
+                let parsed =
+                    units::parse_integer(name, value, node.unit.as_deref(), &node.display)?;
                 self.nodemap
                     .set_integer(name, parsed, &self.transport)
                     .map_err(Into::into)
             }
-            Some(Node::Float(_)) => {
-                let parsed: f64 = value
-                    .parse()
-                    .map_err(|_| GenicamError::parse(format!("invalid float for {name}")))?;
+            Some(Node::Float(node)) => {
+                let parsed = units::parse_float(name, value, node.unit.as_deref())?;
                 self.nodemap
                     .set_float(name, parsed, &self.transport)
                     .map_err(Into::into)
@@ -674,41 +749,56 @@ pub struct ChunkConfig {
 /// from outside of the runtime context to avoid nested `block_on` panics.
 pub struct GigeRegisterIo {
     handle: tokio::runtime::Handle,
-    device: Mutex<GigeDevice>,
+    device: GigeDevice,
 }
 
 impl GigeRegisterIo {
     /// Create a new adapter using the provided runtime handle and device.
     pub fn new(handle: tokio::runtime::Handle, device: GigeDevice) -> Self {
-        Self {
-            handle,
-            device: Mutex::new(device),
-        }
+        Self { handle, device }
     }
 
-    fn lock(&self) -> Result<MutexGuard<'_, GigeDevice>, GenApiError> {
-        self.device
-            .lock()
-            .map_err(|_| GenApiError::Io("gige device mutex poisoned".into()))
+    /// Control channel of the wrapped device, shareable with streaming code.
+    pub fn control(&self) -> &ControlHandle {
+        self.device.control()
     }
 }
 
 impl RegisterIo for GigeRegisterIo {
     fn read(&self, addr: u64, len: usize) -> Result<Vec<u8>, GenApiError> {
-        let mut device = self.lock()?;
         self.handle
-            .block_on(device.read_mem(addr, len))
+            .block_on(self.control().read_mem(addr, len))
             .map_err(|err| GenApiError::Io(err.to_string()))
     }
 
     fn write(&self, addr: u64, data: &[u8]) -> Result<(), GenApiError> {
-        let mut device = self.lock()?;
         self.handle
-            .block_on(device.write_mem(addr, data))
+            .block_on(self.control().write_mem(addr, data))
             .map_err(|err| GenApiError::Io(err.to_string()))
     }
 }
 
+/// Discover GigE Vision devices and return the one matching `selector`.
+///
+/// IP selectors are resolved with a unicast discovery request so devices on
+/// routed subnets are found too; every other selector broadcasts on all
+/// interfaces and requires exactly one match.
+pub async fn find_device(
+    selector: &DeviceSelector,
+    timeout: Duration,
+) -> Result<gige::DeviceInfo, GenicamError> {
+    if let DeviceSelector::Ip(ip) = selector {
+        let found = gige::discover_unicast(*ip, timeout)
+            .await
+            .map_err(|err| GenicamError::transport(err.to_string()))?;
+        return found.ok_or_else(|| SelectorError::NotFound(selector.clone()).into());
+    }
+    let devices = gige::discover(timeout)
+        .await
+        .map_err(|err| GenicamError::transport(err.to_string()))?;
+    Ok(selector.select(&devices)?.clone())
+}
+
 fn parse_bool(value: &str) -> Option<bool> {
     match value.trim().to_ascii_lowercase().as_str() {
         "1" | "true" => Some(true),
@@ -716,3 +806,21 @@ fn parse_bool(value: &str) -> Option<bool> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn find_device_reports_silent_ip_as_not_found() {
+        // Nothing serves GVCP on loopback, so the unicast request goes unanswered.
+        let selector = DeviceSelector::Ip(Ipv4Addr::LOCALHOST);
+        let err = find_device(&selector, Duration::from_millis(100))
+            .await
+            .expect_err("no device on loopback");
+        assert!(
+            matches!(&err, GenicamError::Selector(SelectorError::NotFound(s)) if *s == selector),
+            "unexpected error: {err}"
+        );
+    }
+}
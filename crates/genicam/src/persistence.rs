@@ -0,0 +1,511 @@
+//! Save and restore camera configuration as GenApi feature-stream files.
+//!
+//! The format follows the GenApi reference implementation: a comment header
+//! followed by one `Name<TAB>Value` line per feature. Selected features are
+//! written once per selector value, preceded by the selector line, so replaying
+//! the file top to bottom reproduces the configuration.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use tracing::{debug, warn};
+
+use crate::genapi::{AccessMode, DependencyKind, GenApiError, Node, RegisterIo};
+use crate::{Camera, GenicamError};
+
+/// Signature line emitted by GenApi feature persistence.
+const FEATURE_STREAM_MAGIC: &str = "# {05D8C294-F295-4dfb-9D01-096BD04049F4}";
+
+/// Feature whose stored value was out of range and had to be clamped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClampedFeature {
+    /// Feature name.
+    pub name: String,
+    /// Value found in the file.
+    pub requested: String,
+    /// Value actually written to the device.
+    pub applied: String,
+}
+
+/// Feature that could not be restored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedFeature {
+    /// Feature name.
+    pub name: String,
+    /// Value found in the file.
+    pub value: String,
+    /// Reason the write failed.
+    pub error: String,
+}
+
+/// Outcome of [`Camera::load_features`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeatureLoadReport {
+    /// Number of lines applied verbatim.
+    pub applied: usize,
+    /// Lines applied after clamping to the feature limits.
+    pub clamped: Vec<ClampedFeature>,
+    /// Lines that could not be applied.
+    pub failed: Vec<FailedFeature>,
+}
+
+impl FeatureLoadReport {
+    /// Whether every line was applied without clamping or failure.
+    pub fn is_clean(&self) -> bool {
+        self.clamped.is_empty() && self.failed.is_empty()
+    }
+}
+
+impl<T: RegisterIo> Camera<T> {
+    /// Record every readable and writable feature into a feature-stream file.
+    ///
+    /// Selected features are captured for every selector value; selectors are
+    /// restored to their original value afterwards.
+    pub fn save_features(&mut self, path: impl AsRef<Path>) -> Result<(), GenicamError> {
+        let text = self.features_to_string()?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Reapply a feature-stream file written by [`Camera::save_features`].
+    pub fn load_features(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<FeatureLoadReport, GenicamError> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(self.apply_features(&text))
+    }
+
+    /// Serialise the current configuration in feature-stream format.
+    pub fn features_to_string(&mut self) -> Result<String, GenicamError> {
+        let plan = SavePlan::new(self);
+        let mut out = String::new();
+        let _ = writeln!(out, "{FEATURE_STREAM_MAGIC}");
+        let _ = writeln!(out, "# GenApi persistence file (version 3.0.0)");
+        let _ = writeln!(out, "# Schema version {}", self.nodemap.version());
+
+        for name in &plan.plain {
+            self.write_feature(&mut out, name)?;
+        }
+        for (selectors, features) in &plan.groups {
+            let originals = selectors
+                .iter()
+                .map(|selector| self.raw_value(selector))
+                .collect::<Result<Vec<_>, _>>()?;
+            let written = self.write_selected(&mut out, selectors, features);
+            let restored = self.restore_selectors(&mut out, selectors, &originals);
+            written?;
+            restored?;
+        }
+        Ok(out)
+    }
+
+    /// Apply feature-stream text line by line and report what happened.
+    ///
+    /// Lines are replayed in file order, which [`Camera::save_features`]
+    /// writes with dependencies (selectors, address and value providers) first.
+    /// Out-of-range numeric values are clamped to the feature limits, written,
+    /// and reported in [`FeatureLoadReport::clamped`].
+    pub fn apply_features(&mut self, text: &str) -> FeatureLoadReport {
+        let mut report = FeatureLoadReport::default();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((name, value)) = line.split_once(|c: char| c.is_ascii_whitespace()) else {
+                report.failed.push(FailedFeature {
+                    name: line.to_string(),
+                    value: String::new(),
+                    error: "missing value".into(),
+                });
+                continue;
+            };
+            let value = value.trim().trim_matches('"');
+            match self.set(name, value) {
+                Ok(()) => report.applied += 1,
+                Err(GenicamError::GenApi(GenApiError::Range(_))) => {
+                    let result = match self.clamp_value(name, value) {
+                        Some(clamped) => self.set(name, &clamped).map(|_| clamped),
+                        None => Err(GenApiError::Range(name.to_string()).into()),
+                    };
+                    match result {
+                        Ok(applied) => {
+                            warn!(feature = name, value, %applied, "clamped feature value");
+                            report.clamped.push(ClampedFeature {
+                                name: name.to_string(),
+                                requested: value.to_string(),
+                                applied,
+                            });
+                        }
+                        Err(err) => report.failed.push(FailedFeature {
+                            name: name.to_string(),
+                            value: value.to_string(),
+                            error: err.to_string(),
+                        }),
+                    }
+                }
+                Err(err) => {
+                    warn!(feature = name, value, error = %err, "failed to restore feature");
+                    report.failed.push(FailedFeature {
+                        name: name.to_string(),
+                        value: value.to_string(),
+                        error: err.to_string(),
+                    });
+                }
+            }
+        }
+        report
+    }
+
+    fn write_selected(
+        &mut self,
+        out: &mut String,
+        selectors: &[String],
+        features: &[String],
+    ) -> Result<(), GenicamError> {
+        let Some((selector, rest)) = selectors.split_first() else {
+            for name in features {
+                self.write_feature(out, name)?;
+            }
+            return Ok(());
+        };
+        for value in self.nodemap.selector_values(selector).unwrap_or_default() {
+            if let Err(err) = self.set(selector, &value) {
+                debug!(selector = %selector, value = %value, error = %err, "skipping selector value");
+                continue;
+            }
+            push_line(out, selector, &value);
+            self.write_selected(out, rest, features)?;
+        }
+        Ok(())
+    }
+
+    /// Put every selector back to its original value, even after a failure,
+    /// and return the first error encountered.
+    fn restore_selectors(
+        &mut self,
+        out: &mut String,
+        selectors: &[String],
+        originals: &[String],
+    ) -> Result<(), GenicamError> {
+        let mut result = Ok(());
+        for (selector, original) in selectors.iter().zip(originals) {
+            match self.set(selector, original) {
+                Ok(()) => push_line(out, selector, original),
+                Err(err) => {
+                    warn!(selector = %selector, value = %original, error = %err, "failed to restore selector");
+                    if result.is_ok() {
+                        result = Err(err);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    fn write_feature(&self, out: &mut String, name: &str) -> Result<(), GenicamError> {
+        match self.raw_value(name) {
+            Ok(value) => push_line(out, name, &value),
+            Err(GenicamError::GenApi(GenApiError::Io(msg))) => {
+                return Err(GenApiError::Io(msg).into());
+            }
+            Err(err) => debug!(feature = name, error = %err, "feature not saved"),
+        }
+        Ok(())
+    }
+
+    /// Current value in full precision, unlike the display text of
+    /// [`Camera::get`], so reloading reproduces it exactly.
+    fn raw_value(&self, name: &str) -> Result<String, GenicamError> {
+        let value = match self.nodemap.node(name) {
+            Some(Node::Integer(_)) => self.nodemap.get_integer(name, &self.transport)?.to_string(),
+            Some(Node::Float(_)) => self.nodemap.get_float(name, &self.transport)?.to_string(),
+            Some(Node::Enum(_)) => self.nodemap.get_enum(name, &self.transport)?,
+            Some(Node::Boolean(_)) => self.nodemap.get_bool(name, &self.transport)?.to_string(),
+            Some(_) => return Err(GenApiError::Type(name.to_string()).into()),
+            None => return Err(GenApiError::NodeNotFound(name.to_string()).into()),
+        };
+        Ok(value)
+    }
+
+    fn clamp_value(&self, name: &str, value: &str) -> Option<String> {
+        match self.nodemap.node(name)? {
+            Node::Integer(node) => {
+                let requested: i64 = value.parse().ok()?;
+                let mut clamped = requested.clamp(node.min, node.max);
+                if let Some(inc) = node.inc.filter(|inc| *inc > 0) {
+                    let offset = (clamped as i128 - node.min as i128) / inc as i128;
+                    clamped = (node.min as i128 + offset * inc as i128) as i64;
+                }
+                (clamped != requested).then(|| clamped.to_string())
+            }
+            Node::Float(node) => {
+                let requested: f64 = value.parse().ok()?;
+                let clamped = requested.clamp(node.min, node.max);
+                (clamped != requested).then(|| clamped.to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Order in which features are written by [`Camera::save_features`].
+struct SavePlan {
+    /// Features without selectors, dependencies first.
+    plain: Vec<String>,
+    /// Selected features grouped by their selector list.
+    groups: Vec<(Vec<String>, Vec<String>)>,
+}
+
+impl SavePlan {
+    fn new<T: RegisterIo>(camera: &Camera<T>) -> Self {
+        let graph = camera.nodemap.dependency_graph();
+        let order = graph.topological_order();
+        let rank: HashMap<&str, usize> = order
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| (name.as_str(), idx))
+            .collect();
+        let mut selectors_of: HashMap<&str, Vec<&str>> = HashMap::new();
+        for edge in &graph.edges {
+            if edge.kind == DependencyKind::Selector {
+                selectors_of.entry(&edge.from).or_default().push(&edge.to);
+            }
+        }
+
+        let mut plan = SavePlan {
+            plain: Vec::new(),
+            groups: Vec::new(),
+        };
+        for name in &order {
+            if !is_persistable(camera.nodemap.node(name)) {
+                continue;
+            }
+            let Some(selectors) = selectors_of.get_mut(name.as_str()) else {
+                plan.plain.push(name.clone());
+                continue;
+            };
+            selectors.sort_by_key(|selector| rank[selector]);
+            let key: Vec<String> = selectors.iter().map(|s| s.to_string()).collect();
+            match plan.groups.iter_mut().find(|(group, _)| *group == key) {
+                Some((_, features)) => features.push(name.clone()),
+                None => plan.groups.push((key, vec![name.clone()])),
+            }
+        }
+        plan
+    }
+}
+
+fn is_persistable(node: Option<&Node>) -> bool {
+    let access = match node {
+        Some(Node::Integer(node)) => node.access,
+        Some(Node::Float(node)) => node.access,
+        Some(Node::Enum(node)) => node.access,
+        Some(Node::Boolean(node)) => node.access,
+        _ => return false,
+    };
+    access == AccessMode::RW
+}
+
+fn push_line(out: &mut String, name: &str, value: &str) {
+    let _ = writeln!(out, "{name}\t{value}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genapi::{MemoryRegisterIo, NodeMap};
+
+    const FIXTURE: &str = r#"
+        <RegisterDescription SchemaMajorVersion="1" SchemaMinorVersion="0" SchemaSubMinorVersion="0">
+            <Integer Name="Width">
+                <Address>0x100</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>16</Min>
+                <Max>4096</Max>
+                <Inc>16</Inc>
+            </Integer>
+            <Integer Name="SensorWidth">
+                <Address>0x104</Address>
+                <Length>4</Length>
+                <AccessMode>RO</AccessMode>
+                <Min>0</Min>
+                <Max>8192</Max>
+            </Integer>
+            <Float Name="ExposureTime">
+                <Address>0x200</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>10.0</Min>
+                <Max>100000.0</Max>
+            </Float>
+            <Float Name="Gamma">
+                <Address>0x204</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>0.0</Min>
+                <Max>4.0</Max>
+                <Scale>1/1000</Scale>
+                <DisplayPrecision>1</DisplayPrecision>
+            </Float>
+            <Enumeration Name="GainSelector">
+                <Address>0x300</Address>
+                <Length>2</Length>
+                <AccessMode>RW</AccessMode>
+                <EnumEntry Name="All" Value="0" />
+                <EnumEntry Name="Red" Value="1" />
+                <EnumEntry Name="Blue" Value="2" />
+            </Enumeration>
+            <Integer Name="Gain">
+                <Length>2</Length>
+                <AccessMode>RW</AccessMode>
+                <Min>0</Min>
+                <Max>48</Max>
+                <pSelected>GainSelector</pSelected>
+                <Selected>All</Selected>
+                <Address>0x310</Address>
+                <Selected>Red</Selected>
+                <Address>0x314</Address>
+                <Selected>Blue</Selected>
+            </Integer>
+        </RegisterDescription>
+    "#;
+
+    fn camera() -> Camera<MemoryRegisterIo> {
+        let model = genapi_xml::parse(FIXTURE).expect("parse fixture");
+        let io = MemoryRegisterIo::from_model(&model).unwrap();
+        Camera::new(io, NodeMap::try_from_xml(model).expect("build nodemap"))
+    }
+
+    #[test]
+    fn save_writes_selected_features_per_selector_value() {
+        let mut cam = camera();
+        cam.set("Width", "640").unwrap();
+        cam.set("ExposureTime", "5000").unwrap();
+        cam.set("GainSelector", "Red").unwrap();
+        cam.set("Gain", "12").unwrap();
+        cam.set("GainSelector", "All").unwrap();
+        cam.set("Gain", "3").unwrap();
+
+        let text = cam.features_to_string().expect("save features");
+        let body: Vec<&str> = text.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(
+            body,
+            vec![
+                "ExposureTime\t5000",
+                "GainSelector\tAll",
+                "Gamma\t0",
+                "Width\t640",
+                "GainSelector\tAll",
+                "Gain\t3",
+                "GainSelector\tBlue",
+                "GainSelector\tRed",
+                "Gain\t12",
+                "GainSelector\tAll",
+            ]
+        );
+        assert!(text.starts_with(FEATURE_STREAM_MAGIC));
+    }
+
+    /// Register space whose reads of `fail_at` report an IO error.
+    struct FlakyIo {
+        inner: MemoryRegisterIo,
+        fail_at: u64,
+    }
+
+    impl RegisterIo for FlakyIo {
+        fn read(&self, addr: u64, len: usize) -> Result<Vec<u8>, GenApiError> {
+            if addr == self.fail_at {
+                return Err(GenApiError::Io("injected read failure".into()));
+            }
+            self.inner.read(addr, len)
+        }
+
+        fn write(&self, addr: u64, data: &[u8]) -> Result<(), GenApiError> {
+            self.inner.write(addr, data)
+        }
+    }
+
+    #[test]
+    fn failed_save_restores_selectors() {
+        let model = genapi_xml::parse(FIXTURE).expect("parse fixture");
+        let io = FlakyIo {
+            inner: MemoryRegisterIo::from_model(&model).unwrap(),
+            fail_at: 0x314,
+        };
+        let mut cam = Camera::new(io, NodeMap::try_from_xml(model).expect("build nodemap"));
+        cam.set("GainSelector", "All").unwrap();
+
+        assert!(cam.features_to_string().is_err());
+        assert_eq!(cam.get("GainSelector").unwrap(), "All");
+        assert_eq!(cam.transport().inner.peek(0x300, 2).unwrap(), vec![0, 0]);
+    }
+
+    #[test]
+    fn load_restores_configuration_on_another_unit() {
+        let mut source = camera();
+        source.set("Width", "1024").unwrap();
+        source.set("GainSelector", "Red").unwrap();
+        source.set("Gain", "7").unwrap();
+        let text = source.features_to_string().unwrap();
+
+        let mut target = camera();
+        let report = target.apply_features(&text);
+        assert!(report.is_clean(), "{report:?}");
+        assert_eq!(target.get("Width").unwrap(), "1024");
+        assert_eq!(target.get("GainSelector").unwrap(), "Red");
+        assert_eq!(target.get("Gain").unwrap(), "7");
+    }
+
+    #[test]
+    fn save_and_load_keep_full_precision() {
+        let mut source = camera();
+        source.set("Gamma", "0.456").unwrap();
+        source.set("Width", "0x280").unwrap();
+        assert_eq!(source.get("Gamma").unwrap(), "0.5");
+        let text = source.features_to_string().unwrap();
+        assert!(text.contains("Gamma\t0.456\n"), "{text}");
+
+        let mut target = camera();
+        let report = target.apply_features(&text);
+        assert!(report.is_clean(), "{report:?}");
+        assert_eq!(
+            target
+                .nodemap()
+                .get_float("Gamma", target.transport())
+                .unwrap(),
+            0.456
+        );
+        assert_eq!(target.get("Width").unwrap(), "640");
+    }
+
+    #[test]
+    fn load_reports_clamped_and_failed_features() {
+        let mut cam = camera();
+        cam.set("Width", "640").unwrap();
+        let report =
+            cam.apply_features("Width\t5000\nExposureTime\t1\nMissing\t1\nSensorWidth\t5\n");
+        assert_eq!(report.applied, 0);
+        assert_eq!(
+            report.clamped,
+            vec![
+                ClampedFeature {
+                    name: "Width".into(),
+                    requested: "5000".into(),
+                    applied: "4096".into(),
+                },
+                ClampedFeature {
+                    name: "ExposureTime".into(),
+                    requested: "1".into(),
+                    applied: "10".into(),
+                },
+            ]
+        );
+        let failed: Vec<&str> = report.failed.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(failed, vec!["Missing", "SensorWidth"]);
+        assert_eq!(cam.get("Width").unwrap(), "4096");
+    }
+}
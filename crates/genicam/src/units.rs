@@ -0,0 +1,304 @@
+//! Parsing and formatting of feature values honouring units and representation.
+
+use std::net::Ipv4Addr;
+
+use crate::genapi::{DisplayHints, DisplayNotation, Representation};
+use crate::GenicamError;
+
+/// Precision used by GenApi when a float does not declare `<DisplayPrecision>`.
+const DEFAULT_DISPLAY_PRECISION: usize = 6;
+
+/// Parse user input for an integer feature.
+///
+/// Accepts decimal and `0x` hexadecimal numbers, dotted IPv4 addresses,
+/// colon or dash separated MAC addresses, and numbers followed by a unit
+/// that converts to the node unit (e.g. `5ms` for a feature in `us`).
+pub(crate) fn parse_integer(
+    name: &str,
+    text: &str,
+    unit: Option<&str>,
+    hints: &DisplayHints,
+) -> Result<i64, GenicamError> {
+    let text = text.trim();
+    let invalid = || GenicamError::parse(format!("invalid integer for {name}: {text}"));
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        return i64::from_str_radix(&hex.replace('_', ""), 16).map_err(|_| invalid());
+    }
+    if hints.representation == Some(Representation::IPV4Address) || text.matches('.').count() == 3 {
+        if let Ok(ip) = text.parse::<Ipv4Addr>() {
+            return Ok(u32::from(ip) as i64);
+        }
+    }
+    if hints.representation == Some(Representation::MACAddress) || is_mac_like(text) {
+        if let Some(mac) = parse_mac(text) {
+            return Ok(mac);
+        }
+    }
+    if hints.representation == Some(Representation::Boolean) {
+        match text.to_ascii_lowercase().as_str() {
+            "true" => return Ok(1),
+            "false" => return Ok(0),
+            _ => {}
+        }
+    }
+    if let Ok(value) = text.parse::<i64>() {
+        return Ok(value);
+    }
+    let (number, suffix) = split_number(text).ok_or_else(invalid)?;
+    let value = convert_unit(name, number, suffix, unit)?;
+    let rounded = value.round();
+    if (value - rounded).abs() > 1e-9 * value.abs().max(1.0) {
+        return Err(GenicamError::parse(format!(
+            "{text} is not a whole number of {} for {name}",
+            unit.unwrap_or("units")
+        )));
+    }
+    Ok(rounded as i64)
+}
+
+/// Parse user input for a float feature, converting units when given.
+pub(crate) fn parse_float(name: &str, text: &str, unit: Option<&str>) -> Result<f64, GenicamError> {
+    let text = text.trim();
+    if let Ok(value) = text.parse::<f64>() {
+        return Ok(value);
+    }
+    let (number, suffix) = split_number(text)
+        .ok_or_else(|| GenicamError::parse(format!("invalid float for {name}: {text}")))?;
+    convert_unit(name, number, suffix, unit)
+}
+
+/// Format an integer according to its declared representation.
+pub(crate) fn format_integer(value: i64, hints: &DisplayHints) -> String {
+    match hints.representation {
+        Some(Representation::HexNumber) => format!("0x{value:X}"),
+        Some(Representation::IPV4Address) => match u32::try_from(value) {
+            Ok(raw) => Ipv4Addr::from(raw).to_string(),
+            Err(_) => value.to_string(),
+        },
+        Some(Representation::MACAddress) if (0..1 << 48).contains(&value) => {
+            let bytes = value.to_be_bytes();
+            bytes[2..]
+                .iter()
+                .map(|b| format!("{b:02X}"))
+                .collect::<Vec<_>>()
+                .join(":")
+        }
+        _ => value.to_string(),
+    }
+}
+
+/// Format a float honouring `<DisplayNotation>` and `<DisplayPrecision>`.
+///
+/// Without a declared precision the automatic notation keeps the shortest
+/// representation that round-trips.
+pub(crate) fn format_float(value: f64, hints: &DisplayHints) -> String {
+    let precision = hints.precision.map(|p| p as usize);
+    match hints.notation {
+        DisplayNotation::Fixed => {
+            format!("{value:.*}", precision.unwrap_or(DEFAULT_DISPLAY_PRECISION))
+        }
+        DisplayNotation::Scientific => {
+            format!(
+                "{value:.*e}",
+                precision.unwrap_or(DEFAULT_DISPLAY_PRECISION)
+            )
+        }
+        DisplayNotation::Automatic => match precision {
+            None => value.to_string(),
+            Some(precision) => format_general(value, precision.max(1)),
+        },
+    }
+}
+
+/// `%g`-style formatting with `precision` significant digits.
+fn format_general(value: f64, precision: usize) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return value.to_string();
+    }
+    let exponent = value.abs().log10().floor() as i32;
+    if exponent < -4 || exponent >= precision as i32 {
+        let text = format!("{value:.*e}", precision - 1);
+        match text.split_once('e') {
+            Some((mantissa, exp)) => format!("{}e{exp}", trim_fraction(mantissa)),
+            None => text,
+        }
+    } else {
+        let decimals = (precision as i32 - 1 - exponent).max(0) as usize;
+        trim_fraction(&format!("{value:.decimals$}")).to_string()
+    }
+}
+
+fn trim_fraction(text: &str) -> &str {
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        text
+    }
+}
+
+/// Split `12.5 dB` into the number and the trimmed unit suffix.
+fn split_number(text: &str) -> Option<(f64, &str)> {
+    let bytes = text.as_bytes();
+    let mut end = 0;
+    if matches!(bytes.first(), Some(b'+' | b'-')) {
+        end += 1;
+    }
+    while end < bytes.len() && (bytes[end].is_ascii_digit() || bytes[end] == b'.') {
+        end += 1;
+    }
+    if end < bytes.len() && matches!(bytes[end], b'e' | b'E') {
+        let mut exp_end = end + 1;
+        if matches!(bytes.get(exp_end), Some(b'+' | b'-')) {
+            exp_end += 1;
+        }
+        if bytes.get(exp_end).is_some_and(u8::is_ascii_digit) {
+            end = exp_end;
+            while end < bytes.len() && bytes[end].is_ascii_digit() {
+                end += 1;
+            }
+        }
+    }
+    let number = text[..end].parse().ok()?;
+    Some((number, text[end..].trim()))
+}
+
+/// Convert `value` expressed in `from` into the node unit.
+fn convert_unit(
+    name: &str,
+    value: f64,
+    from: &str,
+    node_unit: Option<&str>,
+) -> Result<f64, GenicamError> {
+    if from.is_empty() {
+        return Ok(value);
+    }
+    let Some(to) = node_unit else {
+        return Err(GenicamError::parse(format!(
+            "{name} has no unit; cannot interpret '{from}'"
+        )));
+    };
+    if from.eq_ignore_ascii_case(to) {
+        return Ok(value);
+    }
+    match (unit_factor(from), unit_factor(to)) {
+        (Some((from_dim, from_factor)), Some((to_dim, to_factor))) if from_dim == to_dim => {
+            Ok(value * from_factor / to_factor)
+        }
+        _ => Err(GenicamError::parse(format!(
+            "unit '{from}' is not compatible with {name} unit '{to}'"
+        ))),
+    }
+}
+
+/// Dimension and scale factor to the SI base unit for known units.
+fn unit_factor(unit: &str) -> Option<(&'static str, f64)> {
+    let factor = match unit {
+        "s" => ("time", 1.0),
+        "ms" => ("time", 1e-3),
+        "us" | "\u{b5}s" | "\u{3bc}s" => ("time", 1e-6),
+        "ns" => ("time", 1e-9),
+        "Hz" => ("frequency", 1.0),
+        "kHz" => ("frequency", 1e3),
+        "MHz" => ("frequency", 1e6),
+        "GHz" => ("frequency", 1e9),
+        "B" => ("bytes", 1.0),
+        "kB" | "KB" => ("bytes", 1e3),
+        "MB" => ("bytes", 1e6),
+        "Bps" | "B/s" => ("rate", 1.0),
+        "kBps" | "kB/s" => ("rate", 1e3),
+        "MBps" | "MB/s" => ("rate", 1e6),
+        "%" => ("ratio", 1e-2),
+        _ => return None,
+    };
+    Some(factor)
+}
+
+fn is_mac_like(text: &str) -> bool {
+    let separators = text.chars().filter(|c| *c == ':' || *c == '-').count();
+    separators == 5
+}
+
+fn parse_mac(text: &str) -> Option<i64> {
+    let mut value = 0i64;
+    let mut count = 0;
+    for part in text.split([':', '-']) {
+        if part.len() != 2 {
+            return None;
+        }
+        value = (value << 8) | i64::from(u8::from_str_radix(part, 16).ok()?);
+        count += 1;
+    }
+    (count == 6).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hints(representation: Option<Representation>) -> DisplayHints {
+        DisplayHints {
+            representation,
+            ..DisplayHints::default()
+        }
+    }
+
+    #[test]
+    fn integer_inputs_with_units_and_representations() {
+        let plain = hints(None);
+        assert_eq!(parse_integer("X", "0x1F", None, &plain).unwrap(), 31);
+        assert_eq!(parse_integer("X", "-12", None, &plain).unwrap(), -12);
+        assert_eq!(parse_integer("X", "5ms", Some("us"), &plain).unwrap(), 5000);
+        assert_eq!(
+            parse_integer(
+                "X",
+                "192.168.1.10",
+                None,
+                &hints(Some(Representation::IPV4Address))
+            )
+            .unwrap(),
+            0xC0A8_010A
+        );
+        assert_eq!(
+            parse_integer("X", "00:11:22:aa:bb:cc", None, &plain).unwrap(),
+            0x0011_22AA_BBCC
+        );
+        assert!(parse_integer("X", "1.5us", Some("us"), &plain).is_err());
+        assert!(parse_integer("X", "5 dB", Some("us"), &plain).is_err());
+    }
+
+    #[test]
+    fn float_inputs_convert_units() {
+        assert_eq!(parse_float("E", "5ms", Some("us")).unwrap(), 5000.0);
+        assert_eq!(parse_float("G", "12.5 dB", Some("dB")).unwrap(), 12.5);
+        assert_eq!(parse_float("F", "1.5kHz", Some("Hz")).unwrap(), 1500.0);
+        assert_eq!(parse_float("E", "2e3 us", Some("us")).unwrap(), 2000.0);
+        assert!(parse_float("E", "5 dB", Some("us")).is_err());
+        assert!(parse_float("E", "5 ms", None).is_err());
+    }
+
+    #[test]
+    fn formatting_honours_hints() {
+        assert_eq!(
+            format_integer(31, &hints(Some(Representation::HexNumber))),
+            "0x1F"
+        );
+        assert_eq!(
+            format_integer(0xC0A8_010A, &hints(Some(Representation::IPV4Address))),
+            "192.168.1.10"
+        );
+        assert_eq!(
+            format_integer(0x0011_22AA_BBCC, &hints(Some(Representation::MACAddress))),
+            "00:11:22:AA:BB:CC"
+        );
+
+        let mut float = DisplayHints::default();
+        assert_eq!(format_float(5000.0, &float), "5000");
+        float.precision = Some(3);
+        assert_eq!(format_float(1234.5678, &float), "1.23e3");
+        assert_eq!(format_float(12.3456, &float), "12.3");
+        float.notation = DisplayNotation::Fixed;
+        assert_eq!(format_float(12.3456, &float), "12.346");
+        float.notation = DisplayNotation::Scientific;
+        assert_eq!(format_float(12.3456, &float), "1.235e1");
+    }
+}
@@ -0,0 +1,597 @@
+//! Technology Agnostic Bootstrap Register Map (ABRM).
+//!
+//! GenCP devices expose their identity and basic control registers at fixed
+//! addresses starting at zero, so a host can identify a device, keep its
+//! heartbeat alive and locate the GenApi manifest before any XML is parsed.
+//! GigE Vision devices publish the equivalent information in their own
+//! bootstrap map instead.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bitflags::bitflags;
+
+use crate::GenCpError;
+
+/// Register addresses and lengths of the ABRM (GenCP 1.2, table 31).
+pub mod consts {
+    /// `GenCP Version` (major in the upper 16 bits).
+    pub const GENCP_VERSION: u64 = 0x0000;
+    /// `Manufacturer Name` string.
+    pub const MANUFACTURER_NAME: u64 = 0x0004;
+    /// `Model Name` string.
+    pub const MODEL_NAME: u64 = 0x0044;
+    /// `Family Name` string.
+    pub const FAMILY_NAME: u64 = 0x0084;
+    /// `Device Version` string.
+    pub const DEVICE_VERSION: u64 = 0x00C4;
+    /// `Manufacturer Info` string.
+    pub const MANUFACTURER_INFO: u64 = 0x0104;
+    /// `Serial Number` string.
+    pub const SERIAL_NUMBER: u64 = 0x0144;
+    /// `User Defined Name` string.
+    pub const USER_DEFINED_NAME: u64 = 0x0184;
+    /// `Device Capability` bit field (64 bits).
+    pub const DEVICE_CAPABILITY: u64 = 0x01C4;
+    /// `Maximum Device Response Time` in milliseconds.
+    pub const MAX_DEVICE_RESPONSE_TIME: u64 = 0x01CC;
+    /// `Manifest Table Address` (64 bits).
+    pub const MANIFEST_TABLE_ADDRESS: u64 = 0x01D0;
+    /// `SBRM Address` (64 bits).
+    pub const SBRM_ADDRESS: u64 = 0x01D8;
+    /// `Device Configuration` bit field (64 bits).
+    pub const DEVICE_CONFIGURATION: u64 = 0x01E0;
+    /// `Heartbeat Timeout` in milliseconds.
+    pub const HEARTBEAT_TIMEOUT: u64 = 0x01E8;
+    /// `Message Channel ID`.
+    pub const MESSAGE_CHANNEL_ID: u64 = 0x01EC;
+    /// `Timestamp` in device ticks (64 bits).
+    pub const TIMESTAMP: u64 = 0x01F0;
+    /// `Timestamp Latch`; writing 1 latches the current device time.
+    pub const TIMESTAMP_LATCH: u64 = 0x01F8;
+    /// `Timestamp Increment` in nanoseconds per tick (64 bits).
+    pub const TIMESTAMP_INCREMENT: u64 = 0x01FC;
+    /// `Access Privilege`.
+    pub const ACCESS_PRIVILEGE: u64 = 0x0204;
+    /// `Protocol Endianess`: all ones for little endian, zero for big endian.
+    pub const PROTOCOL_ENDIANESS: u64 = 0x0208;
+    /// `Implementation Endianess`.
+    pub const IMPLEMENTATION_ENDIANESS: u64 = 0x020C;
+    /// `Device Software Interface Version` string.
+    pub const DEVICE_SOFTWARE_INTERFACE_VERSION: u64 = 0x0210;
+
+    /// Length of every ABRM string register (bytes, including the terminator).
+    pub const STRING_LENGTH: usize = 64;
+}
+
+bitflags! {
+    /// Optional ABRM features advertised in `Device Capability`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct DeviceCapability: u64 {
+        /// `User Defined Name` is implemented.
+        const USER_DEFINED_NAME = 1 << 0;
+        /// `Access Privilege` is implemented.
+        const ACCESS_PRIVILEGE = 1 << 1;
+        /// The device has a message channel.
+        const MESSAGE_CHANNEL = 1 << 2;
+        /// `Timestamp` and its latch are implemented.
+        const TIMESTAMP = 1 << 3;
+        /// `Family Name` is implemented.
+        const FAMILY_NAME = 1 << 8;
+        /// `SBRM Address` points to a technology specific map.
+        const SBRM = 1 << 9;
+        /// The endianess registers are implemented.
+        const ENDIANESS = 1 << 10;
+        /// Write acknowledgements report the number of bytes written.
+        const WRITTEN_LENGTH = 1 << 11;
+        /// Several events may be sent in one event command.
+        const MULTI_EVENT = 1 << 12;
+        /// Stacked read and write commands are accepted.
+        const STACKED_COMMANDS = 1 << 13;
+        /// `Device Software Interface Version` is implemented.
+        const SOFTWARE_INTERFACE_VERSION = 1 << 14;
+    }
+}
+
+impl DeviceCapability {
+    /// Encoding used by the string registers.
+    pub fn string_encoding(self) -> StringEncoding {
+        match (self.bits() >> 4) & 0xF {
+            2 => StringEncoding::Utf16,
+            1 => StringEncoding::Utf8,
+            _ => StringEncoding::Ascii,
+        }
+    }
+}
+
+/// Character encoding of ABRM string registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StringEncoding {
+    /// 7-bit ASCII.
+    #[default]
+    Ascii,
+    /// UTF-8.
+    Utf8,
+    /// UTF-16 in protocol byte order.
+    Utf16,
+}
+
+/// Byte order of multi-byte ABRM registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ByteOrder {
+    /// Least significant byte first, as used by USB3 Vision.
+    #[default]
+    Little,
+    /// Most significant byte first.
+    Big,
+}
+
+/// Memory-mapped register access provided by a transport.
+#[async_trait]
+pub trait RegisterAccess: Send + Sync {
+    /// Read `len` bytes starting at `addr`.
+    async fn read(&self, addr: u64, len: usize) -> Result<Vec<u8>, GenCpError>;
+    /// Write `data` starting at `addr`.
+    async fn write(&self, addr: u64, data: &[u8]) -> Result<(), GenCpError>;
+}
+
+#[async_trait]
+impl<T: RegisterAccess + ?Sized> RegisterAccess for &T {
+    async fn read(&self, addr: u64, len: usize) -> Result<Vec<u8>, GenCpError> {
+        (**self).read(addr, len).await
+    }
+
+    async fn write(&self, addr: u64, data: &[u8]) -> Result<(), GenCpError> {
+        (**self).write(addr, data).await
+    }
+}
+
+/// Identity strings read from the ABRM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceIdentity {
+    /// Manufacturer name.
+    pub manufacturer: String,
+    /// Model name.
+    pub model: String,
+    /// Family name, when the device implements it.
+    pub family: Option<String>,
+    /// Device version.
+    pub device_version: String,
+    /// Additional manufacturer information.
+    pub manufacturer_info: String,
+    /// Serial number.
+    pub serial_number: String,
+    /// User-defined name, when the device implements it.
+    pub user_defined_name: Option<String>,
+}
+
+/// Typed accessors for the ABRM of one device.
+#[derive(Debug, Clone)]
+pub struct Abrm<R> {
+    regs: R,
+    byte_order: ByteOrder,
+    encoding: StringEncoding,
+}
+
+impl<R: RegisterAccess> Abrm<R> {
+    /// Access a little-endian ABRM with ASCII strings.
+    pub fn new(regs: R) -> Self {
+        Self {
+            regs,
+            byte_order: ByteOrder::Little,
+            encoding: StringEncoding::Ascii,
+        }
+    }
+
+    /// Access the ABRM using the byte order and string encoding the device
+    /// reports, falling back to little endian when it has no endianess
+    /// register.
+    pub async fn detect(regs: R) -> Result<Self, GenCpError> {
+        let mut abrm = Self::new(regs);
+        // The byte order of `Device Capability` is not known yet, so look for
+        // the endianess bit in both.
+        let raw = abrm.read_exact::<8>(consts::DEVICE_CAPABILITY).await?;
+        let has_endianess = [u64::from_le_bytes(raw), u64::from_be_bytes(raw)]
+            .into_iter()
+            .any(|bits| {
+                DeviceCapability::from_bits_retain(bits).contains(DeviceCapability::ENDIANESS)
+            });
+        if has_endianess {
+            // Both valid values read the same in either byte order.
+            let raw = abrm.read_exact::<4>(consts::PROTOCOL_ENDIANESS).await?;
+            if raw == [0, 0, 0, 0] {
+                abrm.byte_order = ByteOrder::Big;
+            }
+        }
+        abrm.encoding = abrm.capabilities().await?.string_encoding();
+        Ok(abrm)
+    }
+
+    /// Use `order` for multi-byte registers instead of little endian.
+    pub fn with_byte_order(mut self, order: ByteOrder) -> Self {
+        self.byte_order = order;
+        self
+    }
+
+    /// Byte order used for multi-byte registers.
+    pub fn byte_order(&self) -> ByteOrder {
+        self.byte_order
+    }
+
+    /// Underlying register access.
+    pub fn registers(&self) -> &R {
+        &self.regs
+    }
+
+    /// Release the underlying register access.
+    pub fn into_inner(self) -> R {
+        self.regs
+    }
+
+    /// GenCP version implemented by the device as `(major, minor)`.
+    pub async fn gencp_version(&self) -> Result<(u16, u16), GenCpError> {
+        let raw = self.read_u32(consts::GENCP_VERSION).await?;
+        Ok(((raw >> 16) as u16, raw as u16))
+    }
+
+    /// Manufacturer name.
+    pub async fn manufacturer_name(&self) -> Result<String, GenCpError> {
+        self.read_string(consts::MANUFACTURER_NAME).await
+    }
+
+    /// Model name.
+    pub async fn model_name(&self) -> Result<String, GenCpError> {
+        self.read_string(consts::MODEL_NAME).await
+    }
+
+    /// Family name; check [`DeviceCapability::FAMILY_NAME`] first.
+    pub async fn family_name(&self) -> Result<String, GenCpError> {
+        self.read_string(consts::FAMILY_NAME).await
+    }
+
+    /// Device version.
+    pub async fn device_version(&self) -> Result<String, GenCpError> {
+        self.read_string(consts::DEVICE_VERSION).await
+    }
+
+    /// Additional manufacturer information.
+    pub async fn manufacturer_info(&self) -> Result<String, GenCpError> {
+        self.read_string(consts::MANUFACTURER_INFO).await
+    }
+
+    /// Serial number.
+    pub async fn serial_number(&self) -> Result<String, GenCpError> {
+        self.read_string(consts::SERIAL_NUMBER).await
+    }
+
+    /// User-defined name; check [`DeviceCapability::USER_DEFINED_NAME`] first.
+    pub async fn user_defined_name(&self) -> Result<String, GenCpError> {
+        self.read_string(consts::USER_DEFINED_NAME).await
+    }
+
+    /// Store a new user-defined name, padded with NUL bytes.
+    pub async fn set_user_defined_name(&self, name: &str) -> Result<(), GenCpError> {
+        let mut data = self.encode_string(name);
+        if data.len() >= consts::STRING_LENGTH {
+            return Err(GenCpError::InvalidValue(format!(
+                "user-defined name '{name}' exceeds {} bytes",
+                consts::STRING_LENGTH - 1
+            )));
+        }
+        data.resize(consts::STRING_LENGTH, 0);
+        self.regs.write(consts::USER_DEFINED_NAME, &data).await
+    }
+
+    /// Device software interface version; check
+    /// [`DeviceCapability::SOFTWARE_INTERFACE_VERSION`] first.
+    pub async fn software_interface_version(&self) -> Result<String, GenCpError> {
+        self.read_string(consts::DEVICE_SOFTWARE_INTERFACE_VERSION)
+            .await
+    }
+
+    /// Read every identity string, skipping the optional ones the device
+    /// does not implement.
+    pub async fn identity(&self) -> Result<DeviceIdentity, GenCpError> {
+        let capabilities = self.capabilities().await?;
+        let family = if capabilities.contains(DeviceCapability::FAMILY_NAME) {
+            Some(self.family_name().await?)
+        } else {
+            None
+        };
+        let user_defined_name = if capabilities.contains(DeviceCapability::USER_DEFINED_NAME) {
+            Some(self.user_defined_name().await?)
+        } else {
+            None
+        };
+        Ok(DeviceIdentity {
+            manufacturer: self.manufacturer_name().await?,
+            model: self.model_name().await?,
+            family,
+            device_version: self.device_version().await?,
+            manufacturer_info: self.manufacturer_info().await?,
+            serial_number: self.serial_number().await?,
+            user_defined_name,
+        })
+    }
+
+    /// Optional features implemented by the device.
+    pub async fn capabilities(&self) -> Result<DeviceCapability, GenCpError> {
+        let raw = self.read_u64(consts::DEVICE_CAPABILITY).await?;
+        Ok(DeviceCapability::from_bits_retain(raw))
+    }
+
+    /// Longest time the device may take to acknowledge a command.
+    pub async fn max_response_time(&self) -> Result<Duration, GenCpError> {
+        let millis = self.read_u32(consts::MAX_DEVICE_RESPONSE_TIME).await?;
+        Ok(Duration::from_millis(millis as u64))
+    }
+
+    /// Address of the manifest table listing the GenApi XML files.
+    pub async fn manifest_table_address(&self) -> Result<u64, GenCpError> {
+        self.read_u64(consts::MANIFEST_TABLE_ADDRESS).await
+    }
+
+    /// Address of the technology specific bootstrap register map.
+    pub async fn sbrm_address(&self) -> Result<u64, GenCpError> {
+        self.read_u64(consts::SBRM_ADDRESS).await
+    }
+
+    /// Raw `Device Configuration` bits.
+    pub async fn device_configuration(&self) -> Result<u64, GenCpError> {
+        self.read_u64(consts::DEVICE_CONFIGURATION).await
+    }
+
+    /// Write raw `Device Configuration` bits.
+    pub async fn set_device_configuration(&self, bits: u64) -> Result<(), GenCpError> {
+        self.write_u64(consts::DEVICE_CONFIGURATION, bits).await
+    }
+
+    /// Heartbeat timeout configured on the device.
+    pub async fn heartbeat_timeout(&self) -> Result<Duration, GenCpError> {
+        let millis = self.read_u32(consts::HEARTBEAT_TIMEOUT).await?;
+        Ok(Duration::from_millis(millis as u64))
+    }
+
+    /// Change the heartbeat timeout (millisecond resolution).
+    pub async fn set_heartbeat_timeout(&self, timeout: Duration) -> Result<(), GenCpError> {
+        let millis = u32::try_from(timeout.as_millis()).map_err(|_| {
+            GenCpError::InvalidValue(format!("heartbeat timeout {timeout:?} out of range"))
+        })?;
+        self.write_u32(consts::HEARTBEAT_TIMEOUT, millis).await
+    }
+
+    /// Identifier of the channel events are sent on.
+    pub async fn message_channel_id(&self) -> Result<u32, GenCpError> {
+        self.read_u32(consts::MESSAGE_CHANNEL_ID).await
+    }
+
+    /// Select the channel events are sent on.
+    pub async fn set_message_channel_id(&self, id: u32) -> Result<(), GenCpError> {
+        self.write_u32(consts::MESSAGE_CHANNEL_ID, id).await
+    }
+
+    /// Latch and read the device timestamp, in ticks.
+    pub async fn timestamp(&self) -> Result<u64, GenCpError> {
+        self.write_u32(consts::TIMESTAMP_LATCH, 1).await?;
+        self.read_u64(consts::TIMESTAMP).await
+    }
+
+    /// Duration of one timestamp tick.
+    pub async fn timestamp_increment(&self) -> Result<Duration, GenCpError> {
+        let nanos = self.read_u64(consts::TIMESTAMP_INCREMENT).await?;
+        Ok(Duration::from_nanos(nanos))
+    }
+
+    /// Raw `Access Privilege` value.
+    pub async fn access_privilege(&self) -> Result<u32, GenCpError> {
+        self.read_u32(consts::ACCESS_PRIVILEGE).await
+    }
+
+    /// Write the raw `Access Privilege` value.
+    pub async fn set_access_privilege(&self, value: u32) -> Result<(), GenCpError> {
+        self.write_u32(consts::ACCESS_PRIVILEGE, value).await
+    }
+
+    async fn read_exact<const N: usize>(&self, addr: u64) -> Result<[u8; N], GenCpError> {
+        let data = self.regs.read(addr, N).await?;
+        data.try_into()
+            .map_err(|_| GenCpError::InvalidPacket("register read returned wrong length"))
+    }
+
+    async fn read_u32(&self, addr: u64) -> Result<u32, GenCpError> {
+        let raw = self.read_exact::<4>(addr).await?;
+        Ok(match self.byte_order {
+            ByteOrder::Little => u32::from_le_bytes(raw),
+            ByteOrder::Big => u32::from_be_bytes(raw),
+        })
+    }
+
+    async fn read_u64(&self, addr: u64) -> Result<u64, GenCpError> {
+        let raw = self.read_exact::<8>(addr).await?;
+        Ok(match self.byte_order {
+            ByteOrder::Little => u64::from_le_bytes(raw),
+            ByteOrder::Big => u64::from_be_bytes(raw),
+        })
+    }
+
+    async fn write_u32(&self, addr: u64, value: u32) -> Result<(), GenCpError> {
+        let raw = match self.byte_order {
+            ByteOrder::Little => value.to_le_bytes(),
+            ByteOrder::Big => value.to_be_bytes(),
+        };
+        self.regs.write(addr, &raw).await
+    }
+
+    async fn write_u64(&self, addr: u64, value: u64) -> Result<(), GenCpError> {
+        let raw = match self.byte_order {
+            ByteOrder::Little => value.to_le_bytes(),
+            ByteOrder::Big => value.to_be_bytes(),
+        };
+        self.regs.write(addr, &raw).await
+    }
+
+    async fn read_string(&self, addr: u64) -> Result<String, GenCpError> {
+        let raw = self.regs.read(addr, consts::STRING_LENGTH).await?;
+        Ok(self.decode_string(&raw))
+    }
+
+    fn decode_string(&self, raw: &[u8]) -> String {
+        let text = match self.encoding {
+            StringEncoding::Ascii | StringEncoding::Utf8 => {
+                let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+                String::from_utf8_lossy(&raw[..end]).into_owned()
+            }
+            StringEncoding::Utf16 => {
+                let units: Vec<u16> = raw
+                    .chunks_exact(2)
+                    .map(|pair| match self.byte_order {
+                        ByteOrder::Little => u16::from_le_bytes([pair[0], pair[1]]),
+                        ByteOrder::Big => u16::from_be_bytes([pair[0], pair[1]]),
+                    })
+                    .take_while(|&unit| unit != 0)
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+        };
+        text.trim().to_string()
+    }
+
+    fn encode_string(&self, text: &str) -> Vec<u8> {
+        match self.encoding {
+            StringEncoding::Ascii | StringEncoding::Utf8 => text.as_bytes().to_vec(),
+            StringEncoding::Utf16 => text
+                .encode_utf16()
+                .flat_map(|unit| match self.byte_order {
+                    ByteOrder::Little => unit.to_le_bytes(),
+                    ByteOrder::Big => unit.to_be_bytes(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Flat register space backed by a byte vector.
+    struct Memory(Mutex<Vec<u8>>);
+
+    impl Memory {
+        fn new() -> Self {
+            Self(Mutex::new(vec![0; 0x0250]))
+        }
+
+        fn put(&self, addr: u64, data: &[u8]) {
+            let addr = addr as usize;
+            self.0.lock().unwrap()[addr..addr + data.len()].copy_from_slice(data);
+        }
+    }
+
+    #[async_trait]
+    impl RegisterAccess for Memory {
+        async fn read(&self, addr: u64, len: usize) -> Result<Vec<u8>, GenCpError> {
+            let memory = self.0.lock().unwrap();
+            let addr = addr as usize;
+            memory
+                .get(addr..addr + len)
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| GenCpError::Transport(format!("read outside memory at {addr:#x}")))
+        }
+
+        async fn write(&self, addr: u64, data: &[u8]) -> Result<(), GenCpError> {
+            self.put(addr, data);
+            Ok(())
+        }
+    }
+
+    fn camera(order: ByteOrder) -> Memory {
+        let memory = Memory::new();
+        let capabilities = (DeviceCapability::USER_DEFINED_NAME
+            | DeviceCapability::TIMESTAMP
+            | DeviceCapability::ENDIANESS)
+            .bits()
+            | 1 << 4;
+        let (version, capabilities, heartbeat, manifest, endianess) = match order {
+            ByteOrder::Little => (
+                0x0001_0002u32.to_le_bytes(),
+                capabilities.to_le_bytes(),
+                3000u32.to_le_bytes(),
+                0x1_0000u64.to_le_bytes(),
+                [0xFF; 4],
+            ),
+            ByteOrder::Big => (
+                0x0001_0002u32.to_be_bytes(),
+                capabilities.to_be_bytes(),
+                3000u32.to_be_bytes(),
+                0x1_0000u64.to_be_bytes(),
+                [0; 4],
+            ),
+        };
+        memory.put(consts::GENCP_VERSION, &version);
+        memory.put(consts::MANUFACTURER_NAME, b"Acme Vision\0garbage");
+        memory.put(consts::MODEL_NAME, b"AV-1920\0");
+        memory.put(consts::FAMILY_NAME, b"Unused\0");
+        memory.put(consts::SERIAL_NUMBER, b"21345678 \0");
+        memory.put(consts::USER_DEFINED_NAME, b"LeftCam\0");
+        memory.put(consts::DEVICE_CAPABILITY, &capabilities);
+        memory.put(consts::HEARTBEAT_TIMEOUT, &heartbeat);
+        memory.put(consts::MANIFEST_TABLE_ADDRESS, &manifest);
+        memory.put(consts::PROTOCOL_ENDIANESS, &endianess);
+        memory
+    }
+
+    #[tokio::test]
+    async fn reads_identity_and_typed_registers() {
+        let memory = camera(ByteOrder::Little);
+        let abrm = Abrm::detect(&memory).await.expect("detect");
+        assert_eq!(abrm.byte_order(), ByteOrder::Little);
+        assert_eq!(abrm.gencp_version().await.unwrap(), (1, 2));
+        assert_eq!(abrm.manifest_table_address().await.unwrap(), 0x1_0000);
+        assert_eq!(
+            abrm.heartbeat_timeout().await.unwrap(),
+            Duration::from_secs(3)
+        );
+        let capabilities = abrm.capabilities().await.unwrap();
+        assert!(capabilities.contains(DeviceCapability::TIMESTAMP));
+        assert_eq!(capabilities.string_encoding(), StringEncoding::Utf8);
+
+        let identity = abrm.identity().await.unwrap();
+        assert_eq!(identity.manufacturer, "Acme Vision");
+        assert_eq!(identity.model, "AV-1920");
+        assert_eq!(identity.family, None);
+        assert_eq!(identity.serial_number, "21345678");
+        assert_eq!(identity.user_defined_name.as_deref(), Some("LeftCam"));
+    }
+
+    #[tokio::test]
+    async fn missing_endianess_register_means_little_endian() {
+        let memory = camera(ByteOrder::Little);
+        let capabilities = DeviceCapability::USER_DEFINED_NAME.bits().to_le_bytes();
+        memory.put(consts::DEVICE_CAPABILITY, &capabilities);
+        memory.put(consts::PROTOCOL_ENDIANESS, &[0; 4]);
+        let abrm = Abrm::detect(&memory).await.expect("detect");
+        assert_eq!(abrm.byte_order(), ByteOrder::Little);
+        assert_eq!(abrm.gencp_version().await.unwrap(), (1, 2));
+    }
+
+    #[tokio::test]
+    async fn writes_use_device_byte_order() {
+        let memory = camera(ByteOrder::Big);
+        let abrm = Abrm::detect(&memory).await.expect("detect");
+        assert_eq!(abrm.byte_order(), ByteOrder::Big);
+        assert_eq!(abrm.gencp_version().await.unwrap(), (1, 2));
+
+        abrm.set_heartbeat_timeout(Duration::from_millis(750))
+            .await
+            .unwrap();
+        assert_eq!(
+            memory.read(consts::HEARTBEAT_TIMEOUT, 4).await.unwrap(),
+            750u32.to_be_bytes()
+        );
+        abrm.set_user_defined_name("RightCam").await.unwrap();
+        assert_eq!(abrm.user_defined_name().await.unwrap(), "RightCam");
+        assert!(abrm.set_user_defined_name(&"x".repeat(64)).await.is_err());
+    }
+}
@@ -0,0 +1,448 @@
+//! Async GenCP client shared by every transport.
+//!
+//! The client owns request ids, retries with backoff, `DEVICE_BUSY` and
+//! `PENDING_ACK` handling and payload chunking; a [`Transport`] only moves
+//! encoded packets. Transports whose wire prefix differs from the GenCP header
+//! produced by [`encode_cmd`](crate::encode_cmd) translate it in their
+//! implementation.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use fastrand::Rng;
+use tokio::sync::Mutex;
+use tokio::time::{self, Instant};
+use tracing::{debug, info, trace, warn};
+
+use crate::{
+    decode_ack, CommandFlags, GenCpAck, GenCpError, OpCode, RegisterAccess, StatusCode,
+    HEADER_SIZE, PENDING_ACK,
+};
+
+/// Packet transport underneath a [`GenCpClient`].
+#[async_trait]
+pub trait Transport: Send {
+    /// Transmit one encoded command packet.
+    async fn send(&mut self, packet: &[u8]) -> Result<(), GenCpError>;
+
+    /// Wait up to `timeout` for the next packet from the device.
+    ///
+    /// Returns `Ok(None)` when nothing arrived in time.
+    async fn recv(&mut self, timeout: Duration) -> Result<Option<Bytes>, GenCpError>;
+
+    /// Largest command or acknowledgement payload the transport carries.
+    fn max_payload(&self) -> usize;
+}
+
+/// Timeouts and retry policy of a [`GenCpClient`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientConfig {
+    /// Time to wait for an acknowledgement before resending.
+    pub timeout: Duration,
+    /// Attempts made before a command fails.
+    pub max_attempts: usize,
+    /// Delay before the first retry; doubled for later ones up to 8x.
+    pub retry_delay: Duration,
+    /// Upper bound of the random delay added to each retry.
+    pub retry_jitter: Duration,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_millis(500),
+            max_attempts: 4,
+            retry_delay: Duration::from_millis(20),
+            retry_jitter: Duration::from_millis(10),
+        }
+    }
+}
+
+/// GenCP command/acknowledgement engine over a [`Transport`].
+pub struct GenCpClient<T> {
+    transport: T,
+    config: ClientConfig,
+    request_id: u16,
+    rng: Rng,
+}
+
+impl<T: Transport> GenCpClient<T> {
+    /// Create a client with the default [`ClientConfig`].
+    pub fn new(transport: T) -> Self {
+        Self::with_config(transport, ClientConfig::default())
+    }
+
+    /// Create a client with an explicit retry policy.
+    pub fn with_config(transport: T, config: ClientConfig) -> Self {
+        Self {
+            transport,
+            config,
+            request_id: 1,
+            rng: Rng::new(),
+        }
+    }
+
+    /// Retry policy in use.
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// Underlying transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Mutable access to the underlying transport.
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Release the underlying transport.
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    fn next_request_id(&mut self) -> u16 {
+        let id = self.request_id;
+        self.request_id = self.request_id.wrapping_add(1);
+        if self.request_id == 0 {
+            self.request_id = 1;
+        }
+        id
+    }
+
+    /// Send a raw `command` and return the acknowledgement packet answering it.
+    ///
+    /// Lost packets and `DEVICE_BUSY` replies are retried under the same
+    /// request id. The acknowledgement is returned whatever its final status, so this
+    /// also serves commands outside [`OpCode`], such as GVCP `FORCEIP`.
+    pub async fn request(&mut self, command: u16, payload: &[u8]) -> Result<Bytes, GenCpError> {
+        // Retransmissions reuse the id so a late acknowledgement of an
+        // earlier attempt still completes the command.
+        let request_id = self.next_request_id();
+        let packet = encode_request(command, payload, request_id);
+        let mut attempt = 0usize;
+        loop {
+            attempt += 1;
+            trace!(
+                request_id,
+                command,
+                bytes = packet.len(),
+                attempt,
+                "sending GenCP command"
+            );
+            let outcome = match self.transport.send(&packet).await {
+                Ok(()) => self.recv_ack(request_id).await,
+                Err(err) => Err(err),
+            };
+            let last = attempt >= self.config.max_attempts;
+            match outcome {
+                Ok(ack) if ack_status(&ack) == StatusCode::DeviceBusy && !last => {
+                    warn!(request_id, attempt, "device busy, retrying");
+                }
+                Ok(ack) => {
+                    trace!(request_id, bytes = ack.len(), attempt, "received GenCP ack");
+                    return Ok(ack);
+                }
+                Err(err) if last => return Err(err),
+                Err(err) => {
+                    warn!(request_id, command, attempt, error = %err, "command failed, retrying");
+                }
+            }
+            self.backoff(attempt).await;
+        }
+    }
+
+    /// Send a raw `command` once without asking for an acknowledgement.
+    ///
+    /// For notifications such as GVCP packet resends, where a retry would
+    /// arrive too late to be useful and waiting would stall other commands.
+    pub async fn notify(&mut self, command: u16, payload: &[u8]) -> Result<(), GenCpError> {
+        let request_id = self.next_request_id();
+        let packet = encode_packet(CommandFlags::empty(), command, payload, request_id);
+        trace!(
+            request_id,
+            command,
+            bytes = packet.len(),
+            "sending GenCP notification"
+        );
+        self.transport.send(&packet).await
+    }
+
+    /// Issue a GenCP command, returning the acknowledgement whatever its
+    /// status so callers can inspect error payloads.
+    pub async fn transact(
+        &mut self,
+        opcode: OpCode,
+        payload: &[u8],
+    ) -> Result<GenCpAck, GenCpError> {
+        let packet = self.request(opcode.command_code(), payload).await?;
+        let ack = decode_ack(&packet)?;
+        if ack.header.opcode != opcode {
+            return Err(GenCpError::InvalidPacket(
+                "unexpected opcode in acknowledgement",
+            ));
+        }
+        Ok(ack)
+    }
+
+    async fn transact_ok(
+        &mut self,
+        opcode: OpCode,
+        payload: &[u8],
+    ) -> Result<GenCpAck, GenCpError> {
+        let ack = self.transact(opcode, payload).await?;
+        match ack.header.status {
+            StatusCode::Success => Ok(ack),
+            other => Err(GenCpError::Status(other)),
+        }
+    }
+
+    /// Read `len` bytes at `addr`, split into transport-sized `READMEM`s.
+    pub async fn read_mem(&mut self, addr: u64, len: usize) -> Result<Vec<u8>, GenCpError> {
+        let block = self.transport.max_payload().min(u16::MAX as usize);
+        if block == 0 && len > 0 {
+            return Err(GenCpError::InvalidValue(
+                "transport payload too small for READMEM".into(),
+            ));
+        }
+        let mut data = Vec::with_capacity(len);
+        while data.len() < len {
+            let chunk = (len - data.len()).min(block);
+            let mut payload = BytesMut::with_capacity(12);
+            payload.put_u64(addr + data.len() as u64);
+            payload.put_u16(0); // Reserved as per spec.
+            payload.put_u16(chunk as u16);
+            let ack = self.transact_ok(OpCode::ReadMem, &payload).await?;
+            if ack.payload.len() != chunk {
+                return Err(GenCpError::InvalidPacket("READMEM returned wrong length"));
+            }
+            data.extend_from_slice(&ack.payload);
+        }
+        Ok(data)
+    }
+
+    /// Write `data` at `addr`, split into transport-sized `WRITEMEM`s.
+    pub async fn write_mem(&mut self, addr: u64, data: &[u8]) -> Result<(), GenCpError> {
+        let block = self.transport.max_payload().saturating_sub(8);
+        if block == 0 && !data.is_empty() {
+            return Err(GenCpError::InvalidValue(
+                "transport payload too small for WRITEMEM".into(),
+            ));
+        }
+        for (idx, chunk) in data.chunks(block.max(1)).enumerate() {
+            let mut payload = BytesMut::with_capacity(8 + chunk.len());
+            payload.put_u64(addr + (idx * block) as u64);
+            payload.extend_from_slice(chunk);
+            self.transact_ok(OpCode::WriteMem, &payload).await?;
+        }
+        Ok(())
+    }
+
+    /// Wait for the acknowledgement of `request_id`.
+    ///
+    /// Replies to earlier attempts are dropped, and a `PENDING_ACK` extends
+    /// the wait without resending the command.
+    async fn recv_ack(&mut self, request_id: u16) -> Result<Bytes, GenCpError> {
+        let mut deadline = Instant::now() + self.config.timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(GenCpError::Timeout);
+            }
+            let Some(packet) = self.transport.recv(remaining).await? else {
+                return Err(GenCpError::Timeout);
+            };
+            if packet.len() < HEADER_SIZE {
+                debug!(
+                    request_id,
+                    bytes = packet.len(),
+                    "ignoring truncated packet"
+                );
+                continue;
+            }
+            let mut header = &packet[..HEADER_SIZE];
+            let _status = header.get_u16();
+            let command = header.get_u16();
+            let _length = header.get_u16();
+            let got = header.get_u16();
+            if got != request_id {
+                debug!(
+                    request_id,
+                    got, "ignoring acknowledgement for another request"
+                );
+                continue;
+            }
+            if command == PENDING_ACK {
+                let completion = pending_completion(&packet);
+                let extension = completion.max(self.config.timeout);
+                info!(
+                    request_id,
+                    ?completion,
+                    ?extension,
+                    "device sent PENDING_ACK; extending wait"
+                );
+                deadline = Instant::now() + extension;
+                continue;
+            }
+            return Ok(packet);
+        }
+    }
+
+    async fn backoff(&mut self, attempt: usize) {
+        let multiplier = 1u32 << attempt.saturating_sub(1).min(3);
+        let jitter_ms = self.rng.u64(..=self.config.retry_jitter.as_millis() as u64);
+        let delay = self.config.retry_delay * multiplier + Duration::from_millis(jitter_ms);
+        debug!(attempt, ?delay, "GenCP retry backoff");
+        time::sleep(delay).await;
+    }
+}
+
+/// Share one client between callers; each access holds the lock for the
+/// whole transfer.
+#[async_trait]
+impl<T: Transport> RegisterAccess for Mutex<GenCpClient<T>> {
+    async fn read(&self, addr: u64, len: usize) -> Result<Vec<u8>, GenCpError> {
+        self.lock().await.read_mem(addr, len).await
+    }
+
+    async fn write(&self, addr: u64, data: &[u8]) -> Result<(), GenCpError> {
+        self.lock().await.write_mem(addr, data).await
+    }
+}
+
+fn encode_request(command: u16, payload: &[u8], request_id: u16) -> Bytes {
+    encode_packet(CommandFlags::ACK_REQUIRED, command, payload, request_id)
+}
+
+fn encode_packet(flags: CommandFlags, command: u16, payload: &[u8], request_id: u16) -> Bytes {
+    let mut packet = BytesMut::with_capacity(HEADER_SIZE + payload.len());
+    packet.put_u16(flags.bits());
+    packet.put_u16(command);
+    packet.put_u16(payload.len() as u16);
+    packet.put_u16(request_id);
+    packet.extend_from_slice(payload);
+    packet.freeze()
+}
+
+fn ack_status(packet: &[u8]) -> StatusCode {
+    StatusCode::from_raw(u16::from_be_bytes([packet[0], packet[1]]))
+}
+
+/// Time to completion announced by a `PENDING_ACK` (reserved u16, ms u16).
+fn pending_completion(packet: &[u8]) -> Duration {
+    match packet.get(HEADER_SIZE + 2..HEADER_SIZE + 4) {
+        Some(&[hi, lo]) => Duration::from_millis(u16::from_be_bytes([hi, lo]) as u64),
+        _ => Duration::ZERO,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abrm::{self, Abrm};
+    use crate::loopback::{Fault, Loopback};
+
+    fn client(device: Loopback) -> GenCpClient<Loopback> {
+        GenCpClient::with_config(
+            device,
+            ClientConfig {
+                retry_delay: Duration::from_millis(1),
+                retry_jitter: Duration::ZERO,
+                ..ClientConfig::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn chunks_reads_and_writes_to_payload_limit() {
+        let mut client = client(Loopback::new(4096).max_payload(64));
+        let data: Vec<u8> = (0..200).map(|b| b as u8).collect();
+        client.write_mem(0x100, &data).await.unwrap();
+        assert_eq!(client.transport().commands(), 4);
+        assert_eq!(client.read_mem(0x100, 200).await.unwrap(), data);
+        assert_eq!(client.transport().commands(), 8);
+        assert_eq!(&client.transport().memory()[0x100..0x100 + 200], &data[..]);
+    }
+
+    #[tokio::test]
+    async fn rejects_transports_without_room_for_data() {
+        let mut client = client(Loopback::new(64).max_payload(0));
+        assert!(matches!(
+            client.read_mem(0, 4).await,
+            Err(GenCpError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            client.write_mem(0, &[1]).await,
+            Err(GenCpError::InvalidValue(_))
+        ));
+        assert_eq!(client.transport().commands(), 0);
+    }
+
+    #[tokio::test]
+    async fn retries_lost_busy_and_stale_replies() {
+        let mut device = Loopback::new(64);
+        device.inject(Fault::Drop);
+        device.inject(Fault::Busy);
+        device.inject(Fault::StaleAck);
+        let mut client = client(device);
+        client.write_mem(0, &[1, 2, 3, 4]).await.unwrap();
+        // The drop and the busy reply each cost one extra attempt; the stale
+        // acknowledgement is skipped without resending.
+        assert_eq!(client.transport().commands(), 3);
+        assert_eq!(client.transport().request_ids(), [1, 1, 1]);
+        assert_eq!(client.read_mem(0, 4).await.unwrap(), vec![1, 2, 3, 4]);
+        assert_eq!(client.transport().request_ids()[3], 2);
+    }
+
+    #[tokio::test]
+    async fn pending_ack_waits_without_resending() {
+        let mut device = Loopback::new(64);
+        device.inject(Fault::Pending(Duration::from_secs(2)));
+        let mut client = client(device);
+        client.read_mem(0, 4).await.unwrap();
+        assert_eq!(client.transport().commands(), 1);
+    }
+
+    #[tokio::test]
+    async fn reports_timeouts_and_error_status() {
+        let mut device = Loopback::new(64);
+        for _ in 0..2 {
+            device.inject(Fault::Drop);
+        }
+        let mut client = GenCpClient::with_config(
+            device,
+            ClientConfig {
+                max_attempts: 2,
+                retry_delay: Duration::from_millis(1),
+                retry_jitter: Duration::ZERO,
+                ..ClientConfig::default()
+            },
+        );
+        assert!(matches!(
+            client.read_mem(0, 4).await,
+            Err(GenCpError::Timeout)
+        ));
+        assert!(matches!(
+            client.read_mem(60, 8).await,
+            Err(GenCpError::Status(StatusCode::InvalidAddress))
+        ));
+    }
+
+    #[tokio::test]
+    async fn serves_abrm_through_shared_client() {
+        let mut memory = vec![0u8; 0x0250];
+        memory[abrm::consts::MODEL_NAME as usize..][..7].copy_from_slice(b"AV-1920");
+        let client = Mutex::new(client(Loopback::with_memory(memory)));
+        let abrm = Abrm::new(&client);
+        assert_eq!(abrm.model_name().await.unwrap(), "AV-1920");
+        abrm.set_heartbeat_timeout(Duration::from_millis(1500))
+            .await
+            .unwrap();
+        assert_eq!(
+            abrm.heartbeat_timeout().await.unwrap(),
+            Duration::from_millis(1500)
+        );
+    }
+}
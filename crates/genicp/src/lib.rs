@@ -5,9 +5,19 @@ use bitflags::bitflags;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use thiserror::Error;
 
+pub mod abrm;
+pub mod client;
+pub mod loopback;
+
+pub use abrm::{Abrm, DeviceIdentity, RegisterAccess};
+pub use client::{ClientConfig, GenCpClient, Transport};
+
 /// Size of the GenCP header (in bytes).
 pub const HEADER_SIZE: usize = 8;
 
+/// Acknowledgement code asking the host to wait longer for the real reply.
+pub const PENDING_ACK: u16 = 0x0089;
+
 bitflags! {
     /// Flags that can be set on a GenCP command packet.
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -22,6 +32,10 @@ bitflags! {
 /// GenCP operation codes supported by this crate.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OpCode {
+    /// Read one or more 32-bit registers at scattered addresses (GVCP only).
+    ReadReg,
+    /// Write one or more 32-bit registers at scattered addresses (GVCP only).
+    WriteReg,
     /// Read a block of memory from the device.
     ReadMem,
     /// Write a block of memory to the device.
@@ -32,6 +46,8 @@ impl OpCode {
     /// Raw command value as defined by the GenCP/GVCP specification.
     pub const fn command_code(self) -> u16 {
         match self {
+            OpCode::ReadReg => 0x0080,
+            OpCode::WriteReg => 0x0082,
             OpCode::ReadMem => 0x0084,
             OpCode::WriteMem => 0x0086,
         }
@@ -42,9 +58,10 @@ impl OpCode {
         self.command_code() + 1
     }
 
-    #[allow(dead_code)]
     fn from_command(code: u16) -> Result<Self, GenCpError> {
         match code {
+            0x0080 => Ok(OpCode::ReadReg),
+            0x0082 => Ok(OpCode::WriteReg),
             0x0084 => Ok(OpCode::ReadMem),
             0x0086 => Ok(OpCode::WriteMem),
             _ => Err(GenCpError::UnknownOpcode(code)),
@@ -53,6 +70,8 @@ impl OpCode {
 
     fn from_ack(code: u16) -> Result<Self, GenCpError> {
         match code {
+            0x0081 => Ok(OpCode::ReadReg),
+            0x0083 => Ok(OpCode::WriteReg),
             0x0085 => Ok(OpCode::ReadMem),
             0x0087 => Ok(OpCode::WriteMem),
             _ => Err(GenCpError::UnknownOpcode(code)),
@@ -116,6 +135,14 @@ pub enum GenCpError {
     UnknownOpcode(u16),
     #[error("io: {0}")]
     Io(#[from] std::io::Error),
+    #[error("transport: {0}")]
+    Transport(String),
+    #[error("invalid value: {0}")]
+    InvalidValue(String),
+    #[error("timeout waiting for acknowledgement")]
+    Timeout,
+    #[error("device reported status {0:?}")]
+    Status(StatusCode),
 }
 
 /// Command header for GenCP requests.
@@ -176,6 +203,30 @@ pub fn encode_cmd(cmd: &GenCpCmd) -> Bytes {
     buffer.freeze()
 }
 
+/// Decode a GenCP command from raw bytes, as a device would.
+pub fn decode_cmd(buf: &[u8]) -> Result<GenCpCmd, GenCpError> {
+    if buf.len() < HEADER_SIZE {
+        return Err(GenCpError::InvalidPacket("too short"));
+    }
+    let mut cursor = buf;
+    let flags = CommandFlags::from_bits_retain(cursor.get_u16());
+    let opcode = OpCode::from_command(cursor.get_u16())?;
+    let length = cursor.get_u16();
+    let request_id = cursor.get_u16();
+    if buf.len() != HEADER_SIZE + length as usize {
+        return Err(GenCpError::InvalidPacket("length mismatch"));
+    }
+    Ok(GenCpCmd {
+        header: CommandHeader {
+            flags,
+            opcode,
+            length,
+            request_id,
+        },
+        payload: Bytes::copy_from_slice(&buf[HEADER_SIZE..]),
+    })
+}
+
 /// Decode a GenCP acknowledgement from raw bytes.
 pub fn decode_ack(buf: &[u8]) -> Result<GenCpAck, GenCpError> {
     if buf.len() < HEADER_SIZE {
@@ -230,6 +281,8 @@ mod tests {
         };
 
         let encoded = encode_cmd(&cmd);
+        let decoded = decode_cmd(&encoded).expect("decode command");
+        assert_eq!(decoded.header, cmd.header);
         assert_eq!(
             &encoded[..2],
             &CommandFlags::ACK_REQUIRED.bits().to_be_bytes()
@@ -271,4 +324,21 @@ mod tests {
         assert_eq!(ack.header.status, StatusCode::Success);
         assert_eq!(ack.payload.len(), 0);
     }
+
+    #[test]
+    fn decode_write_reg_ack() {
+        let mut buf = BytesMut::with_capacity(HEADER_SIZE + 4);
+        buf.put_u16(StatusCode::InvalidAddress.to_raw());
+        buf.put_u16(0x0083);
+        buf.put_u16(4);
+        buf.put_u16(0x0007);
+        buf.put_u16(0);
+        buf.put_u16(2);
+        let ack = decode_ack(&buf).expect("decode");
+        assert_eq!(ack.header.opcode, OpCode::WriteReg);
+        assert_eq!(ack.header.status, StatusCode::InvalidAddress);
+        assert_eq!(&ack.payload[..], &[0, 0, 0, 2]);
+        assert_eq!(OpCode::from_command(0x0080).unwrap(), OpCode::ReadReg);
+        assert_eq!(OpCode::ReadReg.ack_code(), 0x0081);
+    }
 }
@@ -0,0 +1,157 @@
+//! In-memory GenCP device for exercising clients without hardware.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use crate::client::Transport;
+use crate::{decode_cmd, GenCpError, OpCode, StatusCode, HEADER_SIZE, PENDING_ACK};
+
+/// Misbehaviour applied to the next command a [`Loopback`] receives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Swallow the command without replying.
+    Drop,
+    /// Answer with `DEVICE_BUSY` without executing the command.
+    Busy,
+    /// Send a `PENDING_ACK` announcing the given completion time first.
+    Pending(Duration),
+    /// Send an acknowledgement for the previous request id first.
+    StaleAck,
+}
+
+/// Transport answering `READMEM`/`WRITEMEM` from a flat register space.
+///
+/// Replies are queued synchronously, so a dropped command reads as an
+/// immediate timeout and tests run without real delays.
+#[derive(Debug, Clone)]
+pub struct Loopback {
+    memory: Vec<u8>,
+    max_payload: usize,
+    faults: VecDeque<Fault>,
+    replies: VecDeque<Bytes>,
+    commands: usize,
+    request_ids: Vec<u16>,
+}
+
+impl Loopback {
+    /// Zero-filled register space of `size` bytes.
+    pub fn new(size: usize) -> Self {
+        Self::with_memory(vec![0; size])
+    }
+
+    /// Register space initialised with `memory`, mapped at address zero.
+    pub fn with_memory(memory: Vec<u8>) -> Self {
+        Self {
+            memory,
+            max_payload: 512,
+            faults: VecDeque::new(),
+            replies: VecDeque::new(),
+            commands: 0,
+            request_ids: Vec::new(),
+        }
+    }
+
+    /// Limit the payload size reported to clients.
+    pub fn max_payload(mut self, max_payload: usize) -> Self {
+        self.max_payload = max_payload;
+        self
+    }
+
+    /// Queue a fault for the next command without one.
+    pub fn inject(&mut self, fault: Fault) {
+        self.faults.push_back(fault);
+    }
+
+    /// Current register contents.
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Number of command packets received so far.
+    pub fn commands(&self) -> usize {
+        self.commands
+    }
+
+    /// Request ids of the well-formed commands received so far.
+    pub fn request_ids(&self) -> &[u16] {
+        &self.request_ids
+    }
+
+    fn execute(&mut self, opcode: OpCode, mut payload: &[u8]) -> (StatusCode, Vec<u8>) {
+        if payload.len() < 8 {
+            return (StatusCode::InvalidParameter, Vec::new());
+        }
+        let addr = payload.get_u64() as usize;
+        match opcode {
+            OpCode::ReadMem if payload.len() == 4 => {
+                let _reserved = payload.get_u16();
+                let len = payload.get_u16() as usize;
+                match self.memory.get(addr..addr + len) {
+                    Some(data) => (StatusCode::Success, data.to_vec()),
+                    None => (StatusCode::InvalidAddress, Vec::new()),
+                }
+            }
+            OpCode::WriteMem => match self.memory.get_mut(addr..addr + payload.len()) {
+                Some(target) => {
+                    target.copy_from_slice(payload);
+                    (StatusCode::Success, Vec::new())
+                }
+                None => (StatusCode::InvalidAddress, Vec::new()),
+            },
+            _ => (StatusCode::NotImplemented, Vec::new()),
+        }
+    }
+
+    fn reply(&mut self, status: StatusCode, command: u16, request_id: u16, payload: &[u8]) {
+        let mut ack = BytesMut::with_capacity(HEADER_SIZE + payload.len());
+        ack.put_u16(status.to_raw());
+        ack.put_u16(command);
+        ack.put_u16(payload.len() as u16);
+        ack.put_u16(request_id);
+        ack.extend_from_slice(payload);
+        self.replies.push_back(ack.freeze());
+    }
+}
+
+#[async_trait]
+impl Transport for Loopback {
+    async fn send(&mut self, packet: &[u8]) -> Result<(), GenCpError> {
+        self.commands += 1;
+        let cmd = decode_cmd(packet)?;
+        let ack_code = cmd.header.opcode.ack_code();
+        let request_id = cmd.header.request_id;
+        self.request_ids.push(request_id);
+        match self.faults.pop_front() {
+            Some(Fault::Drop) => return Ok(()),
+            Some(Fault::Busy) => {
+                self.reply(StatusCode::DeviceBusy, ack_code, request_id, &[]);
+                return Ok(());
+            }
+            Some(Fault::Pending(completion)) => {
+                let millis = completion.as_millis().min(u16::MAX as u128) as u16;
+                let mut payload = [0u8; 4];
+                payload[2..].copy_from_slice(&millis.to_be_bytes());
+                self.reply(StatusCode::Success, PENDING_ACK, request_id, &payload);
+            }
+            Some(Fault::StaleAck) => {
+                let stale = request_id.wrapping_sub(1);
+                self.reply(StatusCode::Success, ack_code, stale, &[]);
+            }
+            None => {}
+        }
+        let (status, payload) = self.execute(cmd.header.opcode, &cmd.payload);
+        self.reply(status, ack_code, request_id, &payload);
+        Ok(())
+    }
+
+    async fn recv(&mut self, _timeout: Duration) -> Result<Option<Bytes>, GenCpError> {
+        Ok(self.replies.pop_front())
+    }
+
+    fn max_payload(&self) -> usize {
+        self.max_payload
+    }
+}
@@ -0,0 +1,464 @@
+//! Shared GVCP control channel.
+//!
+//! A device accepts one outstanding command per control socket, so a single
+//! task owns the socket and serves every [`ControlHandle`] in turn. Handles are
+//! cheap to clone: register access, packet resend requests and heartbeats from
+//! different tasks all travel over the same channel without stepping on each
+//! other's acknowledgements.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use genicp::{
+    decode_ack, ClientConfig, GenCpAck, GenCpClient, GenCpError, OpCode, StatusCode, Transport,
+};
+use tokio::net::UdpSocket;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time;
+use tracing::{trace, warn};
+
+use crate::gvcp::{consts, GigeError};
+
+/// Requests allowed to queue for the control task before senders wait.
+const QUEUE_DEPTH: usize = 32;
+
+/// Command waiting to be sent by the control task.
+enum Request {
+    /// Retried until acknowledged; the acknowledgement goes to `reply`.
+    Command {
+        command: u16,
+        payload: Bytes,
+        reply: oneshot::Sender<Result<Bytes, GigeError>>,
+    },
+    /// Sent once without waiting for an acknowledgement.
+    Notify { command: u16, payload: Bytes },
+    /// Memory read split into `READMEM`s by the client.
+    ReadMem {
+        addr: u64,
+        len: usize,
+        reply: oneshot::Sender<Result<Vec<u8>, GigeError>>,
+    },
+    /// Memory write split into `WRITEMEM`s by the client.
+    WriteMem {
+        addr: u64,
+        data: Bytes,
+        reply: oneshot::Sender<Result<(), GigeError>>,
+    },
+}
+
+/// Cloneable handle to the control channel of one device.
+///
+/// The task serving the channel stops once every handle has been dropped.
+#[derive(Debug, Clone)]
+pub struct ControlHandle {
+    requests: mpsc::Sender<Request>,
+    remote: SocketAddr,
+}
+
+impl ControlHandle {
+    /// Open a control socket towards `addr` and start the task serving it.
+    pub async fn connect(addr: SocketAddr) -> Result<Self, GigeError> {
+        let local_ip = match addr.ip() {
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            IpAddr::V6(_) => {
+                return Err(GigeError::Protocol("IPv6 GVCP is not supported".into()));
+            }
+        };
+        let socket = UdpSocket::bind(SocketAddr::new(local_ip, 0)).await?;
+        socket.connect(addr).await?;
+        let (requests, queue) = mpsc::channel(QUEUE_DEPTH);
+        tokio::spawn(Server::new(socket).run(queue));
+        Ok(Self {
+            requests,
+            remote: addr,
+        })
+    }
+
+    /// Remote GVCP socket address served by this channel.
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.remote
+    }
+
+    /// Send a raw GVCP command and return the acknowledgement datagram.
+    async fn request(&self, command: u16, payload: Bytes) -> Result<Bytes, GigeError> {
+        let (reply, response) = oneshot::channel();
+        let request = Request::Command {
+            command,
+            payload,
+            reply,
+        };
+        self.submit(request, response).await
+    }
+
+    /// Queue `request` for the control task and wait for its `response`.
+    async fn submit<T>(
+        &self,
+        request: Request,
+        response: oneshot::Receiver<Result<T, GigeError>>,
+    ) -> Result<T, GigeError> {
+        let stopped = || GigeError::Protocol("control channel task stopped".into());
+        self.requests.send(request).await.map_err(|_| stopped())?;
+        response.await.map_err(|_| stopped())?
+    }
+
+    /// Queue a raw GVCP command that is sent once and never acknowledged.
+    async fn notify(&self, command: u16, payload: Bytes) -> Result<(), GigeError> {
+        self.requests
+            .send(Request::Notify { command, payload })
+            .await
+            .map_err(|_| GigeError::Protocol("control channel task stopped".into()))
+    }
+
+    /// Issue a GenCP command with retries, returning the acknowledgement
+    /// whatever its final status so callers can inspect error payloads.
+    pub async fn transact(&self, opcode: OpCode, payload: Bytes) -> Result<GenCpAck, GigeError> {
+        let datagram = self.request(opcode.command_code(), payload).await?;
+        let ack = decode_ack(&datagram)?;
+        if ack.header.opcode != opcode {
+            return Err(GigeError::Protocol(
+                "unexpected opcode in acknowledgement".into(),
+            ));
+        }
+        Ok(ack)
+    }
+
+    /// Read a block of memory from the remote device with chunking and retries.
+    pub async fn read_mem(&self, addr: u64, len: usize) -> Result<Vec<u8>, GigeError> {
+        let (reply, response) = oneshot::channel();
+        self.submit(Request::ReadMem { addr, len, reply }, response)
+            .await
+    }
+
+    /// Write a block of memory to the remote device with chunking and retries.
+    pub async fn write_mem(&self, addr: u64, data: &[u8]) -> Result<(), GigeError> {
+        let (reply, response) = oneshot::channel();
+        let data = Bytes::copy_from_slice(data);
+        self.submit(Request::WriteMem { addr, data, reply }, response)
+            .await
+    }
+
+    /// Read 32-bit registers at scattered addresses using GVCP `READREG`.
+    ///
+    /// Addresses are batched into as few commands as possible. When the device
+    /// rejects one of them the call fails with [`GigeError::RegisterAccess`]
+    /// identifying the first register that could not be read.
+    pub async fn read_regs(&self, addrs: &[u64]) -> Result<Vec<u32>, GigeError> {
+        let mut values = Vec::with_capacity(addrs.len());
+        for chunk in addrs.chunks(consts::READREG_MAX_COUNT) {
+            let payload = encode_read_regs(chunk)?;
+            let ack = self.transact(OpCode::ReadReg, payload.freeze()).await?;
+            let read = decode_reg_values(&ack.payload)?;
+            if ack.header.status != StatusCode::Success {
+                // Devices return the values read before the failing address.
+                let index = values.len() + read.len().min(chunk.len());
+                warn!(index, status = ?ack.header.status, "READREG failed");
+                return Err(GigeError::RegisterAccess {
+                    index,
+                    status: ack.header.status,
+                });
+            }
+            if read.len() != chunk.len() {
+                return Err(GigeError::Protocol(format!(
+                    "expected {} register values but device returned {}",
+                    chunk.len(),
+                    read.len()
+                )));
+            }
+            values.extend(read);
+        }
+        Ok(values)
+    }
+
+    /// Write 32-bit registers at scattered addresses using GVCP `WRITEREG`.
+    ///
+    /// Writes are applied in order and batched into as few commands as
+    /// possible. When the device rejects one of them the call fails with
+    /// [`GigeError::RegisterAccess`]; registers before the reported index were
+    /// written and later ones were not.
+    pub async fn write_regs(&self, regs: &[(u64, u32)]) -> Result<(), GigeError> {
+        let mut written = 0usize;
+        for chunk in regs.chunks(consts::WRITEREG_MAX_COUNT) {
+            let payload = encode_write_regs(chunk)?;
+            let ack = self.transact(OpCode::WriteReg, payload.freeze()).await?;
+            if ack.header.status != StatusCode::Success {
+                let failed = decode_write_reg_index(&ack.payload)?;
+                let index = written + failed.min(chunk.len());
+                warn!(index, status = ?ack.header.status, "WRITEREG failed");
+                return Err(GigeError::RegisterAccess {
+                    index,
+                    status: ack.header.status,
+                });
+            }
+            written += chunk.len();
+        }
+        Ok(())
+    }
+
+    /// Read a single register with `READREG`.
+    pub(crate) async fn read_reg(&self, addr: u64) -> Result<u32, GigeError> {
+        match self.read_regs(&[addr]).await?[..] {
+            [value] => Ok(value),
+            _ => Err(GigeError::Protocol(
+                "READREG acknowledgement length mismatch".into(),
+            )),
+        }
+    }
+
+    /// Request resend of a packet range for the provided block identifier.
+    ///
+    /// The request is sent once without an acknowledgement: a late resend is
+    /// worthless, and waiting for one would delay heartbeats and register
+    /// access queued behind it.
+    pub async fn request_resend(
+        &self,
+        block_id: u16,
+        first_packet: u16,
+        last_packet: u16,
+    ) -> Result<(), GigeError> {
+        let mut payload = BytesMut::with_capacity(8);
+        payload.put_u16(block_id);
+        payload.put_u16(0); // Reserved as per spec.
+        payload.put_u16(first_packet);
+        payload.put_u16(last_packet);
+        trace!(
+            block_id,
+            first_packet,
+            last_packet,
+            "sending packet resend request"
+        );
+        self.notify(consts::PACKET_RESEND_COMMAND, payload.freeze())
+            .await
+    }
+}
+
+/// Connected UDP socket carrying GVCP for a [`GenCpClient`].
+struct UdpTransport {
+    socket: UdpSocket,
+    buf: Vec<u8>,
+}
+
+#[async_trait]
+impl Transport for UdpTransport {
+    async fn send(&mut self, packet: &[u8]) -> Result<(), GenCpError> {
+        self.socket.send(packet).await?;
+        Ok(())
+    }
+
+    async fn recv(&mut self, timeout: Duration) -> Result<Option<Bytes>, GenCpError> {
+        match time::timeout(timeout, self.socket.recv(&mut self.buf)).await {
+            Ok(Ok(len)) => Ok(Some(Bytes::copy_from_slice(&self.buf[..len]))),
+            Ok(Err(err)) => Err(err.into()),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn max_payload(&self) -> usize {
+        consts::GENCP_MAX_BLOCK
+    }
+}
+
+/// Task owning the control socket.
+struct Server {
+    client: GenCpClient<UdpTransport>,
+}
+
+impl Server {
+    fn new(socket: UdpSocket) -> Self {
+        let transport = UdpTransport {
+            socket,
+            buf: vec![0u8; genicp::HEADER_SIZE + consts::GVCP_MAX_PAYLOAD],
+        };
+        let config = ClientConfig {
+            timeout: consts::CONTROL_TIMEOUT,
+            max_attempts: consts::MAX_RETRIES,
+            retry_delay: consts::RETRY_BASE_DELAY,
+            retry_jitter: consts::RETRY_JITTER,
+        };
+        Self {
+            client: GenCpClient::with_config(transport, config),
+        }
+    }
+
+    async fn run(mut self, mut queue: mpsc::Receiver<Request>) {
+        while let Some(request) = queue.recv().await {
+            match request {
+                Request::Command {
+                    command,
+                    payload,
+                    reply,
+                } => {
+                    let result = self
+                        .client
+                        .request(command, &payload)
+                        .await
+                        .map_err(gige_error);
+                    // The caller may have given up waiting; nothing to report then.
+                    let _ = reply.send(result);
+                }
+                Request::ReadMem { addr, len, reply } => {
+                    let result = self.client.read_mem(addr, len).await.map_err(gige_error);
+                    let _ = reply.send(result);
+                }
+                Request::WriteMem { addr, data, reply } => {
+                    let result = self.client.write_mem(addr, &data).await.map_err(gige_error);
+                    let _ = reply.send(result);
+                }
+                Request::Notify { command, payload } => {
+                    if let Err(err) = self.client.notify(command, &payload).await {
+                        warn!(command, error = %err, "failed to send GVCP notification");
+                    }
+                }
+            }
+        }
+        trace!("control channel closed");
+    }
+}
+
+fn gige_error(err: GenCpError) -> GigeError {
+    match err {
+        GenCpError::Timeout => GigeError::Timeout,
+        GenCpError::Io(err) => GigeError::Io(err),
+        GenCpError::Status(status) => GigeError::Status(status),
+        other => GigeError::GenCp(other),
+    }
+}
+
+fn register_address(addr: u64) -> Result<u32, GigeError> {
+    match u32::try_from(addr) {
+        Ok(addr) if addr & 0x3 == 0 => Ok(addr),
+        _ => Err(GigeError::Protocol(format!(
+            "register address {addr:#x} is not a 32-bit aligned GVCP address"
+        ))),
+    }
+}
+
+fn encode_read_regs(addrs: &[u64]) -> Result<BytesMut, GigeError> {
+    let mut payload = BytesMut::with_capacity(addrs.len() * 4);
+    for &addr in addrs {
+        payload.put_u32(register_address(addr)?);
+    }
+    Ok(payload)
+}
+
+fn encode_write_regs(regs: &[(u64, u32)]) -> Result<BytesMut, GigeError> {
+    let mut payload = BytesMut::with_capacity(regs.len() * 8);
+    for &(addr, value) in regs {
+        payload.put_u32(register_address(addr)?);
+        payload.put_u32(value);
+    }
+    Ok(payload)
+}
+
+fn decode_reg_values(payload: &[u8]) -> Result<Vec<u32>, GigeError> {
+    let words = payload.chunks_exact(4);
+    if !words.remainder().is_empty() {
+        return Err(GigeError::Protocol(
+            "READREG acknowledgement is not a whole number of registers".into(),
+        ));
+    }
+    Ok(words
+        .map(|word| u32::from_be_bytes([word[0], word[1], word[2], word[3]]))
+        .collect())
+}
+
+/// Index of the failing write reported by a `WRITEREG` acknowledgement.
+fn decode_write_reg_index(payload: &[u8]) -> Result<usize, GigeError> {
+    if payload.len() != 4 {
+        return Err(GigeError::Protocol(
+            "WRITEREG acknowledgement length mismatch".into(),
+        ));
+    }
+    let mut cursor = payload;
+    let _reserved = cursor.get_u16();
+    Ok(cursor.get_u16() as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_batches_encode_and_decode() {
+        let payload = encode_write_regs(&[(0x0A00, 2), (0x0938, 0xDEAD_BEEF)]).unwrap();
+        assert_eq!(
+            &payload[..],
+            &[0, 0, 0x0A, 0, 0, 0, 0, 2, 0, 0, 0x09, 0x38, 0xDE, 0xAD, 0xBE, 0xEF]
+        );
+        assert!(encode_read_regs(&[0x0A02]).is_err());
+        assert!(encode_read_regs(&[0x1_0000_0000]).is_err());
+        assert_eq!(
+            decode_reg_values(&[0, 0, 0, 1, 0xFF, 0, 0, 0]).unwrap(),
+            vec![1, 0xFF00_0000]
+        );
+        assert!(decode_reg_values(&[0, 0, 1]).is_err());
+        assert_eq!(decode_write_reg_index(&[0, 0, 0, 3]).unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn stale_acknowledgements_are_skipped() {
+        let device = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = device.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, peer) = device.recv_from(&mut buf).await.unwrap();
+            let request_id = u16::from_be_bytes([buf[6], buf[7]]);
+            for (id, value) in [(request_id.wrapping_sub(1), 0xDEAD), (request_id, 0xBEEF)] {
+                let mut ack = BytesMut::new();
+                ack.put_u16(0);
+                ack.put_u16(OpCode::ReadReg.ack_code());
+                ack.put_u16(4);
+                ack.put_u16(id);
+                ack.put_u32(value);
+                device.send_to(&ack, peer).await.unwrap();
+            }
+            let resent = time::timeout(Duration::from_millis(50), device.recv_from(&mut buf)).await;
+            assert!(resent.is_err(), "command must not be resent");
+        });
+
+        let control = ControlHandle::connect(addr).await.unwrap();
+        assert_eq!(control.read_regs(&[0x0A00]).await.unwrap(), vec![0xBEEF]);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn resend_requests_are_not_acknowledged() {
+        let device = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = device.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (len, _) = device.recv_from(&mut buf).await.unwrap();
+            assert_eq!(len, genicp::HEADER_SIZE + 8);
+            let flags = u16::from_be_bytes([buf[0], buf[1]]);
+            let command = u16::from_be_bytes([buf[2], buf[3]]);
+            assert_eq!(command, consts::PACKET_RESEND_COMMAND);
+            assert_eq!(flags & genicp::CommandFlags::ACK_REQUIRED.bits(), 0);
+
+            // Only the register read that follows is answered.
+            let (_, peer) = device.recv_from(&mut buf).await.unwrap();
+            assert_eq!(
+                u16::from_be_bytes([buf[2], buf[3]]),
+                OpCode::ReadReg.command_code()
+            );
+            let mut ack = BytesMut::new();
+            ack.put_u16(0);
+            ack.put_u16(OpCode::ReadReg.ack_code());
+            ack.put_u16(4);
+            ack.extend_from_slice(&buf[6..8]);
+            ack.put_u32(7);
+            device.send_to(&ack, peer).await.unwrap();
+        });
+
+        let control = ControlHandle::connect(addr).await.unwrap();
+        let exchange = async {
+            control.request_resend(5, 10, 12).await.unwrap();
+            control.read_regs(&[0x0A00]).await.unwrap()
+        };
+        let values = time::timeout(consts::CONTROL_TIMEOUT, exchange)
+            .await
+            .expect("resend must not wait for an acknowledgement");
+        assert_eq!(values, vec![7]);
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,442 @@
+//! Passive device monitoring through periodic GVCP discovery.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use thiserror::Error;
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time;
+use tracing::{debug, info, warn};
+use wildmatch::WildMatch;
+
+use crate::gvcp::{self, DeviceInfo, GigeError};
+
+/// Consecutive scans a device may be missing before it is reported removed.
+pub const DEFAULT_REMOVAL_MISSES: u32 = 2;
+
+/// Where a discovery scan looks for devices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiscoveryTarget {
+    /// Broadcast on every IPv4 interface.
+    AllInterfaces,
+    /// Broadcast on the named interface only.
+    Interface(String),
+    /// Subnet-directed broadcast to the given broadcast address.
+    Subnet(Ipv4Addr),
+    /// Unicast discovery of a single device address.
+    Unicast(Ipv4Addr),
+}
+
+impl DiscoveryTarget {
+    /// Run one discovery scan.
+    pub async fn scan(&self, timeout: Duration) -> Result<Vec<DeviceInfo>, GigeError> {
+        match self {
+            DiscoveryTarget::AllInterfaces => gvcp::discover(timeout).await,
+            DiscoveryTarget::Interface(name) => gvcp::discover_on_interface(timeout, name).await,
+            DiscoveryTarget::Subnet(broadcast) => gvcp::discover_subnet(*broadcast, timeout).await,
+            DiscoveryTarget::Unicast(ip) => Ok(gvcp::discover_unicast(*ip, timeout)
+                .await?
+                .into_iter()
+                .collect()),
+        }
+    }
+}
+
+/// Criterion picking one device out of discovery results.
+///
+/// Parsed from `ip:<addr>` (or a bare IPv4 address), `mac:<aa:bb:..>`,
+/// `sn:<serial>`, `name:<user-defined name>` or `model:<model name>`. Serial,
+/// name and model accept `*` and `?` wildcards, e.g. `model:*acA1920*`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceSelector {
+    /// Current IP address.
+    Ip(Ipv4Addr),
+    /// MAC address.
+    Mac([u8; 6]),
+    /// Serial number pattern.
+    Serial(String),
+    /// User-defined name pattern (`DeviceUserID`).
+    UserName(String),
+    /// Model name pattern.
+    Model(String),
+}
+
+/// Failure to parse or resolve a [`DeviceSelector`].
+#[derive(Debug, Error)]
+pub enum SelectorError {
+    #[error("invalid device selector '{0}': expected ip:, mac:, sn:, name: or model:")]
+    Invalid(String),
+    #[error("no discovered device matches {0}")]
+    NotFound(DeviceSelector),
+    #[error("{selector} matches {} devices: {}", .matches.len(), join_ips(.matches))]
+    Ambiguous {
+        selector: DeviceSelector,
+        matches: Vec<Ipv4Addr>,
+    },
+}
+
+fn join_ips(ips: &[Ipv4Addr]) -> String {
+    ips.iter()
+        .map(Ipv4Addr::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl DeviceSelector {
+    /// Whether `device` satisfies the selector.
+    pub fn matches(&self, device: &DeviceInfo) -> bool {
+        let field_matches = |pattern: &str, value: &Option<String>| {
+            value
+                .as_deref()
+                .is_some_and(|value| WildMatch::new(pattern).matches(value))
+        };
+        match self {
+            DeviceSelector::Ip(ip) => device.ip == *ip,
+            DeviceSelector::Mac(mac) => device.mac == *mac,
+            DeviceSelector::Serial(pattern) => field_matches(pattern, &device.serial_number),
+            DeviceSelector::UserName(pattern) => field_matches(pattern, &device.user_name),
+            DeviceSelector::Model(pattern) => field_matches(pattern, &device.model),
+        }
+    }
+
+    /// Pick the single device in `devices` matching the selector.
+    pub fn select<'a>(&self, devices: &'a [DeviceInfo]) -> Result<&'a DeviceInfo, SelectorError> {
+        let mut matches = devices.iter().filter(|device| self.matches(device));
+        let Some(first) = matches.next() else {
+            return Err(SelectorError::NotFound(self.clone()));
+        };
+        let rest: Vec<&DeviceInfo> = matches.collect();
+        if rest.is_empty() {
+            return Ok(first);
+        }
+        Err(SelectorError::Ambiguous {
+            selector: self.clone(),
+            matches: std::iter::once(first)
+                .chain(rest)
+                .map(|device| device.ip)
+                .collect(),
+        })
+    }
+}
+
+impl FromStr for DeviceSelector {
+    type Err = SelectorError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || SelectorError::Invalid(text.to_string());
+        let Some((kind, value)) = text.split_once(':') else {
+            return text.parse().map(DeviceSelector::Ip).map_err(|_| invalid());
+        };
+        if value.is_empty() {
+            return Err(invalid());
+        }
+        match kind {
+            "ip" => value.parse().map(DeviceSelector::Ip).map_err(|_| invalid()),
+            "mac" => parse_mac(value)
+                .map(DeviceSelector::Mac)
+                .ok_or_else(invalid),
+            "sn" | "serial" => Ok(DeviceSelector::Serial(value.to_string())),
+            "name" => Ok(DeviceSelector::UserName(value.to_string())),
+            "model" => Ok(DeviceSelector::Model(value.to_string())),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for DeviceSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviceSelector::Ip(ip) => write!(f, "ip:{ip}"),
+            DeviceSelector::Mac(mac) => {
+                let octets: Vec<String> = mac.iter().map(|byte| format!("{byte:02X}")).collect();
+                write!(f, "mac:{}", octets.join(":"))
+            }
+            DeviceSelector::Serial(pattern) => write!(f, "sn:{pattern}"),
+            DeviceSelector::UserName(pattern) => write!(f, "name:{pattern}"),
+            DeviceSelector::Model(pattern) => write!(f, "model:{pattern}"),
+        }
+    }
+}
+
+/// Parse a MAC address written as six hex octets separated by `:` or `-`.
+pub fn parse_mac(text: &str) -> Option<[u8; 6]> {
+    let mut mac = [0u8; 6];
+    let mut parts = text.split([':', '-']);
+    for byte in &mut mac {
+        *byte = u8::from_str_radix(parts.next()?, 16).ok()?;
+    }
+    parts.next().is_none().then_some(mac)
+}
+
+/// Change in the set of devices observed by a [`DiscoveryWatcher`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiscoveryEvent {
+    /// A device answered for the first time.
+    Added(DeviceInfo),
+    /// A device stopped answering.
+    Removed(DeviceInfo),
+    /// A known device answered with different information, e.g. a new IP
+    /// address after a reboot.
+    Changed {
+        before: Box<DeviceInfo>,
+        after: Box<DeviceInfo>,
+    },
+}
+
+/// Devices seen so far, keyed by MAC address.
+///
+/// Ordered by MAC so removals from one scan are reported deterministically.
+#[derive(Debug)]
+struct DeviceTracker {
+    known: BTreeMap<[u8; 6], (DeviceInfo, u32)>,
+    removal_misses: u32,
+}
+
+impl DeviceTracker {
+    fn new(removal_misses: u32) -> Self {
+        Self {
+            known: BTreeMap::new(),
+            removal_misses: removal_misses.max(1),
+        }
+    }
+
+    /// Fold one scan into the known devices and report what changed.
+    fn update(&mut self, scan: Vec<DeviceInfo>) -> Vec<DiscoveryEvent> {
+        let mut events = Vec::new();
+        let mut seen = Vec::with_capacity(scan.len());
+        for device in scan {
+            seen.push(device.mac);
+            match self.known.get_mut(&device.mac) {
+                Some((known, misses)) => {
+                    *misses = 0;
+                    if !same_device(known, &device) {
+                        let before = std::mem::replace(known, device.clone());
+                        events.push(DiscoveryEvent::Changed {
+                            before: Box::new(before),
+                            after: Box::new(device),
+                        });
+                    }
+                }
+                None => {
+                    self.known.insert(device.mac, (device.clone(), 0));
+                    events.push(DiscoveryEvent::Added(device));
+                }
+            }
+        }
+
+        let removal_misses = self.removal_misses;
+        let mut removed = Vec::new();
+        for (mac, (_, misses)) in self.known.iter_mut() {
+            if seen.contains(mac) {
+                continue;
+            }
+            *misses += 1;
+            if *misses >= removal_misses {
+                removed.push(*mac);
+            }
+        }
+        for mac in removed {
+            if let Some((device, _)) = self.known.remove(&mac) {
+                events.push(DiscoveryEvent::Removed(device));
+            }
+        }
+        events
+    }
+}
+
+/// Compare the fields a device reports about itself.
+///
+/// `interface` and `reachable` describe the host side of the answer; with
+/// several interfaces reaching the same device they follow whichever answer
+/// arrives first and would otherwise flap between scans.
+fn same_device(a: &DeviceInfo, b: &DeviceInfo) -> bool {
+    a.ip == b.ip
+        && a.mac == b.mac
+        && a.subnet == b.subnet
+        && a.gateway == b.gateway
+        && a.ip_config_options == b.ip_config_options
+        && a.ip_config_current == b.ip_config_current
+        && a.spec_version == b.spec_version
+        && a.device_mode == b.device_mode
+        && a.manufacturer == b.manufacturer
+        && a.model == b.model
+        && a.device_version == b.device_version
+        && a.manufacturer_info == b.manufacturer_info
+        && a.serial_number == b.serial_number
+        && a.user_name == b.user_name
+}
+
+/// Background task rediscovering devices periodically and reporting changes.
+///
+/// Devices are identified by MAC address. A device is reported removed after
+/// it misses [`DEFAULT_REMOVAL_MISSES`] consecutive scans, so a single lost
+/// acknowledgement does not cause spurious events. Failed scans are logged
+/// and skipped. The task stops when the watcher is dropped.
+pub struct DiscoveryWatcher {
+    events: mpsc::UnboundedReceiver<DiscoveryEvent>,
+    task: JoinHandle<()>,
+}
+
+impl DiscoveryWatcher {
+    /// Scan `target` every `interval`, listening `timeout` for each scan.
+    pub fn spawn(target: DiscoveryTarget, interval: Duration, timeout: Duration) -> Self {
+        Self::spawn_with_misses(target, interval, timeout, DEFAULT_REMOVAL_MISSES)
+    }
+
+    /// Like [`DiscoveryWatcher::spawn`] with a custom removal threshold.
+    pub fn spawn_with_misses(
+        target: DiscoveryTarget,
+        interval: Duration,
+        timeout: Duration,
+        removal_misses: u32,
+    ) -> Self {
+        let (tx, events) = mpsc::unbounded_channel();
+        let task = tokio::spawn(async move {
+            let mut tracker = DeviceTracker::new(removal_misses);
+            let mut ticker = time::interval(interval);
+            ticker.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+            info!(?target, ?interval, "watching for GigE Vision devices");
+            loop {
+                ticker.tick().await;
+                let scan = match target.scan(timeout).await {
+                    Ok(scan) => scan,
+                    Err(err) => {
+                        warn!(error = %err, "discovery scan failed");
+                        continue;
+                    }
+                };
+                debug!(devices = scan.len(), "discovery scan complete");
+                for event in tracker.update(scan) {
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Self { events, task }
+    }
+
+    /// Wait for the next change; `None` once the watcher task has stopped.
+    pub async fn next(&mut self) -> Option<DiscoveryEvent> {
+        self.events.recv().await
+    }
+}
+
+impl Drop for DiscoveryWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(last: u8, ip: [u8; 4]) -> DeviceInfo {
+        let mut info = DeviceInfo::from_ip(Ipv4Addr::from(ip));
+        info.mac = [0x00, 0x30, 0x53, 0x00, 0x00, last];
+        info
+    }
+
+    #[test]
+    fn selectors_parse_and_match() {
+        let mut left = device(1, [192, 168, 1, 10]);
+        left.serial_number = Some("21345678".into());
+        left.user_name = Some("LeftCam".into());
+        left.model = Some("acA1920-40gm".into());
+        let mut right = device(2, [192, 168, 1, 11]);
+        right.model = Some("acA1920-40gc".into());
+        let devices = vec![left.clone(), right.clone()];
+
+        let pick = |text: &str| {
+            let selector: DeviceSelector = text.parse().expect("parse selector");
+            selector.select(&devices).map(|device| device.ip)
+        };
+        assert_eq!(pick("sn:21345678").unwrap(), left.ip);
+        assert_eq!(pick("name:LeftCam").unwrap(), left.ip);
+        assert_eq!(pick("mac:00-30-53-00-00-02").unwrap(), right.ip);
+        assert_eq!(pick("192.168.1.11").unwrap(), right.ip);
+        assert_eq!(pick("model:*40gc").unwrap(), right.ip);
+        assert!(matches!(
+            pick("model:*acA1920*"),
+            Err(SelectorError::Ambiguous { ref matches, .. }) if matches.len() == 2
+        ));
+        assert!(matches!(
+            pick("name:Right*"),
+            Err(SelectorError::NotFound(_))
+        ));
+
+        assert!("serial".parse::<DeviceSelector>().is_err());
+        assert!("mac:00:30:53".parse::<DeviceSelector>().is_err());
+        assert!("colour:red".parse::<DeviceSelector>().is_err());
+        let selector: DeviceSelector = "mac:00:30:53:0a:0b:0c".parse().unwrap();
+        assert_eq!(selector.to_string(), "mac:00:30:53:0A:0B:0C");
+    }
+
+    #[test]
+    fn tracker_reports_added_changed_and_removed() {
+        let mut tracker = DeviceTracker::new(2);
+        let a = device(1, [192, 168, 1, 10]);
+        let b = device(2, [192, 168, 1, 11]);
+
+        let events = tracker.update(vec![a.clone(), b.clone()]);
+        assert_eq!(
+            events,
+            vec![
+                DiscoveryEvent::Added(a.clone()),
+                DiscoveryEvent::Added(b.clone())
+            ]
+        );
+        assert!(tracker.update(vec![a.clone(), b.clone()]).is_empty());
+
+        // Answering through another host interface is not a device change.
+        let mut other_nic = a.clone();
+        other_nic.interface = Some("eth1".into());
+        other_nic.reachable = !a.reachable;
+        assert!(tracker.update(vec![other_nic, b.clone()]).is_empty());
+
+        let rebooted = device(2, [192, 168, 1, 42]);
+        assert_eq!(
+            tracker.update(vec![rebooted.clone()]),
+            vec![DiscoveryEvent::Changed {
+                before: Box::new(b),
+                after: Box::new(rebooted.clone())
+            }]
+        );
+        // First miss is tolerated, the second one removes the device.
+        assert_eq!(
+            tracker.update(vec![rebooted.clone()]),
+            vec![DiscoveryEvent::Removed(a)]
+        );
+        assert!(tracker.update(Vec::new()).is_empty());
+        assert_eq!(
+            tracker.update(Vec::new()),
+            vec![DiscoveryEvent::Removed(rebooted)]
+        );
+    }
+
+    #[test]
+    fn tracker_reports_removals_in_mac_order() {
+        let mut tracker = DeviceTracker::new(1);
+        let devices: Vec<DeviceInfo> = [9, 3, 7, 1, 5]
+            .into_iter()
+            .map(|last| device(last, [192, 168, 1, last]))
+            .collect();
+        tracker.update(devices.clone());
+
+        let mut expected = devices;
+        expected.sort_by_key(|info| info.mac);
+        assert_eq!(
+            tracker.update(Vec::new()),
+            expected
+                .into_iter()
+                .map(DiscoveryEvent::Removed)
+                .collect::<Vec<_>>()
+        );
+    }
+}
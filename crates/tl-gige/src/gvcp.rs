@@ -1,22 +1,23 @@
 //! GVCP control plane utilities.
 
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::io::Cursor;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::time::Duration;
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
-use fastrand::Rng;
-use genicp::{
-    decode_ack, encode_cmd, AckHeader, CommandFlags, GenCpAck, GenCpCmd, OpCode, StatusCode,
-};
-use if_addrs::{get_if_addrs, IfAddr};
+use genicp::{AckHeader, CommandFlags, StatusCode};
+use if_addrs::{get_if_addrs, IfAddr, Ifv4Addr};
 use thiserror::Error;
 use tokio::net::UdpSocket;
-use tokio::task::JoinSet;
+use tokio::sync::watch;
+use tokio::task::{JoinHandle, JoinSet};
 use tokio::time;
 use tracing::{debug, info, trace, warn};
 
+use crate::control::ControlHandle;
+use crate::ipconfig::{IpConfig, IpConfigMode};
 use crate::nic::{self, Iface};
 
 /// GVCP protocol constants grouped by semantic area.
@@ -43,10 +44,30 @@ pub mod consts {
     /// Stride between successive event notification mask registers (bytes).
     pub const EVENT_NOTIFICATION_STRIDE: u64 = 4;
 
-    /// Maximum number of bytes we read per GenCP `ReadMem` operation.
+    /// Address of the GVCP capability register.
+    pub const GVCP_CAPABILITY: u64 = 0x0934;
+    /// GVCP capability bit advertising `PENDING_ACK` support.
+    pub const CAPABILITY_PENDING_ACK: u32 = 1 << 5;
+    /// Address of the GVCP configuration register.
+    pub const GVCP_CONFIGURATION: u64 = 0x0954;
+    /// GVCP configuration bit enabling `PENDING_ACK` replies.
+    pub const CONFIGURATION_PENDING_ACK_ENABLE: u32 = 1 << 1;
+    /// Address of the heartbeat timeout register (milliseconds).
+    pub const HEARTBEAT_TIMEOUT: u64 = 0x0938;
+    /// Address of the control channel privilege (CCP) register.
+    pub const CONTROL_CHANNEL_PRIVILEGE: u64 = 0x0A00;
+    /// Shortest interval between two heartbeats, whatever the device timeout.
+    pub const HEARTBEAT_MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Maximum payload carried by a GVCP command or acknowledgement (bytes).
+    pub const GVCP_MAX_PAYLOAD: usize = 540;
+    /// Maximum number of addresses in a single `READREG` command.
+    pub const READREG_MAX_COUNT: usize = GVCP_MAX_PAYLOAD / 4;
+    /// Maximum number of address/value pairs in a single `WRITEREG` command.
+    pub const WRITEREG_MAX_COUNT: usize = GVCP_MAX_PAYLOAD / 8;
+
+    /// Largest `READMEM`/`WRITEMEM` payload exchanged over the control channel.
     pub const GENCP_MAX_BLOCK: usize = 512;
-    /// Additional bytes that accompany a GenCP `WriteMem` block.
-    pub const GENCP_WRITE_OVERHEAD: usize = 8;
 
     /// Default timeout for control transactions.
     pub const CONTROL_TIMEOUT: Duration = Duration::from_millis(500);
@@ -140,6 +161,13 @@ pub enum GigeError {
     GenCp(#[from] genicp::GenCpError),
     #[error("device reported status {0:?}")]
     Status(StatusCode),
+    /// A batched register access stopped part-way through.
+    ///
+    /// `index` is the position of the failing register in the slice passed to
+    /// [`GigeDevice::read_regs`] or [`GigeDevice::write_regs`]; every register
+    /// before it was accessed successfully.
+    #[error("register access failed at index {index} with status {status:?}")]
+    RegisterAccess { index: usize, status: StatusCode },
 }
 
 /// Information returned by GVCP discovery packets.
@@ -149,9 +177,61 @@ pub struct DeviceInfo {
     pub mac: [u8; 6],
     pub model: Option<String>,
     pub manufacturer: Option<String>,
+    /// GigE Vision specification version implemented by the device.
+    pub spec_version: (u16, u16),
+    /// Raw device mode: endianness, device class, link configuration and
+    /// string character set.
+    pub device_mode: u32,
+    /// Address assignment methods the device supports.
+    pub ip_config_options: IpConfig,
+    /// Address assignment methods currently enabled.
+    pub ip_config_current: IpConfig,
+    /// Current subnet mask.
+    pub subnet: Ipv4Addr,
+    /// Current default gateway.
+    pub gateway: Ipv4Addr,
+    pub device_version: Option<String>,
+    pub manufacturer_info: Option<String>,
+    pub serial_number: Option<String>,
+    /// User-defined name (`DeviceUserID`).
+    pub user_name: Option<String>,
+    /// Host interface the acknowledgement arrived on.
+    pub interface: Option<String>,
+    /// Whether the device address lies in the receiving interface's subnet.
+    ///
+    /// Devices outside it answer broadcasts but cannot be opened until their
+    /// address is fixed, e.g. with [`force_ip`](crate::ipconfig::force_ip).
+    pub reachable: bool,
 }
 
 impl DeviceInfo {
+    /// Placeholder for a device known only by its address.
+    pub fn from_ip(ip: Ipv4Addr) -> Self {
+        Self {
+            ip,
+            mac: [0; 6],
+            model: None,
+            manufacturer: None,
+            spec_version: (0, 0),
+            device_mode: 0,
+            ip_config_options: IpConfig::from_bits(0),
+            ip_config_current: IpConfig::from_bits(0),
+            subnet: Ipv4Addr::UNSPECIFIED,
+            gateway: Ipv4Addr::UNSPECIFIED,
+            device_version: None,
+            manufacturer_info: None,
+            serial_number: None,
+            user_name: None,
+            interface: None,
+            reachable: true,
+        }
+    }
+
+    /// Method that assigned the current address.
+    pub fn ip_mode(&self) -> IpConfigMode {
+        self.ip_config_current.mode()
+    }
+
     fn mac_string(&self) -> String {
         self.mac
             .iter()
@@ -161,6 +241,11 @@ impl DeviceInfo {
     }
 }
 
+fn same_subnet(a: Ipv4Addr, b: Ipv4Addr, netmask: Ipv4Addr) -> bool {
+    let mask = u32::from(netmask);
+    u32::from(a) & mask == u32::from(b) & mask
+}
+
 /// Discover GigE Vision devices on the local network by broadcasting a GVCP discovery command.
 pub async fn discover(timeout: Duration) -> Result<Vec<DeviceInfo>, GigeError> {
     discover_filtered(timeout, None).await
@@ -178,22 +263,7 @@ async fn discover_filtered(
     timeout: Duration,
     iface_filter: Option<&str>,
 ) -> Result<Vec<DeviceInfo>, GigeError> {
-    let mut interfaces = Vec::new();
-    for iface in get_if_addrs()? {
-        let IfAddr::V4(v4) = iface.addr else {
-            continue;
-        };
-        if v4.ip.is_loopback() {
-            continue;
-        }
-        if let Some(filter) = iface_filter {
-            if iface.name != filter {
-                continue;
-            }
-        }
-        interfaces.push((iface.name, v4));
-    }
-
+    let interfaces = broadcast_interfaces(iface_filter)?;
     if interfaces.is_empty() {
         return Ok(Vec::new());
     }
@@ -209,45 +279,43 @@ async fn discover_filtered(
             let broadcast = v4.broadcast.unwrap_or(Ipv4Addr::BROADCAST);
             let destination = SocketAddr::new(IpAddr::V4(broadcast), consts::PORT);
 
-            let header = GvcpRequestHeader {
-                flags: CommandFlags::ACK_REQUIRED | CommandFlags::BROADCAST,
-                command: consts::DISCOVERY_COMMAND,
-                length: 0,
-                request_id,
-            };
-            let packet = header.encode(&[]);
             info!(%interface_name, local = %v4.ip, dest = %destination, "sending GVCP discovery");
-            trace!(%interface_name, bytes = packet.len(), "GVCP discovery payload size");
-            socket.send_to(&packet, destination).await?;
-
-            let mut responses = Vec::new();
-            let mut buffer = vec![0u8; consts::DISCOVERY_BUFFER];
-            let timer = time::sleep(timeout);
-            tokio::pin!(timer);
-            loop {
-                tokio::select! {
-                    _ = &mut timer => break,
-                    recv = socket.recv_from(&mut buffer) => {
-                        let (len, src) = recv?;
-                        info!(%interface_name, %src, "received GVCP response");
-                        trace!(%interface_name, bytes = len, "GVCP response length");
-                        if let Some(info) = parse_discovery_ack(&buffer[..len], request_id)? {
-                            trace!(ip = %info.ip, mac = %info.mac_string(), "parsed discovery ack");
-                            responses.push(info);
-                        }
-                    }
+            let mut responses =
+                send_discovery(&socket, destination, true, request_id, timeout, false).await?;
+            for info in &mut responses {
+                info.reachable = same_subnet(info.ip, v4.ip, v4.netmask);
+                if !info.reachable {
+                    warn!(
+                        %interface_name,
+                        ip = %info.ip,
+                        subnet = %info.subnet,
+                        local = %v4.ip,
+                        netmask = %v4.netmask,
+                        "device is outside the interface subnet"
+                    );
                 }
+                info.interface = Some(interface_name.clone());
             }
             Ok::<_, GigeError>(responses)
         });
     }
 
-    let mut seen = HashMap::new();
+    let mut seen: HashMap<_, DeviceInfo> = HashMap::new();
     while let Some(res) = join_set.join_next().await {
         let devices =
             res.map_err(|e| GigeError::Protocol(format!("discovery task failed: {e}")))??;
         for dev in devices {
-            seen.entry((dev.ip, dev.mac)).or_insert(dev);
+            // Prefer the answer received on an interface that can reach the device.
+            match seen.entry((dev.ip, dev.mac)) {
+                Entry::Occupied(mut entry) => {
+                    if dev.reachable && !entry.get().reachable {
+                        entry.insert(dev);
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(dev);
+                }
+            }
         }
     }
 
@@ -256,6 +324,132 @@ async fn discover_filtered(
     Ok(devices)
 }
 
+/// Discover a single device by sending the discovery command straight to `ip`.
+///
+/// Reaches devices behind routers where broadcasts are not forwarded. Returns
+/// `None` when nothing answers within `timeout`.
+pub async fn discover_unicast(
+    ip: Ipv4Addr,
+    timeout: Duration,
+) -> Result<Option<DeviceInfo>, GigeError> {
+    discover_unicast_at(SocketAddr::new(IpAddr::V4(ip), consts::PORT), timeout).await
+}
+
+async fn discover_unicast_at(
+    destination: SocketAddr,
+    timeout: Duration,
+) -> Result<Option<DeviceInfo>, GigeError> {
+    let socket = UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0)).await?;
+    info!(dest = %destination, "sending unicast GVCP discovery");
+    let request_id = 0x0180;
+    let mut devices =
+        send_discovery(&socket, destination, false, request_id, timeout, true).await?;
+    Ok(devices.pop())
+}
+
+/// Discover devices with a subnet-directed broadcast to `broadcast`.
+///
+/// `broadcast` is the broadcast address of the target subnet, for example
+/// `192.168.5.255`; routers configured to forward directed broadcasts make
+/// this work across networks.
+pub async fn discover_subnet(
+    broadcast: Ipv4Addr,
+    timeout: Duration,
+) -> Result<Vec<DeviceInfo>, GigeError> {
+    discover_subnet_at(
+        SocketAddr::new(IpAddr::V4(broadcast), consts::PORT),
+        timeout,
+    )
+    .await
+}
+
+async fn discover_subnet_at(
+    destination: SocketAddr,
+    timeout: Duration,
+) -> Result<Vec<DeviceInfo>, GigeError> {
+    let socket = UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0)).await?;
+    socket.set_broadcast(true)?;
+    info!(dest = %destination, "sending subnet-directed GVCP discovery");
+    let request_id = 0x0181;
+    let mut devices =
+        send_discovery(&socket, destination, true, request_id, timeout, false).await?;
+    devices.sort_by_key(|d| d.ip);
+    devices.dedup_by_key(|d| (d.ip, d.mac));
+    Ok(devices)
+}
+
+/// Send a discovery command to `destination` and gather the acknowledgements.
+///
+/// Listens until `timeout` elapses, or until the first answer when
+/// `first_only` is set.
+async fn send_discovery(
+    socket: &UdpSocket,
+    destination: SocketAddr,
+    broadcast: bool,
+    request_id: u16,
+    timeout: Duration,
+    first_only: bool,
+) -> Result<Vec<DeviceInfo>, GigeError> {
+    let mut flags = CommandFlags::ACK_REQUIRED;
+    if broadcast {
+        flags |= CommandFlags::BROADCAST;
+    }
+    let header = GvcpRequestHeader {
+        flags,
+        command: consts::DISCOVERY_COMMAND,
+        length: 0,
+        request_id,
+    };
+    let packet = header.encode(&[]);
+    trace!(dest = %destination, bytes = packet.len(), "GVCP discovery payload size");
+    socket.send_to(&packet, destination).await?;
+
+    let mut responses = Vec::new();
+    let mut buffer = vec![0u8; consts::DISCOVERY_BUFFER];
+    let timer = time::sleep(timeout);
+    tokio::pin!(timer);
+    loop {
+        tokio::select! {
+            _ = &mut timer => break,
+            recv = socket.recv_from(&mut buffer) => {
+                let (len, src) = recv?;
+                info!(%src, "received GVCP response");
+                trace!(bytes = len, "GVCP response length");
+                if let Some(info) = parse_discovery_ack(&buffer[..len], request_id)? {
+                    trace!(ip = %info.ip, mac = %info.mac_string(), "parsed discovery ack");
+                    responses.push(info);
+                    if first_only {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    Ok(responses)
+}
+
+/// Non-loopback IPv4 interfaces, optionally limited to the one named `filter`.
+pub(crate) fn broadcast_interfaces(
+    filter: Option<&str>,
+) -> Result<Vec<(String, Ifv4Addr)>, GigeError> {
+    let mut interfaces = Vec::new();
+    for iface in get_if_addrs()? {
+        let IfAddr::V4(v4) = iface.addr else {
+            continue;
+        };
+        if v4.ip.is_loopback() {
+            continue;
+        }
+        if let Some(filter) = filter {
+            if iface.name != filter {
+                continue;
+            }
+        }
+        interfaces.push((iface.name, v4));
+    }
+    Ok(interfaces)
+}
+
 fn parse_discovery_ack(buf: &[u8], expected_request: u16) -> Result<Option<DeviceInfo>, GigeError> {
     if buf.len() < genicp::HEADER_SIZE {
         return Err(GigeError::Protocol("GVCP ack too short".into()));
@@ -288,50 +482,68 @@ fn parse_discovery_ack(buf: &[u8], expected_request: u16) -> Result<Option<Devic
 
 fn parse_discovery_payload(payload: &[u8]) -> Result<DeviceInfo, GigeError> {
     let mut cursor = Cursor::new(payload);
-    if cursor.remaining() < 32 {
+    if cursor.remaining() < 72 {
         return Err(GigeError::Protocol("discovery payload too small".into()));
     }
-    let _spec_major = cursor.get_u16();
-    let _spec_minor = cursor.get_u16();
-    let _device_mode = cursor.get_u32();
-    let _device_class = cursor.get_u16();
-    let _device_capability = cursor.get_u16();
+    let spec_major = cursor.get_u16();
+    let spec_minor = cursor.get_u16();
+    let device_mode = cursor.get_u32();
+    let _reserved = cursor.get_u16();
     let mut mac = [0u8; 6];
     cursor.copy_to_slice(&mut mac);
-    let _ip_config_options = cursor.get_u16();
-    let _ip_config_current = cursor.get_u16();
-    let ip = Ipv4Addr::from(cursor.get_u32());
-    let _subnet = cursor.get_u32();
-    let _gateway = cursor.get_u32();
+    let ip_config_options = IpConfig::from_bits(cursor.get_u32());
+    let ip_config_current = IpConfig::from_bits(cursor.get_u32());
+    let ip = read_address(&mut cursor);
+    let subnet = read_address(&mut cursor);
+    let gateway = read_address(&mut cursor);
     let manufacturer = read_fixed_string(&mut cursor, 32)?;
     let model = read_fixed_string(&mut cursor, 32)?;
-    let _ = skip_string(&mut cursor, 32);
-    let _ = skip_string(&mut cursor, 16);
-    let _ = skip_string(&mut cursor, 16);
+    // Trailing strings are optional to tolerate short acknowledgements.
+    let device_version = read_optional_string(&mut cursor, 32);
+    let manufacturer_info = read_optional_string(&mut cursor, 48);
+    let serial_number = read_optional_string(&mut cursor, 16);
+    let user_name = read_optional_string(&mut cursor, 16);
 
     Ok(DeviceInfo {
         ip,
         mac,
         manufacturer,
         model,
+        spec_version: (spec_major, spec_minor),
+        device_mode,
+        ip_config_options,
+        ip_config_current,
+        subnet,
+        gateway,
+        device_version,
+        manufacturer_info,
+        serial_number,
+        user_name,
+        interface: None,
+        reachable: true,
     })
 }
 
+/// Read an IPv4 address stored in the last four bytes of a 16-byte field.
+fn read_address(cursor: &mut Cursor<&[u8]>) -> Ipv4Addr {
+    cursor.advance(12);
+    Ipv4Addr::from(cursor.get_u32())
+}
+
 fn read_fixed_string(cursor: &mut Cursor<&[u8]>, len: usize) -> Result<Option<String>, GigeError> {
     if cursor.remaining() < len {
         return Err(GigeError::Protocol("discovery string truncated".into()));
     }
-    let mut buf = vec![0u8; len];
-    cursor.copy_to_slice(&mut buf);
-    Ok(parse_string(&buf))
+    Ok(read_optional_string(cursor, len))
 }
 
-fn skip_string(cursor: &mut Cursor<&[u8]>, len: usize) -> Option<()> {
+fn read_optional_string(cursor: &mut Cursor<&[u8]>, len: usize) -> Option<String> {
     if cursor.remaining() < len {
         return None;
     }
-    cursor.advance(len);
-    Some(())
+    let mut buf = vec![0u8; len];
+    cursor.copy_to_slice(&mut buf);
+    parse_string(&buf)
 }
 
 fn parse_string(bytes: &[u8]) -> Option<String> {
@@ -345,12 +557,99 @@ fn parse_string(bytes: &[u8]) -> Option<String> {
     }
 }
 
+/// Control channel privilege requested when opening a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlAccess {
+    /// No other application may read or write device registers.
+    Exclusive,
+    /// Other applications may read registers but not write them.
+    Control,
+    /// Like [`ControlAccess::Control`], but another application may take over.
+    ControlWithSwitchover,
+}
+
+impl ControlAccess {
+    /// Value written to the CCP register to request this privilege.
+    pub const fn ccp_bits(self) -> u32 {
+        match self {
+            ControlAccess::Exclusive => 0x1,
+            ControlAccess::Control => 0x2,
+            ControlAccess::ControlWithSwitchover => 0x6,
+        }
+    }
+
+    /// Whether the CCP value read back still grants exclusive or control access.
+    fn held(ccp: u32) -> bool {
+        ccp & 0x3 != 0
+    }
+}
+
+/// Control privilege status tracked by the heartbeat task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlState {
+    /// The device keeps acknowledging heartbeats with the privilege granted.
+    Held,
+    /// Control was lost; the message describes why.
+    Lost(String),
+}
+
+/// Background task keeping the control channel privilege alive.
+struct Heartbeat {
+    access: ControlAccess,
+    task: JoinHandle<()>,
+    interval: watch::Sender<Duration>,
+    state: watch::Receiver<ControlState>,
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn heartbeat_interval(timeout: Duration) -> Duration {
+    (timeout / 3).max(consts::HEARTBEAT_MIN_INTERVAL)
+}
+
+async fn run_heartbeat(
+    control: ControlHandle,
+    mut interval: watch::Receiver<Duration>,
+    state: watch::Sender<ControlState>,
+) {
+    loop {
+        let period = *interval.borrow_and_update();
+        tokio::select! {
+            _ = time::sleep(period) => {}
+            changed = interval.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+                continue;
+            }
+        }
+        let ccp = control.read_reg(consts::CONTROL_CHANNEL_PRIVILEGE).await;
+        let reason = match ccp {
+            Ok(ccp) if ControlAccess::held(ccp) => {
+                trace!(ccp, "heartbeat acknowledged");
+                continue;
+            }
+            Ok(ccp) => format!("device no longer grants control (CCP {ccp:#x})"),
+            Err(err) => format!("heartbeat failed: {err}"),
+        };
+        warn!(%reason, "lost control of device");
+        let _ = state.send(ControlState::Lost(reason));
+        return;
+    }
+}
+
 /// GVCP device handle.
+///
+/// Owns the control channel privilege, if any, and its heartbeat. Register
+/// access goes through a [`ControlHandle`] that can be cloned with
+/// [`GigeDevice::control`] and shared with other tasks.
 pub struct GigeDevice {
-    socket: UdpSocket,
-    remote: SocketAddr,
-    request_id: u16,
-    rng: Rng,
+    control: ControlHandle,
+    heartbeat: Option<Heartbeat>,
 }
 
 /// Stream negotiation outcome describing the values written to the device.
@@ -371,183 +670,162 @@ pub struct StreamParams {
 impl GigeDevice {
     /// Connect to a device GVCP endpoint.
     pub async fn open(addr: SocketAddr) -> Result<Self, GigeError> {
-        let local_ip = match addr.ip() {
-            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
-            IpAddr::V6(_) => {
-                return Err(GigeError::Protocol("IPv6 GVCP is not supported".into()));
+        Ok(Self::from_control(ControlHandle::connect(addr).await?))
+    }
+
+    /// Wrap a control channel already in use, e.g. to configure streaming on
+    /// a camera opened elsewhere. The new device holds no privilege of its own.
+    pub fn from_control(control: ControlHandle) -> Self {
+        Self {
+            control,
+            heartbeat: None,
+        }
+    }
+
+    /// Shared control channel used by this device.
+    pub fn control(&self) -> &ControlHandle {
+        &self.control
+    }
+
+    /// Connect to a device and acquire the requested control channel privilege.
+    ///
+    /// The heartbeat timeout is read from the device and a background task
+    /// reads the CCP register at a third of that period to keep the privilege
+    /// alive. Use [`GigeDevice::control_state`] to learn when control is lost.
+    pub async fn open_with_access(
+        addr: SocketAddr,
+        access: ControlAccess,
+    ) -> Result<Self, GigeError> {
+        let mut device = Self::open(addr).await?;
+        device
+            .write_regs(&[(consts::CONTROL_CHANNEL_PRIVILEGE, access.ccp_bits())])
+            .await?;
+        if let Err(err) = device.enable_pending_ack().await {
+            warn!(%addr, error = %err, "could not enable PENDING_ACK");
+        }
+        let timeout = match device.heartbeat_timeout().await {
+            Ok(timeout) => timeout,
+            Err(err) => {
+                // Give the privilege back rather than lock other applications
+                // out until the device heartbeat expires.
+                if let Err(release) = device
+                    .write_regs(&[(consts::CONTROL_CHANNEL_PRIVILEGE, 0)])
+                    .await
+                {
+                    warn!(%addr, error = %release, "failed to release control privilege");
+                }
+                return Err(err);
             }
         };
-        let socket = UdpSocket::bind(SocketAddr::new(local_ip, 0)).await?;
-        socket.connect(addr).await?;
-        Ok(Self {
-            socket,
-            remote: addr,
-            request_id: 1,
-            rng: Rng::new(),
-        })
+        info!(%addr, ?access, ?timeout, "acquired control channel privilege");
+
+        let (interval_tx, interval_rx) = watch::channel(heartbeat_interval(timeout));
+        let (state_tx, state_rx) = watch::channel(ControlState::Held);
+        let task = tokio::spawn(run_heartbeat(device.control.clone(), interval_rx, state_tx));
+        device.heartbeat = Some(Heartbeat {
+            access,
+            task,
+            interval: interval_tx,
+            state: state_rx,
+        });
+        Ok(device)
     }
 
-    /// Return the remote GVCP socket address associated with this device.
-    pub fn remote_addr(&self) -> SocketAddr {
-        self.remote
+    /// Privilege held since [`GigeDevice::open_with_access`], if any.
+    pub fn access(&self) -> Option<ControlAccess> {
+        self.heartbeat.as_ref().map(|heartbeat| heartbeat.access)
+    }
+
+    /// Watch the control privilege; `None` when no privilege was requested.
+    ///
+    /// The state switches to [`ControlState::Lost`] when a heartbeat fails or
+    /// the device no longer reports the privilege; heartbeats stop afterwards.
+    pub fn control_state(&self) -> Option<watch::Receiver<ControlState>> {
+        self.heartbeat
+            .as_ref()
+            .map(|heartbeat| heartbeat.state.clone())
     }
 
-    fn next_request_id(&mut self) -> u16 {
-        let id = self.request_id;
-        self.request_id = self.request_id.wrapping_add(1);
-        if self.request_id == 0 {
-            self.request_id = 1;
+    /// Let the device answer slow commands with `PENDING_ACK` if it can.
+    ///
+    /// Returns whether the capability is supported. Requires control access,
+    /// so [`GigeDevice::open_with_access`] calls it automatically.
+    pub async fn enable_pending_ack(&mut self) -> Result<bool, GigeError> {
+        let capability = self.control.read_reg(consts::GVCP_CAPABILITY).await?;
+        if capability & consts::CAPABILITY_PENDING_ACK == 0 {
+            debug!("device does not support PENDING_ACK");
+            return Ok(false);
         }
-        id
+        let config = self.control.read_reg(consts::GVCP_CONFIGURATION).await?;
+        self.write_regs(&[(
+            consts::GVCP_CONFIGURATION,
+            config | consts::CONFIGURATION_PENDING_ACK_ENABLE,
+        )])
+        .await?;
+        debug!("enabled PENDING_ACK");
+        Ok(true)
     }
 
-    async fn transact_with_retry(
-        &mut self,
-        opcode: OpCode,
-        payload: BytesMut,
-    ) -> Result<GenCpAck, GigeError> {
-        let mut attempt = 0usize;
-        let mut payload = payload;
-        loop {
-            attempt += 1;
-            let request_id = self.next_request_id();
-            let payload_bytes = payload.clone().freeze();
-            let cmd = GenCpCmd {
-                header: genicp::CommandHeader {
-                    flags: CommandFlags::ACK_REQUIRED,
-                    opcode,
-                    length: payload_bytes.len() as u16,
-                    request_id,
-                },
-                payload: payload_bytes.clone(),
-            };
-            let encoded = encode_cmd(&cmd);
-            trace!(request_id, opcode = ?opcode, bytes = encoded.len(), attempt, "sending GenCP command");
-            if let Err(err) = self.socket.send(&encoded).await {
-                if attempt >= consts::MAX_RETRIES {
-                    return Err(err.into());
-                }
-                warn!(request_id, ?opcode, attempt, "send failed, retrying");
-                self.backoff(attempt).await;
-                payload = BytesMut::from(&payload_bytes[..]);
-                continue;
-            }
+    /// Read the heartbeat timeout configured on the device.
+    pub async fn heartbeat_timeout(&mut self) -> Result<Duration, GigeError> {
+        let millis = self.control.read_reg(consts::HEARTBEAT_TIMEOUT).await?;
+        Ok(Duration::from_millis(millis as u64))
+    }
 
-            let mut buf =
-                vec![
-                    0u8;
-                    genicp::HEADER_SIZE + consts::GENCP_MAX_BLOCK + consts::GENCP_WRITE_OVERHEAD
-                ];
-            match time::timeout(consts::CONTROL_TIMEOUT, self.socket.recv(&mut buf)).await {
-                Ok(Ok(len)) => {
-                    trace!(request_id, bytes = len, attempt, "received GenCP ack");
-                    let ack = decode_ack(&buf[..len])?;
-                    if ack.header.request_id != request_id {
-                        debug!(
-                            request_id,
-                            got = ack.header.request_id,
-                            attempt,
-                            "acknowledgement id mismatch"
-                        );
-                        if attempt >= consts::MAX_RETRIES {
-                            return Err(GigeError::Protocol("acknowledgement id mismatch".into()));
-                        }
-                        self.backoff(attempt).await;
-                        payload = BytesMut::from(&payload_bytes[..]);
-                        continue;
-                    }
-                    if ack.header.opcode != opcode {
-                        return Err(GigeError::Protocol(
-                            "unexpected opcode in acknowledgement".into(),
-                        ));
-                    }
-                    match ack.header.status {
-                        StatusCode::Success => return Ok(ack),
-                        StatusCode::DeviceBusy if attempt < consts::MAX_RETRIES => {
-                            warn!(request_id, attempt, "device busy, retrying");
-                            self.backoff(attempt).await;
-                            payload = BytesMut::from(&payload_bytes[..]);
-                            continue;
-                        }
-                        other => return Err(GigeError::Status(other)),
-                    }
-                }
-                Ok(Err(err)) => {
-                    if attempt >= consts::MAX_RETRIES {
-                        return Err(err.into());
-                    }
-                    warn!(request_id, ?opcode, attempt, "receive error, retrying");
-                    self.backoff(attempt).await;
-                    payload = BytesMut::from(&payload_bytes[..]);
-                }
-                Err(_) => {
-                    if attempt >= consts::MAX_RETRIES {
-                        return Err(GigeError::Timeout);
-                    }
-                    warn!(request_id, ?opcode, attempt, "command timeout, retrying");
-                    self.backoff(attempt).await;
-                    payload = BytesMut::from(&payload_bytes[..]);
-                }
-            }
+    /// Change the device heartbeat timeout.
+    ///
+    /// A running heartbeat adapts its interval to the value the device reports
+    /// back, as devices may round or clamp the requested timeout.
+    pub async fn set_heartbeat_timeout(&mut self, timeout: Duration) -> Result<(), GigeError> {
+        let millis = u32::try_from(timeout.as_millis())
+            .map_err(|_| GigeError::Protocol("heartbeat timeout out of range".into()))?;
+        self.write_regs(&[(consts::HEARTBEAT_TIMEOUT, millis)])
+            .await?;
+        let actual = self.heartbeat_timeout().await?;
+        debug!(requested = ?timeout, ?actual, "configured heartbeat timeout");
+        if let Some(heartbeat) = &self.heartbeat {
+            let _ = heartbeat.interval.send(heartbeat_interval(actual));
         }
+        Ok(())
     }
 
-    async fn backoff(&mut self, attempt: usize) {
-        let multiplier = 1u32 << (attempt.saturating_sub(1)).min(3);
-        let base_ms = consts::RETRY_BASE_DELAY.as_millis() as u64;
-        let base = Duration::from_millis(base_ms.saturating_mul(multiplier as u64).max(base_ms));
-        let jitter_ms = self.rng.u64(..=consts::RETRY_JITTER.as_millis() as u64);
-        let jitter = Duration::from_millis(jitter_ms);
-        let delay = base + jitter;
-        debug!(attempt, delay = ?delay, "gvcp retry backoff");
-        time::sleep(delay).await;
+    /// Stop the heartbeat and give up the control channel privilege.
+    pub async fn release_control(&mut self) -> Result<(), GigeError> {
+        if self.heartbeat.take().is_none() {
+            return Ok(());
+        }
+        info!(remote = %self.remote_addr(), "releasing control channel privilege");
+        self.write_regs(&[(consts::CONTROL_CHANNEL_PRIVILEGE, 0)])
+            .await
+    }
+
+    /// Return the remote GVCP socket address associated with this device.
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.control.remote_addr()
     }
 
     /// Read a block of memory from the remote device with chunking and retries.
     pub async fn read_mem(&mut self, addr: u64, len: usize) -> Result<Vec<u8>, GigeError> {
-        let mut remaining = len;
-        let mut offset = 0usize;
-        let mut data = Vec::with_capacity(len);
-        while remaining > 0 {
-            let chunk = remaining.min(consts::GENCP_MAX_BLOCK);
-            let mut payload = BytesMut::with_capacity(12);
-            payload.put_u64(addr + offset as u64);
-            payload.put_u32(chunk as u32);
-            let ack = self.transact_with_retry(OpCode::ReadMem, payload).await?;
-            if ack.payload.len() != chunk {
-                return Err(GigeError::Protocol(format!(
-                    "expected {chunk} bytes but device returned {}",
-                    ack.payload.len()
-                )));
-            }
-            data.extend_from_slice(&ack.payload);
-            remaining -= chunk;
-            offset += chunk;
-        }
-        Ok(data)
+        self.control.read_mem(addr, len).await
     }
 
     /// Write a block of memory to the remote device with chunking and retries.
     pub async fn write_mem(&mut self, addr: u64, data: &[u8]) -> Result<(), GigeError> {
-        let mut offset = 0usize;
-        while offset < data.len() {
-            let chunk =
-                (data.len() - offset).min(consts::GENCP_MAX_BLOCK - consts::GENCP_WRITE_OVERHEAD);
-            if chunk == 0 {
-                return Err(GigeError::Protocol("write chunk size is zero".into()));
-            }
-            let mut payload = BytesMut::with_capacity(consts::GENCP_WRITE_OVERHEAD + chunk);
-            payload.put_u64(addr + offset as u64);
-            payload.extend_from_slice(&data[offset..offset + chunk]);
-            let ack = self.transact_with_retry(OpCode::WriteMem, payload).await?;
-            if !ack.payload.is_empty() {
-                return Err(GigeError::Protocol(
-                    "write acknowledgement carried unexpected payload".into(),
-                ));
-            }
-            offset += chunk;
-        }
-        Ok(())
+        self.control.write_mem(addr, data).await
+    }
+
+    /// Read 32-bit registers at scattered addresses using GVCP `READREG`.
+    ///
+    /// See [`ControlHandle::read_regs`].
+    pub async fn read_regs(&mut self, addrs: &[u64]) -> Result<Vec<u32>, GigeError> {
+        self.control.read_regs(addrs).await
+    }
+
+    /// Write 32-bit registers at scattered addresses using GVCP `WRITEREG`.
+    ///
+    /// See [`ControlHandle::write_regs`].
+    pub async fn write_regs(&mut self, regs: &[(u64, u32)]) -> Result<(), GigeError> {
+        self.control.write_regs(regs).await
     }
 
     /// Configure the message channel destination address/port.
@@ -674,62 +952,18 @@ impl GigeDevice {
         first_packet: u16,
         last_packet: u16,
     ) -> Result<(), GigeError> {
-        let mut payload = BytesMut::with_capacity(8);
-        payload.put_u16(block_id);
-        payload.put_u16(0); // Reserved as per spec.
-        payload.put_u16(first_packet);
-        payload.put_u16(last_packet);
-
-        let request_id = self.next_request_id();
-        let header = GvcpRequestHeader {
-            flags: CommandFlags::ACK_REQUIRED,
-            command: consts::PACKET_RESEND_COMMAND,
-            length: payload.len() as u16,
-            request_id,
-        };
-        let packet = header.encode(&payload);
-        trace!(
-            block_id,
-            first_packet,
-            last_packet,
-            request_id,
-            "sending packet resend request"
-        );
-        self.socket.send(&packet).await?;
-        let mut buf = [0u8; genicp::HEADER_SIZE];
-        match time::timeout(consts::CONTROL_TIMEOUT, self.socket.recv(&mut buf)).await {
-            Ok(Ok(len)) => {
-                if len != genicp::HEADER_SIZE {
-                    return Err(GigeError::Protocol("resend ack length mismatch".into()));
-                }
-                let mut cursor = &buf[..];
-                let status = StatusCode::from_raw(cursor.get_u16());
-                let command = cursor.get_u16();
-                let length = cursor.get_u16();
-                let ack_request_id = cursor.get_u16();
-                if command != consts::PACKET_RESEND_ACK {
-                    return Err(GigeError::Protocol("unexpected resend ack opcode".into()));
-                }
-                if length != 0 {
-                    return Err(GigeError::Protocol("resend ack carried payload".into()));
-                }
-                if ack_request_id != request_id {
-                    return Err(GigeError::Protocol("resend ack request id mismatch".into()));
-                }
-                if status != StatusCode::Success {
-                    return Err(GigeError::Status(status));
-                }
-                Ok(())
-            }
-            Ok(Err(err)) => Err(err.into()),
-            Err(_) => Err(GigeError::Timeout),
-        }
+        self.control
+            .request_resend(block_id, first_packet, last_packet)
+            .await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use genicp::OpCode;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
 
     #[test]
     fn request_header_roundtrip() {
@@ -763,4 +997,281 @@ mod tests {
         assert_eq!(converted.length, 12);
         assert_eq!(converted.request_id, 0x44);
     }
+
+    fn discovery_payload() -> Vec<u8> {
+        let mut payload = BytesMut::with_capacity(248);
+        payload.put_u16(2);
+        payload.put_u16(1);
+        payload.put_u32(0x8000_0001);
+        payload.put_u16(0);
+        payload.extend_from_slice(&[0x00, 0x30, 0x53, 0x11, 0x22, 0x33]);
+        payload.put_u32(0x8000_0007);
+        payload.put_u32(0x0000_0002);
+        for addr in [[192, 168, 5, 20], [255, 255, 255, 0], [192, 168, 5, 1]] {
+            payload.put_bytes(0, 12);
+            payload.extend_from_slice(&addr);
+        }
+        for (text, len) in [
+            ("Basler", 32),
+            ("acA1920-40gm", 32),
+            ("106755-18", 32),
+            ("", 48),
+            ("21345678", 16),
+            ("LeftCam", 16),
+        ] {
+            let mut field = vec![0u8; len];
+            field[..text.len()].copy_from_slice(text.as_bytes());
+            payload.extend_from_slice(&field);
+        }
+        payload.to_vec()
+    }
+
+    #[test]
+    fn discovery_payload_fields() {
+        let payload = discovery_payload();
+        assert_eq!(payload.len(), 248);
+        let info = parse_discovery_payload(&payload).expect("parse");
+        assert_eq!(info.spec_version, (2, 1));
+        assert_eq!(info.mac, [0x00, 0x30, 0x53, 0x11, 0x22, 0x33]);
+        assert_eq!(info.ip, Ipv4Addr::new(192, 168, 5, 20));
+        assert_eq!(info.subnet, Ipv4Addr::new(255, 255, 255, 0));
+        assert_eq!(info.gateway, Ipv4Addr::new(192, 168, 5, 1));
+        assert!(info.ip_config_options.persistent && info.ip_config_options.lla);
+        assert_eq!(info.ip_mode(), IpConfigMode::Dhcp);
+        assert_eq!(info.manufacturer.as_deref(), Some("Basler"));
+        assert_eq!(info.model.as_deref(), Some("acA1920-40gm"));
+        assert_eq!(info.device_version.as_deref(), Some("106755-18"));
+        assert_eq!(info.manufacturer_info, None);
+        assert_eq!(info.serial_number.as_deref(), Some("21345678"));
+        assert_eq!(info.user_name.as_deref(), Some("LeftCam"));
+
+        let short = parse_discovery_payload(&payload[..136]).expect("parse short ack");
+        assert_eq!(short.serial_number, None);
+        assert!(same_subnet(
+            info.ip,
+            Ipv4Addr::new(192, 168, 5, 2),
+            Ipv4Addr::new(255, 255, 255, 0)
+        ));
+        assert!(!same_subnet(
+            info.ip,
+            Ipv4Addr::new(10, 0, 0, 2),
+            Ipv4Addr::new(255, 0, 0, 0)
+        ));
+    }
+
+    #[tokio::test]
+    async fn write_regs_reports_failing_index_across_batches() {
+        let device = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = device.local_addr().unwrap();
+        let regs: Vec<(u64, u32)> = (0..consts::WRITEREG_MAX_COUNT as u64 + 3)
+            .map(|idx| (0x1000 + idx * 4, idx as u32))
+            .collect();
+        let server = tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let mut commands = Vec::new();
+            for status in [StatusCode::Success, StatusCode::InvalidAddress] {
+                let (len, peer) = device.recv_from(&mut buf).await.unwrap();
+                let mut header = &buf[..len];
+                let _flags = header.get_u16();
+                let command = header.get_u16();
+                let length = header.get_u16();
+                let request_id = header.get_u16();
+                commands.push((command, length as usize / 8));
+                let mut ack = BytesMut::new();
+                ack.put_u16(status.to_raw());
+                ack.put_u16(OpCode::WriteReg.ack_code());
+                ack.put_u16(4);
+                ack.put_u16(request_id);
+                ack.put_u16(0);
+                ack.put_u16(if status == StatusCode::Success { 67 } else { 1 });
+                device.send_to(&ack, peer).await.unwrap();
+            }
+            commands
+        });
+
+        let mut gige = GigeDevice::open(addr).await.unwrap();
+        let err = gige.write_regs(&regs).await.unwrap_err();
+        assert!(matches!(
+            err,
+            GigeError::RegisterAccess {
+                index,
+                status: StatusCode::InvalidAddress,
+            } if index == consts::WRITEREG_MAX_COUNT + 1
+        ));
+        let commands = server.await.unwrap();
+        assert_eq!(
+            commands,
+            vec![(0x0082, consts::WRITEREG_MAX_COUNT), (0x0082, 3),]
+        );
+    }
+
+    /// Serve GVCP commands on loopback, answering each through `handler`.
+    async fn fake_device<F>(mut handler: F) -> SocketAddr
+    where
+        F: FnMut(u16, &[u8]) -> (StatusCode, Vec<u8>) + Send + 'static,
+    {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            while let Ok((len, peer)) = socket.recv_from(&mut buf).await {
+                let mut header = &buf[..len];
+                let _flags = header.get_u16();
+                let command = header.get_u16();
+                let _length = header.get_u16();
+                let request_id = header.get_u16();
+                let (status, payload) = handler(command, header);
+                let mut ack = BytesMut::new();
+                ack.put_u16(status.to_raw());
+                ack.put_u16(command + 1);
+                ack.put_u16(payload.len() as u16);
+                ack.put_u16(request_id);
+                ack.extend_from_slice(&payload);
+                if socket.send_to(&ack, peer).await.is_err() {
+                    break;
+                }
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn unicast_and_subnet_discovery_over_loopback() {
+        let addr = fake_device(|command, _| {
+            assert_eq!(command, consts::DISCOVERY_COMMAND);
+            (StatusCode::Success, discovery_payload())
+        })
+        .await;
+
+        let device = discover_unicast_at(addr, Duration::from_secs(1))
+            .await
+            .expect("unicast discovery")
+            .expect("device answered");
+        assert_eq!(device.ip, Ipv4Addr::new(192, 168, 5, 20));
+        assert_eq!(device.serial_number.as_deref(), Some("21345678"));
+
+        let devices = discover_subnet_at(addr, Duration::from_millis(200))
+            .await
+            .expect("subnet discovery");
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].mac, [0x00, 0x30, 0x53, 0x11, 0x22, 0x33]);
+    }
+
+    #[tokio::test]
+    async fn heartbeat_reports_lost_privilege() {
+        let mut ccp = 0u32;
+        let mut heartbeats = 0;
+        let addr = fake_device(move |command, mut payload| match command {
+            0x0082 => {
+                let _addr = payload.get_u32();
+                ccp = payload.get_u32();
+                (StatusCode::Success, vec![0, 0, 0, 1])
+            }
+            _ => {
+                let value = match payload.get_u32() as u64 {
+                    consts::HEARTBEAT_TIMEOUT => 300,
+                    consts::GVCP_CAPABILITY => 0,
+                    _ => {
+                        heartbeats += 1;
+                        if heartbeats > 2 {
+                            ccp = 0;
+                        }
+                        ccp
+                    }
+                };
+                (StatusCode::Success, value.to_be_bytes().to_vec())
+            }
+        })
+        .await;
+
+        let device = GigeDevice::open_with_access(addr, ControlAccess::Control)
+            .await
+            .unwrap();
+        assert_eq!(device.access(), Some(ControlAccess::Control));
+        let mut state = device.control_state().unwrap();
+        assert_eq!(*state.borrow(), ControlState::Held);
+        time::timeout(Duration::from_secs(2), state.changed())
+            .await
+            .expect("heartbeat notices the loss")
+            .unwrap();
+        assert!(matches!(&*state.borrow(), ControlState::Lost(reason) if reason.contains("CCP")));
+    }
+
+    #[tokio::test]
+    async fn failed_open_releases_privilege() {
+        let ccp = Arc::new(AtomicU32::new(0));
+        let seen = Arc::clone(&ccp);
+        let addr = fake_device(move |command, mut payload| match command {
+            0x0082 => {
+                let _addr = payload.get_u32();
+                seen.store(payload.get_u32(), Ordering::SeqCst);
+                (StatusCode::Success, vec![0, 0, 0, 1])
+            }
+            _ => match payload.get_u32() as u64 {
+                consts::HEARTBEAT_TIMEOUT => (StatusCode::InvalidAddress, Vec::new()),
+                _ => (StatusCode::Success, vec![0; 4]),
+            },
+        })
+        .await;
+
+        assert!(GigeDevice::open_with_access(addr, ControlAccess::Control)
+            .await
+            .is_err());
+        assert_eq!(ccp.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn pending_ack_extends_wait_without_resending() {
+        let device = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = device.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, peer) = device.recv_from(&mut buf).await.unwrap();
+            let request_id = u16::from_be_bytes([buf[6], buf[7]]);
+            let mut pending = BytesMut::new();
+            pending.put_u16(0);
+            pending.put_u16(genicp::PENDING_ACK);
+            pending.put_u16(4);
+            pending.put_u16(request_id);
+            pending.put_u16(0);
+            pending.put_u16(1_000);
+            device.send_to(&pending, peer).await.unwrap();
+
+            time::sleep(consts::CONTROL_TIMEOUT + Duration::from_millis(200)).await;
+            let mut ack = BytesMut::new();
+            ack.put_u16(0);
+            ack.put_u16(OpCode::ReadReg.ack_code());
+            ack.put_u16(4);
+            ack.put_u16(request_id);
+            ack.put_u32(0xCAFE);
+            device.send_to(&ack, peer).await.unwrap();
+
+            let resent = time::timeout(Duration::from_millis(50), device.recv_from(&mut buf)).await;
+            assert!(resent.is_err(), "command must not be resent");
+        });
+
+        let mut gige = GigeDevice::open(addr).await.unwrap();
+        assert_eq!(gige.read_regs(&[0x0A00]).await.unwrap(), vec![0xCAFE]);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn cloned_handles_share_one_channel() {
+        let addr = fake_device(|_, mut payload| {
+            let addr = payload.get_u32();
+            (StatusCode::Success, (addr * 2).to_be_bytes().to_vec())
+        })
+        .await;
+
+        let device = GigeDevice::open(addr).await.unwrap();
+        let tasks: Vec<_> = (1..=8u64)
+            .map(|idx| {
+                let control = device.control().clone();
+                tokio::spawn(async move { control.read_regs(&[idx * 4]).await })
+            })
+            .collect();
+        for (idx, task) in (1..=8u32).zip(tasks) {
+            assert_eq!(task.await.unwrap().unwrap(), vec![idx * 8]);
+        }
+    }
 }
@@ -0,0 +1,358 @@
+//! Device IP configuration: FORCEIP and the persistent IP bootstrap registers.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use bytes::{BufMut, BytesMut};
+use genicp::{CommandFlags, StatusCode};
+use tokio::net::UdpSocket;
+use tokio::task::JoinSet;
+use tokio::time;
+use tracing::{debug, info, trace, warn};
+
+use crate::gvcp::{self, GigeDevice, GigeError, GvcpRequestHeader, GVCP_PORT};
+
+/// FORCEIP opcodes and IP configuration register addresses.
+pub mod consts {
+    use std::time::Duration;
+
+    /// Opcode of the FORCEIP command.
+    pub const FORCEIP_COMMAND: u16 = 0x0004;
+    /// Opcode of the FORCEIP acknowledgement.
+    pub const FORCEIP_ACK: u16 = 0x0005;
+    /// Size of the FORCEIP payload in bytes.
+    pub const FORCEIP_PAYLOAD: usize = 56;
+    /// Time allowed for the device to apply the address and acknowledge.
+    pub const FORCEIP_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// Network interface configuration register of interface 0.
+    pub const NETWORK_INTERFACE_CONFIGURATION: u64 = 0x0014;
+    /// Current IP address of interface 0.
+    pub const CURRENT_IP_ADDRESS: u64 = 0x0024;
+    /// Current subnet mask of interface 0.
+    pub const CURRENT_SUBNET_MASK: u64 = 0x0034;
+    /// Current default gateway of interface 0.
+    pub const CURRENT_DEFAULT_GATEWAY: u64 = 0x0044;
+    /// Persistent IP address of interface 0.
+    pub const PERSISTENT_IP_ADDRESS: u64 = 0x064C;
+    /// Persistent subnet mask of interface 0.
+    pub const PERSISTENT_SUBNET_MASK: u64 = 0x065C;
+    /// Persistent default gateway of interface 0.
+    pub const PERSISTENT_DEFAULT_GATEWAY: u64 = 0x066C;
+
+    /// Configuration bit enabling the persistent IP address.
+    pub const CONFIG_PERSISTENT: u32 = 0x1;
+    /// Configuration bit enabling DHCP.
+    pub const CONFIG_DHCP: u32 = 0x2;
+    /// Configuration bit enabling link-local addressing.
+    pub const CONFIG_LLA: u32 = 0x4;
+}
+
+/// Address, subnet mask and default gateway of a device network interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpSettings {
+    /// IPv4 address.
+    pub ip: Ipv4Addr,
+    /// Subnet mask.
+    pub subnet: Ipv4Addr,
+    /// Default gateway; unspecified when none is configured.
+    pub gateway: Ipv4Addr,
+}
+
+/// Address assignment methods enabled on a device network interface.
+///
+/// At boot the device tries persistent IP, then DHCP, then link-local
+/// addressing. The GigE Vision specification requires LLA to stay enabled,
+/// so devices may ignore attempts to clear it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpConfig {
+    /// Use the persistent IP address.
+    pub persistent: bool,
+    /// Request an address over DHCP.
+    pub dhcp: bool,
+    /// Fall back to a link-local address.
+    pub lla: bool,
+}
+
+/// Method that assigned a device its current address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpConfigMode {
+    /// Persistent (static) IP address.
+    Persistent,
+    /// Address leased over DHCP.
+    Dhcp,
+    /// Link-local address (169.254.0.0/16).
+    LinkLocal,
+    /// No method reported, e.g. after FORCEIP.
+    Unknown,
+}
+
+impl IpConfig {
+    /// Method in use when these bits describe the current configuration.
+    pub fn mode(self) -> IpConfigMode {
+        if self.persistent {
+            IpConfigMode::Persistent
+        } else if self.dhcp {
+            IpConfigMode::Dhcp
+        } else if self.lla {
+            IpConfigMode::LinkLocal
+        } else {
+            IpConfigMode::Unknown
+        }
+    }
+
+    /// Decode the configuration bits of a network interface register.
+    pub fn from_bits(bits: u32) -> Self {
+        Self {
+            persistent: bits & consts::CONFIG_PERSISTENT != 0,
+            dhcp: bits & consts::CONFIG_DHCP != 0,
+            lla: bits & consts::CONFIG_LLA != 0,
+        }
+    }
+
+    /// Apply these settings to `bits`, preserving unrelated bits.
+    pub fn apply(self, bits: u32) -> u32 {
+        let mask = consts::CONFIG_PERSISTENT | consts::CONFIG_DHCP | consts::CONFIG_LLA;
+        let mut value = bits & !mask;
+        if self.persistent {
+            value |= consts::CONFIG_PERSISTENT;
+        }
+        if self.dhcp {
+            value |= consts::CONFIG_DHCP;
+        }
+        if self.lla {
+            value |= consts::CONFIG_LLA;
+        }
+        value
+    }
+}
+
+impl GigeDevice {
+    /// Read the address assignment methods enabled on interface 0.
+    pub async fn ip_config(&mut self) -> Result<IpConfig, GigeError> {
+        let bits = self
+            .read_regs(&[consts::NETWORK_INTERFACE_CONFIGURATION])
+            .await?;
+        Ok(IpConfig::from_bits(bits[0]))
+    }
+
+    /// Enable or disable address assignment methods on interface 0.
+    ///
+    /// Takes effect at the next device reset or link change.
+    pub async fn set_ip_config(&mut self, config: IpConfig) -> Result<(), GigeError> {
+        let current = self
+            .read_regs(&[consts::NETWORK_INTERFACE_CONFIGURATION])
+            .await?;
+        let value = config.apply(current[0]);
+        info!(?config, "writing IP configuration");
+        self.write_regs(&[(consts::NETWORK_INTERFACE_CONFIGURATION, value)])
+            .await
+    }
+
+    /// Read the address currently used by interface 0.
+    pub async fn current_ip(&mut self) -> Result<IpSettings, GigeError> {
+        self.read_ip_settings([
+            consts::CURRENT_IP_ADDRESS,
+            consts::CURRENT_SUBNET_MASK,
+            consts::CURRENT_DEFAULT_GATEWAY,
+        ])
+        .await
+    }
+
+    /// Read the persistent address of interface 0.
+    pub async fn persistent_ip(&mut self) -> Result<IpSettings, GigeError> {
+        self.read_ip_settings([
+            consts::PERSISTENT_IP_ADDRESS,
+            consts::PERSISTENT_SUBNET_MASK,
+            consts::PERSISTENT_DEFAULT_GATEWAY,
+        ])
+        .await
+    }
+
+    /// Store the persistent address of interface 0.
+    ///
+    /// The address is only used once persistent IP is enabled with
+    /// [`GigeDevice::set_ip_config`] and the device restarts.
+    pub async fn set_persistent_ip(&mut self, settings: IpSettings) -> Result<(), GigeError> {
+        info!(ip = %settings.ip, subnet = %settings.subnet, gateway = %settings.gateway, "writing persistent IP");
+        self.write_regs(&[
+            (consts::PERSISTENT_IP_ADDRESS, u32::from(settings.ip)),
+            (consts::PERSISTENT_SUBNET_MASK, u32::from(settings.subnet)),
+            (
+                consts::PERSISTENT_DEFAULT_GATEWAY,
+                u32::from(settings.gateway),
+            ),
+        ])
+        .await
+    }
+
+    async fn read_ip_settings(&mut self, addrs: [u64; 3]) -> Result<IpSettings, GigeError> {
+        let values = self.read_regs(&addrs).await?;
+        Ok(IpSettings {
+            ip: Ipv4Addr::from(values[0]),
+            subnet: Ipv4Addr::from(values[1]),
+            gateway: Ipv4Addr::from(values[2]),
+        })
+    }
+}
+
+fn encode_force_ip(mac: [u8; 6], ip: Ipv4Addr, subnet: Ipv4Addr, gateway: Ipv4Addr) -> BytesMut {
+    let mut buf = BytesMut::with_capacity(consts::FORCEIP_PAYLOAD);
+    buf.put_u16(0); // reserved
+    buf.extend_from_slice(&mac);
+    // Each address occupies the last four bytes of a 16-byte field.
+    for addr in [ip, subnet, gateway] {
+        buf.put_bytes(0, 12);
+        buf.put_u32(u32::from(addr));
+    }
+    buf
+}
+
+/// Assign a temporary address to the device with `mac` using a FORCEIP broadcast.
+///
+/// Works regardless of the device's current subnet, which makes it the way
+/// to recover cameras with a wrong static IP. The address is lost at the next
+/// device reset; use [`GigeDevice::set_persistent_ip`] to keep it.
+pub async fn force_ip(
+    mac: [u8; 6],
+    ip: Ipv4Addr,
+    subnet: Ipv4Addr,
+    gateway: Ipv4Addr,
+) -> Result<(), GigeError> {
+    force_ip_filtered(None, mac, ip, subnet, gateway).await
+}
+
+/// Like [`force_ip`], broadcasting only on the interface named `interface`.
+pub async fn force_ip_on_interface(
+    interface: &str,
+    mac: [u8; 6],
+    ip: Ipv4Addr,
+    subnet: Ipv4Addr,
+    gateway: Ipv4Addr,
+) -> Result<(), GigeError> {
+    force_ip_filtered(Some(interface), mac, ip, subnet, gateway).await
+}
+
+async fn force_ip_filtered(
+    iface_filter: Option<&str>,
+    mac: [u8; 6],
+    ip: Ipv4Addr,
+    subnet: Ipv4Addr,
+    gateway: Ipv4Addr,
+) -> Result<(), GigeError> {
+    let interfaces = gvcp::broadcast_interfaces(iface_filter)?;
+    if interfaces.is_empty() {
+        return Err(GigeError::Protocol(
+            "no IPv4 interface available for FORCEIP".into(),
+        ));
+    }
+
+    let payload = encode_force_ip(mac, ip, subnet, gateway).freeze();
+    let mut join_set = JoinSet::new();
+    for (idx, (name, v4)) in interfaces.into_iter().enumerate() {
+        let request_id = 0x0200u16.wrapping_add(idx as u16);
+        let payload = payload.clone();
+        join_set.spawn(async move {
+            let socket = UdpSocket::bind(SocketAddr::new(IpAddr::V4(v4.ip), 0)).await?;
+            socket.set_broadcast(true)?;
+            let destination = SocketAddr::new(IpAddr::V4(Ipv4Addr::BROADCAST), GVCP_PORT);
+            let header = GvcpRequestHeader {
+                flags: CommandFlags::ACK_REQUIRED | CommandFlags::BROADCAST,
+                command: consts::FORCEIP_COMMAND,
+                length: payload.len() as u16,
+                request_id,
+            };
+            info!(interface = %name, local = %v4.ip, %ip, "sending FORCEIP");
+            socket
+                .send_to(&header.encode(&payload), destination)
+                .await?;
+            wait_force_ip_ack(&socket, request_id).await
+        });
+    }
+
+    let mut outcome = Err(GigeError::Timeout);
+    while let Some(res) = join_set.join_next().await {
+        match res.map_err(|e| GigeError::Protocol(format!("FORCEIP task failed: {e}")))? {
+            Ok(Some(StatusCode::Success)) => {
+                info!(%ip, "device acknowledged FORCEIP");
+                return Ok(());
+            }
+            Ok(Some(status)) => outcome = Err(GigeError::Status(status)),
+            Ok(None) => {}
+            Err(err) => {
+                warn!(error = %err, "FORCEIP failed on interface");
+                if matches!(outcome, Err(GigeError::Timeout)) {
+                    outcome = Err(err);
+                }
+            }
+        }
+    }
+    outcome
+}
+
+/// Wait for the FORCEIP acknowledgement, returning its status if one arrives.
+async fn wait_force_ip_ack(
+    socket: &UdpSocket,
+    request_id: u16,
+) -> Result<Option<StatusCode>, GigeError> {
+    let mut buf = [0u8; 64];
+    let deadline = time::Instant::now() + consts::FORCEIP_TIMEOUT;
+    loop {
+        let (len, src) = match time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+            Ok(received) => received?,
+            Err(_) => return Ok(None),
+        };
+        trace!(%src, bytes = len, "received FORCEIP response");
+        if len < genicp::HEADER_SIZE {
+            continue;
+        }
+        let command = u16::from_be_bytes([buf[2], buf[3]]);
+        let ack_id = u16::from_be_bytes([buf[6], buf[7]]);
+        if command != consts::FORCEIP_ACK || ack_id != request_id {
+            debug!(command, ack_id, "ignoring unrelated acknowledgement");
+            continue;
+        }
+        return Ok(Some(StatusCode::from_raw(u16::from_be_bytes([
+            buf[0], buf[1],
+        ]))));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn force_ip_payload_layout() {
+        let payload = encode_force_ip(
+            [0x00, 0x30, 0x53, 0x01, 0x02, 0x03],
+            Ipv4Addr::new(192, 168, 1, 50),
+            Ipv4Addr::new(255, 255, 255, 0),
+            Ipv4Addr::new(192, 168, 1, 1),
+        );
+        assert_eq!(payload.len(), consts::FORCEIP_PAYLOAD);
+        assert_eq!(&payload[..8], &[0, 0, 0x00, 0x30, 0x53, 0x01, 0x02, 0x03]);
+        assert_eq!(&payload[8..20], &[0u8; 12]);
+        assert_eq!(&payload[20..24], &[192, 168, 1, 50]);
+        assert_eq!(&payload[36..40], &[255, 255, 255, 0]);
+        assert_eq!(&payload[52..56], &[192, 168, 1, 1]);
+    }
+
+    #[test]
+    fn ip_config_bits_round_trip() {
+        let config = IpConfig::from_bits(0x8000_0006);
+        assert_eq!(
+            config,
+            IpConfig {
+                persistent: false,
+                dhcp: true,
+                lla: true,
+            }
+        );
+        let updated = IpConfig {
+            persistent: true,
+            dhcp: false,
+            ..config
+        };
+        assert_eq!(updated.apply(0x8000_0006), 0x8000_0005);
+    }
+}
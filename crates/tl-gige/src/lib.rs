@@ -2,11 +2,20 @@
 //! GigE Vision TL: discovery (GVCP), control (GenCP/GVCP), streaming (GVSP).
 
 pub mod action;
+pub mod control;
+pub mod discovery;
 pub mod gvcp;
 pub mod gvsp;
+pub mod ipconfig;
 pub mod message;
 pub mod nic;
 pub mod stats;
 pub mod time;
 
-pub use gvcp::{discover, discover_on_interface, DeviceInfo, GigeDevice, GigeError, GVCP_PORT};
+pub use control::ControlHandle;
+pub use discovery::{DeviceSelector, DiscoveryEvent, DiscoveryTarget, DiscoveryWatcher};
+pub use gvcp::{
+    discover, discover_on_interface, discover_subnet, discover_unicast, ControlAccess,
+    ControlState, DeviceInfo, GigeDevice, GigeError, GVCP_PORT,
+};
+pub use ipconfig::force_ip;
@@ -187,6 +187,17 @@ impl ControlChannel for tokio::sync::Mutex<crate::gvcp::GigeDevice> {
     }
 }
 
+#[async_trait]
+impl ControlChannel for crate::control::ControlHandle {
+    async fn read_register(&self, addr: u64, len: usize) -> Result<Vec<u8>, TimeError> {
+        self.read_mem(addr, len).await.map_err(TimeError::from)
+    }
+
+    async fn write_register(&self, addr: u64, data: &[u8]) -> Result<(), TimeError> {
+        self.write_mem(addr, data).await.map_err(TimeError::from)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;